@@ -0,0 +1,217 @@
+use arrow::datatypes::{DataType, Field};
+use arrow::error::ArrowError;
+use arrow::ipc::reader::FileReader;
+use arrow_tools::batch::Manifest;
+use arrow_tools::cli::{run_alias, run_convert, run_schema};
+use arrow_tools::error::{exit_code, Error, ErrorKind};
+use arrow_tools::formats::InputFormat;
+use arrow_tools::writer::OutputFormat;
+use clap::{Parser, Subcommand, ValueEnum, ValueHint};
+use parquet::arrow::parquet_to_arrow_schema;
+use parquet::file::reader::{FileReader as _, SerializedFileReader};
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[clap(version = env!("CARGO_PKG_VERSION"), author = "Dominik Moritz <domoritz@cmu.edu>")]
+#[clap(about = "Convert files of many formats to Arrow and Parquet, and inspect the results.")]
+#[clap(
+    after_help = "The converters are also available as <format>2arrow and <format>2parquet, e.g. when this binary is linked as csv2parquet."
+)]
+struct Opts {
+    #[clap(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Convert a file to Arrow or Parquet. The formats are picked from the file extensions unless --from or --to are given. See "convert --from <FORMAT> --help" for the options of a format.
+    #[clap(disable_help_flag = true)]
+    Convert {
+        #[clap(num_args = 0.., trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<OsString>,
+    },
+    /// Print the inferred Arrow schema of a file. Takes the same input options as convert. "schema diff" compares it to a reference schema, see "schema diff --help".
+    #[clap(disable_help_flag = true)]
+    Schema {
+        #[clap(num_args = 0.., trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<OsString>,
+    },
+    /// Run the conversions listed in a TOML or JSON manifest in parallel.
+    Batch {
+        /// Manifest with a jobs array of input and output files and their options.
+        #[clap(name = "MANIFEST", value_parser, value_hint = ValueHint::FilePath)]
+        manifest: PathBuf,
+
+        /// The number of conversions that run at once. The workers of the manifest, or the number of cores, if not present.
+        #[clap(short = 'j', long)]
+        workers: Option<usize>,
+    },
+    /// Show the schema, size and layout of an Arrow or Parquet file.
+    Inspect {
+        /// Arrow IPC or Parquet file.
+        #[clap(name = "FILE", value_parser, value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+    },
+    /// List the input formats and their file extensions.
+    Formats,
+}
+
+fn main() -> ExitCode {
+    exit_code(run())
+}
+
+fn run() -> Result<(), Error> {
+    if let Some((input, output)) = alias() {
+        return run_alias(input, output);
+    }
+
+    let opts: Opts = Opts::parse();
+
+    match opts.command {
+        Commands::Convert { args } => run_convert(args),
+        Commands::Schema { args } => run_schema(args),
+        Commands::Batch { manifest, workers } => batch(&manifest, workers),
+        Commands::Inspect { file } => {
+            inspect(&file).map_err(|error| Error::new(ErrorKind::Input, error))
+        }
+        Commands::Formats => {
+            for format in InputFormat::ALL {
+                println!("{:<10}{}", format.name(), format.extensions().join(", "));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// The converter this binary runs as when it is called `<format>2arrow` or `<format>2parquet`.
+fn alias() -> Option<(InputFormat, OutputFormat)> {
+    let program = std::env::args_os().next()?;
+    let name = Path::new(&program).file_stem()?.to_str()?;
+    let (input, output) = name.split_once('2')?;
+    Some((
+        InputFormat::from_str(input, true).ok()?,
+        OutputFormat::from_str(output, true).ok()?,
+    ))
+}
+
+fn batch(manifest: &Path, workers: Option<usize>) -> Result<(), Error> {
+    let manifest = Manifest::from_file(manifest).map_err(Error::usage)?;
+    let summary = manifest.run(workers, |job, result| match result {
+        Ok(rows) => eprintln!("{:?} -> {:?}: {rows} rows", job.input, job.output),
+        Err(error) => eprintln!("{:?} -> {:?}: {error}", job.input, job.output),
+    });
+
+    eprintln!(
+        "Converted {} of {} files, {} failed",
+        summary.succeeded(),
+        summary.results.len(),
+        summary.failed()
+    );
+    if summary.failed() > 0 {
+        return Err(Error::check(format!(
+            "{} conversions failed",
+            summary.failed()
+        )));
+    }
+    Ok(())
+}
+
+/// The Arrow field of a Parquet leaf column, from its root field and the rest of its path.
+/// The groups of lists and maps that Arrow has no field for are passed over.
+fn leaf_field<'a>(root: &'a Field, path: &[String]) -> Option<&'a Field> {
+    let mut field = root;
+    for part in path {
+        field = match field.data_type() {
+            DataType::Struct(fields) => fields.iter().find(|field| field.name() == part)?,
+            DataType::List(child)
+            | DataType::LargeList(child)
+            | DataType::FixedSizeList(child, _)
+            | DataType::Map(child, _) => match child.name() == part {
+                true => child,
+                false => field,
+            },
+            _ => return None,
+        };
+    }
+    Some(field)
+}
+
+fn inspect(path: &Path) -> Result<(), ArrowError> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+
+    let mut magic = Vec::with_capacity(6);
+    file.by_ref().take(6).read_to_end(&mut magic)?;
+    file.rewind()?;
+
+    if magic.starts_with(b"PAR1") {
+        let reader = SerializedFileReader::new(file)?;
+        let metadata = reader.metadata();
+        let file_metadata = metadata.file_metadata();
+        let schema = parquet_to_arrow_schema(
+            file_metadata.schema_descr(),
+            file_metadata.key_value_metadata(),
+        )?;
+
+        println!("Format: Parquet");
+        println!("Rows: {}", file_metadata.num_rows());
+        println!("Row groups: {}", metadata.num_row_groups());
+        println!("Size: {size} bytes");
+        if let Some(created_by) = file_metadata.created_by() {
+            println!("Created by: {created_by}");
+        }
+        println!("Columns:");
+        let schema_descr = file_metadata.schema_descr();
+        for (index, column) in schema_descr.columns().iter().enumerate() {
+            let chunks = metadata
+                .row_groups()
+                .iter()
+                .map(|row_group| row_group.column(index));
+            let compressed: i64 = chunks.clone().map(|chunk| chunk.compressed_size()).sum();
+            let uncompressed: i64 = chunks.clone().map(|chunk| chunk.uncompressed_size()).sum();
+            let compression = chunks
+                .map(|chunk| format!("{:?}", chunk.compression()))
+                .next()
+                .unwrap_or_default();
+            let compression = compression.split('(').next().unwrap_or_default();
+            let root = schema.field(schema_descr.get_column_root_idx(index));
+            let data_type = leaf_field(root, &column.path().parts()[1..])
+                .map(|field| field.data_type().to_string())
+                .unwrap_or_else(|| column.physical_type().to_string());
+            println!(
+                "  {}: {data_type}, {compression}, {compressed} bytes compressed, {uncompressed} bytes uncompressed",
+                column.path().string()
+            );
+        }
+    } else if magic == b"ARROW1" {
+        let reader = FileReader::try_new(file, None)?;
+        let schema = reader.schema();
+        let batches = reader.num_batches();
+        let mut rows = 0;
+        for batch in reader {
+            rows += batch?.num_rows();
+        }
+
+        println!("Format: Arrow");
+        println!("Rows: {rows}");
+        println!("Record batches: {batches}");
+        println!("Size: {size} bytes");
+        println!("Columns:");
+        for field in schema.fields() {
+            println!("  {}: {}", field.name(), field.data_type());
+        }
+    } else {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "{path:?} is neither an Arrow nor a Parquet file"
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test;
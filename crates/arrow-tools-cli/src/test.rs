@@ -0,0 +1,335 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+#[allow(deprecated)]
+fn schema_from_extension() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+
+    let assert = cmd.arg("schema").arg("../../data/simple.csv.gz").assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#""name": "a",
+      "data_type": "Int64""#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn schema_with_format_options() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+
+    let assert = cmd
+        .arg("schema")
+        .arg("--layout")
+        .arg("../../data/simple.layout")
+        .arg("../../data/simple.fwf")
+        .assert();
+
+    assert
+        .success()
+        .stdout(predicate::str::contains(r#""fields""#));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn convert_with_from() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+
+    let assert = cmd
+        .arg("convert")
+        .arg("--from")
+        .arg("json")
+        .arg("../../data/simple.csv")
+        .arg("-n")
+        .assert();
+
+    assert
+        .failure()
+        .stderr(predicate::str::contains("Error loading schema"));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn convert_and_inspect() -> Result<(), Box<dyn std::error::Error>> {
+    let output = std::env::temp_dir().join("arrow-tools-simple.parquet");
+
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+    let assert = cmd
+        .arg("convert")
+        .arg("../../data/simple.csv")
+        .arg(&output)
+        .arg("--compression")
+        .arg("zstd")
+        .assert();
+    assert.success();
+
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+    let assert = cmd.arg("inspect").arg(&output).assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("Format: Parquet"))
+        .stdout(predicate::str::contains("Rows: 3"))
+        .stdout(predicate::str::contains("  a: Int64, ZSTD"));
+
+    // Nested columns have the type of their leaf.
+    let input = std::env::temp_dir().join("arrow-tools-nested.json");
+    let output = std::env::temp_dir().join("arrow-tools-nested.parquet");
+    std::fs::write(&input, "{\"s\": {\"a\": \"x\"}, \"a\": 1, \"l\": [1.5]}\n")?;
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+    cmd.arg("convert")
+        .arg(&input)
+        .arg(&output)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+    let assert = cmd.arg("inspect").arg(&output).assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("  s.a: Utf8, "))
+        .stdout(predicate::str::contains("  a: Int64, "))
+        .stdout(predicate::str::contains("  l.list.item: Float64, "));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn schema_diff() -> Result<(), Box<dyn std::error::Error>> {
+    let reference = std::env::temp_dir().join("arrow-tools-schema-diff.parquet");
+    let input = std::env::temp_dir().join("arrow-tools-schema-diff.csv");
+    std::fs::write(&input, "b,a,c\ntrue,x,1\n")?;
+
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+    let assert = cmd
+        .arg("convert")
+        .arg("../../data/simple.csv")
+        .arg(&reference)
+        .assert();
+    assert.success();
+
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+    let assert = cmd
+        .arg("schema")
+        .arg("diff")
+        .arg(&input)
+        .arg("--reference")
+        .arg(&reference)
+        .assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("~ a: Int64 -> Utf8"))
+        .stdout(predicate::str::contains("~ b: moved from position 1 to 0"))
+        .stdout(predicate::str::contains("+ c: Int64"))
+        .stderr(predicate::str::contains("4 changes, 1 of them breaking"));
+
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+    let assert = cmd
+        .arg("schema")
+        .arg("diff")
+        .arg(&input)
+        .arg("--reference")
+        .arg(&reference)
+        .arg("--strict")
+        .assert();
+    assert.code(1);
+
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+    let assert = cmd
+        .arg("schema")
+        .arg("diff")
+        .arg(&reference)
+        .arg("--reference")
+        .arg(&reference)
+        .arg("--strict")
+        .assert();
+    assert
+        .success()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("The schema matches the reference"));
+
+    // Inferred fields are always nullable, so their nullability isn't compared.
+    let schema = std::env::temp_dir().join("arrow-tools-schema-diff.json");
+    std::fs::write(
+        &schema,
+        r#"{"fields":[{"name":"a","data_type":"Int64","nullable":false,"dict_id":0,"dict_is_ordered":false,"metadata":{}},{"name":"b","data_type":"Boolean","nullable":false,"dict_id":0,"dict_is_ordered":false,"metadata":{}}],"metadata":{}}"#,
+    )?;
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+    let assert = cmd
+        .arg("schema")
+        .arg("diff")
+        .arg("../../data/simple.csv")
+        .arg("--schema-file")
+        .arg(&schema)
+        .arg("--strict")
+        .assert();
+    assert
+        .success()
+        .stderr(predicate::str::contains("The schema matches the reference"));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn convert_to_arrow() -> Result<(), Box<dyn std::error::Error>> {
+    let output = std::env::temp_dir().join("arrow-tools-simple.out");
+
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+    let assert = cmd
+        .arg("convert")
+        .arg("../../data/simple.yaml")
+        .arg("--to")
+        .arg("arrow")
+        .arg(&output)
+        .assert();
+    assert.success();
+
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+    let assert = cmd.arg("inspect").arg(&output).assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("Format: Arrow"));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn batch() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir();
+    let data = std::fs::canonicalize("../../data")?;
+    let manifest = dir.join("arrow-tools-batch.toml");
+    std::fs::write(
+        &manifest,
+        format!(
+            r#"
+compression = "zstd"
+
+[[jobs]]
+input = {csv:?}
+output = {parquet:?}
+
+[[jobs]]
+input = {yaml:?}
+output = {arrow:?}
+to = "arrow"
+
+[[jobs]]
+input = {missing:?}
+output = {parquet:?}
+"#,
+            csv = data.join("simple.csv"),
+            yaml = data.join("simple.yaml"),
+            missing = data.join("missing.csv"),
+            parquet = dir.join("arrow-tools-batch.parquet"),
+            arrow = dir.join("arrow-tools-batch.out"),
+        ),
+    )?;
+
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+    let assert = cmd.arg("batch").arg(&manifest).arg("-j").arg("2").assert();
+    assert
+        .code(1)
+        .stderr(predicate::str::contains("simple.csv\" -> "))
+        .stderr(predicate::str::contains(": 3 rows"))
+        .stderr(predicate::str::contains("Converted 2 of 3 files, 1 failed"))
+        .stderr(predicate::str::contains("Error: 1 conversions failed"));
+
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+    let assert = cmd
+        .arg("inspect")
+        .arg(dir.join("arrow-tools-batch.parquet"))
+        .assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains("  a: Int64, ZSTD"));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn batch_json() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir();
+    let data = std::fs::canonicalize("../../data")?;
+    let manifest = dir.join("arrow-tools-batch.json");
+    std::fs::write(
+        &manifest,
+        format!(
+            r#"{{"jobs": [{{"input": {:?}, "output": {:?}, "max-read-records": 1}}]}}"#,
+            data.join("simple.json"),
+            dir.join("arrow-tools-batch-json.parquet"),
+        ),
+    )?;
+
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+    let assert = cmd.arg("batch").arg(&manifest).assert();
+    assert
+        .success()
+        .stderr(predicate::str::contains("Converted 1 of 1 files, 0 failed"));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn unknown_format() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+
+    let assert = cmd.arg("convert").arg("../../data/simple.desc").assert();
+
+    assert.code(2).stderr(predicate::str::contains(
+        "Could not detect the format of \"../../data/simple.desc\", use --from",
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn convert_help() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+
+    let assert = cmd
+        .arg("convert")
+        .arg("--from")
+        .arg("csv")
+        .arg("--help")
+        .assert();
+
+    assert
+        .success()
+        .stdout(predicate::str::contains(
+            "Usage: arrow-tools convert [OPTIONS] <CSV> [OUTPUT]",
+        ))
+        .stdout(predicate::str::contains("--delimiter"));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn help() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("arrow-tools")?;
+
+    let assert = cmd.arg("--help").assert();
+
+    assert
+        .success()
+        .stdout(predicate::str::contains(if cfg!(windows) {
+            "Usage: arrow-tools.exe <COMMAND>"
+        } else {
+            "Usage: arrow-tools <COMMAND>"
+        }));
+
+    Ok(())
+}
@@ -0,0 +1,212 @@
+//! Running many conversions from a manifest.
+//!
+//! A manifest is a TOML or JSON file with a `jobs` array. Each job needs an `input` and an
+//! `output` and can set `from`, `to`, `profile` and any option of `arrow-tools convert` by
+//! its long name. Options at the top level apply to all jobs, and tables named after an
+//! input format only to jobs of that format. `workers` sets how many jobs run at once.
+//! Paths are relative to the current directory.
+//!
+//! ```toml
+//! workers = 4
+//! compression = "zstd"
+//!
+//! [csv]
+//! delimiter = ";"
+//!
+//! [[jobs]]
+//! input = "trips.csv"
+//! output = "trips.parquet"
+//!
+//! [[jobs]]
+//! input = "events.json"
+//! output = "events.arrow"
+//! max-read-records = 100
+//! ```
+
+use crate::cli::{convert, unified_conversion, Mode, ParseError};
+use crate::config::Config;
+use crate::error::Error;
+use arrow::error::ArrowError;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use toml::{Table, Value};
+
+/// Keys of a job that are not options.
+const JOB_KEYS: [&str; 5] = ["input", "output", "from", "to", "profile"];
+
+/// A conversion of a manifest.
+#[derive(Clone, Debug)]
+pub struct Job {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    /// The arguments of `arrow-tools convert` other than the options.
+    args: Vec<OsString>,
+    options: Config,
+}
+
+/// A parsed manifest.
+#[derive(Clone, Debug)]
+pub struct Manifest {
+    pub jobs: Vec<Job>,
+    /// The number of jobs that run at once, if the manifest sets it.
+    pub workers: Option<usize>,
+    defaults: Config,
+}
+
+impl Manifest {
+    /// Reads a manifest, as JSON if the file name ends in `.json` and as TOML otherwise.
+    pub fn from_file(path: &Path) -> Result<Self, ArrowError> {
+        let text = std::fs::read_to_string(path).map_err(|error| {
+            ArrowError::IoError(
+                format!("Error opening manifest: {path:?}, message: {error}"),
+                error,
+            )
+        })?;
+        let table = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str::<Table>(&text).map_err(|error| error.to_string()),
+            _ => text.parse::<Table>().map_err(|error| error.to_string()),
+        }
+        .map_err(|error| {
+            ArrowError::ParseError(format!("Error reading manifest {path:?}: {error}"))
+        })?;
+        Self::from_table(path, table)
+    }
+
+    fn from_table(path: &Path, mut table: Table) -> Result<Self, ArrowError> {
+        let invalid = |message: String| {
+            ArrowError::InvalidArgumentError(format!("Invalid manifest {path:?}: {message}"))
+        };
+
+        let workers = match table.remove("workers") {
+            Some(Value::Integer(workers)) if workers > 0 => Some(workers as usize),
+            Some(value) => {
+                return Err(invalid(format!(
+                    "workers must be a positive integer, got {value}"
+                )))
+            }
+            None => None,
+        };
+        let jobs = match table.remove("jobs") {
+            Some(Value::Array(jobs)) => jobs,
+            _ => return Err(invalid("expected a jobs array".to_string())),
+        };
+
+        let jobs = jobs
+            .into_iter()
+            .enumerate()
+            .map(|(index, job)| {
+                let Value::Table(mut job) = job else {
+                    return Err(invalid(format!("job {} is not a table", index + 1)));
+                };
+                let mut string = |key: &str| match job.remove(key) {
+                    Some(Value::String(value)) => Ok(Some(value)),
+                    Some(value) => Err(invalid(format!(
+                        "{key} of job {} must be a string, got {value}",
+                        index + 1
+                    ))),
+                    None => Ok(None),
+                };
+                let [input, output, from, to, profile] = JOB_KEYS.map(&mut string);
+                let (Some(input), Some(output)) = (input?, output?) else {
+                    return Err(invalid(format!(
+                        "job {} needs an input and an output",
+                        index + 1
+                    )));
+                };
+
+                let mut args = vec![OsString::from(&input), OsString::from(&output)];
+                for (long, value) in [("from", from?), ("to", to?), ("profile", profile?)] {
+                    if let Some(value) = value {
+                        args.push(format!("--{long}={value}").into());
+                    }
+                }
+                Ok(Job {
+                    input: input.into(),
+                    output: output.into(),
+                    args,
+                    options: Config::from_table(path, job),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Manifest {
+            jobs,
+            workers,
+            defaults: Config::from_table(path, table),
+        })
+    }
+
+    /// Runs the jobs on `workers` threads, or as many as the manifest sets or there are
+    /// cores, and calls `done` as each job finishes.
+    pub fn run<F>(&self, workers: Option<usize>, done: F) -> BatchSummary
+    where
+        F: Fn(&Job, &Result<usize, Error>) + Sync,
+    {
+        let workers = workers
+            .or(self.workers)
+            .unwrap_or_else(|| {
+                std::thread::available_parallelism()
+                    .map(usize::from)
+                    .unwrap_or(1)
+            })
+            .clamp(1, self.jobs.len().max(1));
+
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::new());
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(job) = self.jobs.get(index) else {
+                        break;
+                    };
+                    let result = self.run_job(job);
+                    done(job, &result);
+                    results.lock().unwrap().push((index, result));
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|&(index, _)| index);
+        BatchSummary {
+            results: results.into_iter().map(|(_, result)| result).collect(),
+        }
+    }
+
+    /// Runs one job and returns the number of rows it wrote.
+    fn run_job(&self, job: &Job) -> Result<usize, Error> {
+        let extra = [self.defaults.clone(), job.options.clone()];
+        let conversion =
+            unified_conversion(Mode::Convert, &job.args, &extra).map_err(|error| match error {
+                ParseError::Clap(error) => {
+                    let message = error.to_string();
+                    let message = message.lines().next().unwrap_or_default();
+                    Error::usage(ArrowError::InvalidArgumentError(
+                        message.trim_start_matches("error: ").to_string(),
+                    ))
+                }
+                ParseError::Arrow(error) => Error::usage(error),
+            })?;
+        Ok(convert(conversion)?.map_or(0, |summary| summary.rows))
+    }
+}
+
+/// The outcome of the jobs of a manifest.
+#[derive(Debug)]
+pub struct BatchSummary {
+    /// The rows written by each job, in the order of the manifest.
+    pub results: Vec<Result<usize, Error>>,
+}
+
+impl BatchSummary {
+    pub fn succeeded(&self) -> usize {
+        self.results.iter().filter(|result| result.is_ok()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.succeeded()
+    }
+}
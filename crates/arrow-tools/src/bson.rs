@@ -0,0 +1,408 @@
+//! Reading concatenated BSON documents, e.g. from `mongodump`, as JSON values.
+//!
+//! Values are converted to JSON so the schema can be inferred with the same rules as the
+//! JSON reader. Inference also records where ObjectIds, dates, Decimal128 values and binary
+//! data appear so that these columns get their own Arrow types instead of strings.
+
+use crate::values::{self, float, hex, timestamp};
+use arrow::datatypes::{DataType, Field, FieldRef, Fields, Schema, TimeUnit};
+use arrow::error::ArrowError;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::sync::Arc;
+
+/// The Arrow type of ObjectId columns.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ObjectIdType {
+    /// The 12 bytes of the ObjectId.
+    #[default]
+    FixedSizeBinary,
+    /// The 24 character hex string that MongoDB shows.
+    String,
+}
+
+/// BSON types that don't map onto a JSON type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Special {
+    ObjectId,
+    DateTime,
+    Decimal(i32),
+    Binary,
+}
+
+impl Special {
+    fn data_type(&self, object_id: ObjectIdType) -> DataType {
+        match (self, object_id) {
+            (Special::ObjectId, ObjectIdType::FixedSizeBinary) => DataType::FixedSizeBinary(12),
+            (Special::ObjectId, ObjectIdType::String) => DataType::Utf8,
+            (Special::DateTime, _) => {
+                DataType::Timestamp(TimeUnit::Millisecond, Some("+00:00".into()))
+            }
+            (Special::Decimal(scale), _) => DataType::Decimal128(38, (*scale).clamp(0, 38) as i8),
+            (Special::Binary, _) => DataType::Binary,
+        }
+    }
+
+    fn merge(self, other: Special) -> Option<Special> {
+        match (self, other) {
+            (Special::Decimal(a), Special::Decimal(b)) => Some(Special::Decimal(a.max(b))),
+            (a, b) if a == b => Some(a),
+            _ => None,
+        }
+    }
+}
+
+/// A decoded BSON value.
+enum Bson {
+    Double(f64),
+    String(String),
+    Document(Vec<(String, Bson)>),
+    Array(Vec<Bson>),
+    Binary(Vec<u8>),
+    ObjectId([u8; 12]),
+    Boolean(bool),
+    DateTime(i64),
+    Null,
+    Regex(String, String),
+    Int32(i32),
+    Timestamp { time: u32, increment: u32 },
+    Int64(i64),
+    Decimal128([u8; 16]),
+}
+
+/// Decodes the elements of a BSON document.
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], String> {
+        let end = self.position.checked_add(length);
+        let bytes = end.and_then(|end| self.bytes.get(self.position..end));
+        let bytes = bytes.ok_or_else(|| "unexpected end of document".to_string())?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn length(&mut self) -> Result<usize, String> {
+        let length = i32::from_le_bytes(self.array()?);
+        usize::try_from(length).map_err(|_| format!("invalid length {length}"))
+    }
+
+    fn cstring(&mut self) -> Result<String, String> {
+        let rest = &self.bytes[self.position.min(self.bytes.len())..];
+        let end = rest.iter().position(|byte| *byte == 0);
+        let end = end.ok_or_else(|| "unterminated string".to_string())?;
+        let string = String::from_utf8_lossy(self.take(end)?).into_owned();
+        self.position += 1;
+        Ok(string)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let length = self.length()?;
+        let bytes = self.take(length)?;
+        Ok(String::from_utf8_lossy(bytes.strip_suffix(&[0]).unwrap_or(bytes)).into_owned())
+    }
+
+    fn document(&mut self) -> Result<Vec<(String, Bson)>, String> {
+        let start = self.position;
+        let length = self.length()?;
+        let end = start + length;
+        let mut elements = Vec::new();
+        loop {
+            let element_type = self.array::<1>()?[0];
+            if element_type == 0 {
+                break;
+            }
+            let name = self.cstring()?;
+            elements.push((name, self.value(element_type)?));
+        }
+        if self.position != end {
+            return Err(format!(
+                "document length {length} does not match its content"
+            ));
+        }
+        Ok(elements)
+    }
+
+    fn value(&mut self, element_type: u8) -> Result<Bson, String> {
+        Ok(match element_type {
+            0x01 => Bson::Double(f64::from_le_bytes(self.array()?)),
+            0x02 | 0x0d | 0x0e => Bson::String(self.string()?),
+            0x03 => Bson::Document(self.document()?),
+            0x04 => Bson::Array(self.document()?.into_iter().map(|(_, v)| v).collect()),
+            0x05 => {
+                let length = self.length()?;
+                let subtype = self.array::<1>()?[0];
+                let mut bytes = self.take(length)?;
+                // the old binary subtype repeats the length
+                if subtype == 0x02 && bytes.len() >= 4 {
+                    bytes = &bytes[4..];
+                }
+                Bson::Binary(bytes.to_vec())
+            }
+            0x06 | 0x0a | 0x7f | 0xff => Bson::Null,
+            0x07 => Bson::ObjectId(self.array()?),
+            0x08 => Bson::Boolean(self.array::<1>()?[0] != 0),
+            0x09 => Bson::DateTime(i64::from_le_bytes(self.array()?)),
+            0x0b => Bson::Regex(self.cstring()?, self.cstring()?),
+            0x0c => {
+                self.string()?;
+                self.take(12)?;
+                Bson::Null
+            }
+            0x0f => {
+                self.length()?;
+                let code = self.string()?;
+                self.document()?;
+                Bson::String(code)
+            }
+            0x10 => Bson::Int32(i32::from_le_bytes(self.array()?)),
+            0x11 => {
+                let value = u64::from_le_bytes(self.array()?);
+                Bson::Timestamp {
+                    time: (value >> 32) as u32,
+                    increment: value as u32,
+                }
+            }
+            0x12 => Bson::Int64(i64::from_le_bytes(self.array()?)),
+            0x13 => Bson::Decimal128(self.array()?),
+            other => return Err(format!("unknown element type 0x{other:02x}")),
+        })
+    }
+}
+
+/// Iterates over a stream of concatenated BSON documents.
+///
+/// ObjectIds and binary data become hex strings, dates become RFC 3339 strings and
+/// Decimal128 values become decimal strings. Other BSON types become the closest JSON value.
+pub struct BsonDocuments<R> {
+    reader: R,
+    offset: u64,
+    buf: Vec<u8>,
+}
+
+impl<R: BufRead> BsonDocuments<R> {
+    pub fn new(reader: R) -> Self {
+        BsonDocuments {
+            reader,
+            offset: 0,
+            buf: Vec::new(),
+        }
+    }
+
+    fn next_document(&mut self) -> Result<Option<Vec<(String, Bson)>>, ArrowError> {
+        if self.reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        let offset = self.offset;
+        let error = |error: &dyn std::fmt::Display| {
+            ArrowError::ParseError(format!(
+                "Error reading BSON document at byte {offset}: {error}"
+            ))
+        };
+        let mut length = [0; 4];
+        self.reader.read_exact(&mut length).map_err(|e| error(&e))?;
+        let length = i32::from_le_bytes(length);
+        if length < 5 {
+            return Err(error(&format!("invalid length {length}")));
+        }
+        self.buf.clear();
+        self.buf.extend_from_slice(&length.to_le_bytes());
+        self.buf.resize(length as usize, 0);
+        self.reader
+            .read_exact(&mut self.buf[4..])
+            .map_err(|e| error(&e))?;
+        let mut decoder = Decoder {
+            bytes: &self.buf,
+            position: 0,
+        };
+        let document = decoder.document().map_err(|e| error(&e))?;
+        self.offset += length as u64;
+        Ok(Some(document))
+    }
+}
+
+impl<R: BufRead> Iterator for BsonDocuments<R> {
+    type Item = Result<Value, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_document()
+            .map(|document| document.map(document_to_json))
+            .transpose()
+    }
+}
+
+/// Infers the schema from the first `max_read_records` documents, or all documents if
+/// `None`.
+pub fn infer_schema<R: BufRead>(
+    reader: R,
+    max_read_records: Option<usize>,
+    object_id: ObjectIdType,
+) -> Result<Schema, ArrowError> {
+    let mut documents = BsonDocuments::new(reader);
+    let mut specials = HashMap::new();
+    let values = std::iter::from_fn(|| documents.next_document().transpose()).map(|document| {
+        document.map(|document| {
+            for (key, value) in &document {
+                observe(value, key.clone(), &mut specials);
+            }
+            document_to_json(document)
+        })
+    });
+    let schema = values::infer_schema(values, max_read_records)?;
+    let fields = refine_fields(schema.fields(), "", &specials, object_id);
+    Ok(Schema::new_with_metadata(fields, schema.metadata().clone()))
+}
+
+/// Records the special type of every path, or `None` if a path has other values too.
+fn observe(value: &Bson, path: String, specials: &mut HashMap<String, Option<Special>>) {
+    let special = match value {
+        Bson::Null => return,
+        Bson::Document(document) => {
+            for (key, value) in document {
+                observe(value, format!("{path}.{key}"), specials);
+            }
+            return;
+        }
+        Bson::Array(values) => {
+            for value in values {
+                observe(value, format!("{path}[]"), specials);
+            }
+            return;
+        }
+        Bson::ObjectId(_) => Some(Special::ObjectId),
+        Bson::DateTime(_) => Some(Special::DateTime),
+        Bson::Decimal128(bytes) => match decimal_parts(bytes) {
+            Some((_, scale)) => Some(Special::Decimal(scale)),
+            None => return,
+        },
+        Bson::Binary(_) => Some(Special::Binary),
+        _ => None,
+    };
+    specials
+        .entry(path)
+        .and_modify(|existing| *existing = existing.zip(special).and_then(|(a, b)| a.merge(b)))
+        .or_insert(special);
+}
+
+/// Replaces string types with the special types recorded for their path.
+fn refine_fields(
+    fields: &Fields,
+    path: &str,
+    specials: &HashMap<String, Option<Special>>,
+    object_id: ObjectIdType,
+) -> Fields {
+    fields
+        .iter()
+        .map(|field| {
+            let path = match path {
+                "" => field.name().clone(),
+                _ => format!("{path}.{}", field.name()),
+            };
+            refine_field(field, &path, specials, object_id)
+        })
+        .collect()
+}
+
+fn refine_field(
+    field: &FieldRef,
+    path: &str,
+    specials: &HashMap<String, Option<Special>>,
+    object_id: ObjectIdType,
+) -> FieldRef {
+    let data_type = match field.data_type() {
+        DataType::Utf8 => match specials.get(path) {
+            Some(Some(special)) => special.data_type(object_id),
+            _ => return field.clone(),
+        },
+        DataType::Struct(fields) => {
+            DataType::Struct(refine_fields(fields, path, specials, object_id))
+        }
+        DataType::List(item) => DataType::List(refine_field(
+            item,
+            &format!("{path}[]"),
+            specials,
+            object_id,
+        )),
+        _ => return field.clone(),
+    };
+    Arc::new(Field::clone(field).with_data_type(data_type))
+}
+
+fn document_to_json(document: Vec<(String, Bson)>) -> Value {
+    Value::Object(
+        document
+            .into_iter()
+            .map(|(key, value)| (key, to_json(value)))
+            .collect::<Map<_, _>>(),
+    )
+}
+
+fn to_json(value: Bson) -> Value {
+    match value {
+        Bson::Double(value) => float(value),
+        Bson::String(value) => Value::String(value),
+        Bson::Document(document) => document_to_json(document),
+        Bson::Array(values) => Value::Array(values.into_iter().map(to_json).collect()),
+        Bson::Binary(bytes) => hex(&bytes),
+        Bson::ObjectId(bytes) => hex(&bytes),
+        Bson::Boolean(value) => Value::Bool(value),
+        Bson::DateTime(millis) => timestamp(
+            millis.div_euclid(1_000),
+            (millis.rem_euclid(1_000) * 1_000_000) as u32,
+        ),
+        Bson::Null => Value::Null,
+        Bson::Regex(pattern, options) => Value::String(format!("/{pattern}/{options}")),
+        Bson::Int32(value) => Value::from(value),
+        Bson::Timestamp { time, increment } => {
+            let mut object = Map::new();
+            object.insert("t".to_string(), Value::from(time));
+            object.insert("i".to_string(), Value::from(increment));
+            Value::Object(object)
+        }
+        Bson::Int64(value) => Value::from(value),
+        Bson::Decimal128(bytes) => match decimal_parts(&bytes) {
+            Some((text, _)) => Value::String(text),
+            None => Value::Null,
+        },
+    }
+}
+
+/// Formats a Decimal128 without an exponent and returns its scale, or `None` for infinity
+/// and NaN, which have no Arrow decimal representation.
+fn decimal_parts(bytes: &[u8; 16]) -> Option<(String, i32)> {
+    let bits = u128::from_le_bytes(*bytes);
+    let high = (bits >> 64) as u64;
+    if (high >> 59) & 0xf == 0xf {
+        return None;
+    }
+    let (exponent, coefficient) = if (high >> 61) & 0x3 == 0x3 {
+        // coefficients in this form exceed 34 digits and are zero by definition
+        (((high >> 47) & 0x3fff) as i32, 0)
+    } else {
+        (((high >> 49) & 0x3fff) as i32, bits & ((1 << 113) - 1))
+    };
+    let scale = 6176 - exponent;
+    let mut digits = coefficient.to_string();
+    if scale <= 0 {
+        digits.extend(std::iter::repeat_n('0', (-scale) as usize));
+    } else {
+        let scale = scale as usize;
+        if digits.len() <= scale {
+            digits.insert_str(0, &"0".repeat(scale - digits.len() + 1));
+        }
+        digits.insert(digits.len() - scale, '.');
+    }
+    if high >> 63 == 1 {
+        digits.insert(0, '-');
+    }
+    Some((digits, scale))
+}
@@ -0,0 +1,89 @@
+//! Reading concatenated CBOR documents as JSON values.
+
+use crate::values::{float, hex, timestamp};
+use arrow::error::ArrowError;
+use ciborium::value::Value as CborValue;
+use serde_json::{Map, Value};
+use std::io::BufRead;
+
+/// Tag of a date/time as seconds since the epoch.
+const EPOCH_TAG: u64 = 1;
+
+/// Iterates over a stream of concatenated CBOR documents.
+///
+/// Byte strings become hex strings, epoch times become RFC 3339 strings, other tags are
+/// dropped in favor of their content and map keys that aren't strings are converted to
+/// strings.
+pub struct CborValues<R> {
+    reader: R,
+    index: usize,
+}
+
+impl<R: BufRead> CborValues<R> {
+    pub fn new(reader: R) -> Self {
+        CborValues { reader, index: 0 }
+    }
+}
+
+impl<R: BufRead> Iterator for CborValues<R> {
+    type Item = Result<Value, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.reader.fill_buf() {
+            Ok([]) => return None,
+            Ok(_) => {}
+            Err(error) => return Some(Err(error.into())),
+        }
+        let value = ciborium::de::from_reader::<CborValue, _>(&mut self.reader);
+        let index = self.index;
+        self.index += 1;
+        Some(value.map(to_json).map_err(|error| {
+            ArrowError::ParseError(format!("Error reading CBOR document {index}: {error}"))
+        }))
+    }
+}
+
+fn to_json(value: CborValue) -> Value {
+    match value {
+        CborValue::Null => Value::Null,
+        CborValue::Bool(value) => Value::Bool(value),
+        CborValue::Integer(value) => {
+            let value = i128::from(value);
+            match (i64::try_from(value), u64::try_from(value)) {
+                (Ok(value), _) => Value::from(value),
+                (Err(_), Ok(value)) => Value::from(value),
+                _ => Value::String(value.to_string()),
+            }
+        }
+        CborValue::Float(value) => float(value),
+        CborValue::Text(value) => Value::String(value),
+        CborValue::Bytes(value) => hex(&value),
+        CborValue::Array(values) => Value::Array(values.into_iter().map(to_json).collect()),
+        CborValue::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key_string(key), to_json(value)))
+                .collect::<Map<_, _>>(),
+        ),
+        CborValue::Tag(EPOCH_TAG, value) => match *value {
+            CborValue::Integer(seconds) => match i64::try_from(i128::from(seconds)) {
+                Ok(seconds) => timestamp(seconds, 0),
+                Err(_) => Value::Null,
+            },
+            CborValue::Float(seconds) if seconds.is_finite() => {
+                let nanoseconds = (seconds.rem_euclid(1.0) * 1e9) as u32;
+                timestamp(seconds.floor() as i64, nanoseconds)
+            }
+            value => to_json(value),
+        },
+        CborValue::Tag(_, value) => to_json(*value),
+        _ => Value::Null,
+    }
+}
+
+fn key_string(key: CborValue) -> String {
+    match to_json(key) {
+        Value::String(key) => key,
+        key => key.to_string(),
+    }
+}
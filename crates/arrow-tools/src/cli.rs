@@ -0,0 +1,895 @@
+//! The command line interface of the converters, shared by the `<format>2arrow` and
+//! `<format>2parquet` binaries and the `convert`, `schema` and `schema diff` commands of
+//! `arrow-tools`.
+
+use crate::config::{self, find_config, Config, CONFIG_FILE};
+use crate::converter::{ConversionSummary, ConvertError, Converter, SchemaSource};
+use crate::error::Error;
+use crate::formats::{FormatOptions, InputFormat};
+use crate::progress::{Progress, ProgressFormat};
+use crate::report::ConversionReport;
+use crate::schema::{diff_schemas, read_file_schema, read_schema_file, SchemaChange};
+use crate::writer::{OutputFormat, ParquetOptions};
+use arrow::datatypes::Schema;
+use arrow::error::ArrowError;
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{
+    value_parser, Arg, ArgAction, ArgGroup, ArgMatches, Args, Command, FromArgMatches, ValueEnum,
+};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{stdout, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+const AUTHOR: &str = "Dominik Moritz <domoritz@cmu.edu>";
+
+/// What a command does with its input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Mode {
+    /// A converter binary with a fixed output format.
+    Alias(OutputFormat),
+    /// `arrow-tools convert`, which picks the output format from the output file.
+    Convert,
+    /// `arrow-tools schema`, which only prints the schema.
+    Schema,
+    /// `arrow-tools schema diff`, which compares the schema to a reference.
+    Diff,
+}
+
+impl Mode {
+    /// Whether the command writes an output file.
+    fn converts(&self) -> bool {
+        matches!(self, Mode::Alias(_) | Mode::Convert)
+    }
+}
+
+/// A conversion as given on the command line.
+#[derive(Clone, Debug)]
+pub struct Conversion {
+    pub input: PathBuf,
+    /// The output file, stdout if not present.
+    pub output: Option<PathBuf>,
+    pub output_format: OutputFormat,
+    pub options: FormatOptions,
+    pub parquet: ParquetOptions,
+    /// File with the Arrow schema in JSON format. The schema is inferred if not present.
+    pub schema_file: Option<PathBuf>,
+    /// Parquet or Arrow file whose schema is used.
+    pub schema_from: Option<PathBuf>,
+    /// Print the schema to stderr.
+    pub print_schema: bool,
+    /// Only print the schema.
+    pub dry: bool,
+    /// The memory in bytes that the conversion keeps to, unbounded if not present.
+    pub memory_limit: Option<usize>,
+    /// Show the progress on stderr.
+    pub progress: Option<ProgressFormat>,
+    /// Write a JSON report of the conversion to this file.
+    pub report: Option<PathBuf>,
+    /// Only check that every record can be read with the schema.
+    pub validate: bool,
+    /// Only compare the schema to a reference.
+    pub diff: Option<SchemaDiff>,
+}
+
+/// What `arrow-tools schema diff` compares the schema of the input to.
+#[derive(Clone, Debug)]
+pub struct SchemaDiff {
+    /// A schema file in JSON format, or a Parquet or Arrow file.
+    pub reference: PathBuf,
+    /// Fail if there are breaking changes.
+    pub strict: bool,
+}
+
+/// Reads the input, writes it to the output and prints the schema if asked to. Returns
+/// what was written, or `None` for a dry run or a validation.
+///
+/// Errors of the conversion have the position in the input where they happened.
+pub fn convert(conversion: Conversion) -> Result<Option<ConversionSummary>, Error> {
+    let start = Instant::now();
+    let input_size = std::fs::metadata(&conversion.input)
+        .ok()
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len());
+    let mut options = conversion.options.clone();
+    // The schema is inferred before validating, from the records that can be read.
+    if conversion.validate {
+        options.skip_errors();
+    }
+    let locate = |error: ConvertError| Error::from(error).locate(&conversion.input);
+    let mut converter = Converter::new(&conversion.input, options.clone())
+        .with_output_format(conversion.output_format)
+        .with_parquet_options(conversion.parquet);
+    if let Some(ref path) = conversion.schema_file {
+        converter = converter.with_schema_file(path);
+    }
+    if let Some(ref path) = conversion.schema_from {
+        converter = converter.with_schema_from(path);
+    }
+    if let Some(limit) = conversion.memory_limit {
+        converter = converter.with_memory_limit(limit);
+    }
+    let mut reporter = None;
+    if let Some(format) = conversion.progress {
+        let progress = Progress::new();
+        if let Some(size) = input_size {
+            progress.set_total_bytes(size);
+        }
+        reporter = Some(progress.report(format));
+        converter = converter.with_progress(progress);
+    }
+
+    let schema = converter.schema().map_err(locate)?;
+
+    if let Some(ref diff) = conversion.diff {
+        return diff_schema(&schema, diff, true).map(|()| None);
+    }
+
+    if conversion.print_schema || conversion.dry {
+        let json = serde_json::to_string_pretty(&schema).unwrap();
+        eprintln!("Schema:");
+        println!("{json}");
+        if conversion.dry {
+            return Ok(None);
+        }
+    }
+
+    if conversion.validate {
+        let path = conversion.input.clone();
+        let summary = converter
+            .validate(move |mut rejected| {
+                rejected.position.file = Some(path.clone());
+                eprintln!("{rejected}\n");
+            })
+            .map_err(locate)?;
+        drop(reporter);
+        let records = summary.rows + summary.invalid;
+        if summary.invalid > 0 {
+            return Err(Error::check(format!(
+                "{} of {records} records are invalid",
+                summary.invalid
+            )));
+        }
+        eprintln!("All {records} records are valid");
+        return Ok(None);
+    }
+
+    let output = match conversion.output {
+        Some(ref path) => {
+            let file = File::create(path).map_err(|error| write_error(error.into()))?;
+            Box::new(file) as Box<dyn Write + Send>
+        }
+        None => Box::new(stdout()) as Box<dyn Write + Send>,
+    };
+    let summary = converter.write(output).map_err(locate)?;
+    drop(reporter);
+
+    if summary.skipped > 0 {
+        match options.format() {
+            InputFormat::Log => eprintln!("Skipped {} lines that did not match", summary.skipped),
+            _ => eprintln!("Skipped {} records that could not be read", summary.skipped),
+        }
+    }
+    if summary.nulled > 0 {
+        eprintln!(
+            "Set values that could not be read to null in {} records",
+            summary.nulled
+        );
+    }
+    if summary.early_row_groups > 0 {
+        eprintln!(
+            "Wrote {} row groups early to stay within the memory limit",
+            summary.early_row_groups
+        );
+    }
+
+    if let Some(ref path) = conversion.report {
+        let report = ConversionReport {
+            input: conversion.input,
+            schema_source: match (conversion.schema_file, conversion.schema_from) {
+                (Some(path), _) => SchemaSource::File(path),
+                (None, Some(path)) => SchemaSource::Existing(path),
+                (None, None) => SchemaSource::Infer,
+            },
+            output: conversion.output,
+            output_format: conversion.output_format,
+            summary: summary.clone(),
+            elapsed: start.elapsed(),
+        };
+        report.write(path).map_err(write_error)?;
+    }
+
+    Ok(Some(summary))
+}
+
+/// Prints the changes from the reference schema to `schema`, and in strict mode fails if
+/// any of them are breaking. The fields of inferred schemas are always nullable, so their
+/// nullability is not compared.
+fn diff_schema(schema: &Schema, diff: &SchemaDiff, inferred: bool) -> Result<(), Error> {
+    let reference = match read_file_schema(&diff.reference) {
+        Ok(Some(reference)) => Ok(reference),
+        Ok(None) => read_schema_file(&diff.reference),
+        Err(error) => Err(error),
+    }
+    .map_err(|error| Error::new(crate::error::ErrorKind::Schema, error))?;
+
+    let mut changes = diff_schemas(&reference, schema);
+    if inferred {
+        changes.retain(|change| !matches!(change, SchemaChange::NullabilityChanged { .. }));
+    }
+    for change in &changes {
+        println!("{change}");
+    }
+    let breaking = changes.iter().filter(|change| change.is_breaking()).count();
+    match changes.len() {
+        0 => eprintln!("The schema matches the reference"),
+        count => eprintln!("{count} changes, {breaking} of them breaking"),
+    }
+    if diff.strict && breaking > 0 {
+        return Err(Error::new(
+            crate::error::ErrorKind::Check,
+            ArrowError::SchemaError(format!(
+                "{breaking} breaking changes from the reference schema"
+            )),
+        ));
+    }
+    Ok(())
+}
+
+fn write_error(error: ArrowError) -> Error {
+    Error::new(crate::error::ErrorKind::Write, error)
+}
+
+/// Runs the `<input>2<output>` converter with the arguments of the process.
+pub fn run_alias(input: InputFormat, output: OutputFormat) -> Result<(), Error> {
+    run_alias_from(input, output, std::env::args_os())
+}
+
+/// Runs the `<input>2<output>` converter with the given arguments, starting with the binary name.
+pub fn run_alias_from<I, T>(input: InputFormat, output: OutputFormat, args: I) -> Result<(), Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let mode = Mode::Alias(output);
+    let name = format!("{}2{}", input.name(), output.name());
+    let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+    let matches = parse(command(name, Some(input), mode), Some(input), &args, &[])
+        .map_err(Error::usage)?
+        .map_err(ParseError::Clap)?;
+    convert(conversion(input, mode, &matches)?)?;
+    Ok(())
+}
+
+/// Runs `arrow-tools convert` with the arguments after `convert`.
+pub fn run_convert<I, T>(args: I) -> Result<(), Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    run_unified(Mode::Convert, args)
+}
+
+/// Runs `arrow-tools schema` with the arguments after `schema`, or `arrow-tools schema diff`
+/// if they start with `diff`.
+pub fn run_schema<I, T>(args: I) -> Result<(), Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    let mut args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+    if args.first().is_some_and(|arg| arg == "diff") {
+        args.remove(0);
+        return run_schema_diff(args);
+    }
+    run_unified(Mode::Schema, args)
+}
+
+/// Runs `arrow-tools schema diff` with the arguments after `diff`.
+fn run_schema_diff(args: Vec<OsString>) -> Result<(), Error> {
+    // Parquet and Arrow files have a schema, so they take none of the options of a format.
+    let name = "arrow-tools schema diff";
+    let full: Vec<OsString> = std::iter::once(OsString::from(name))
+        .chain(args.iter().cloned())
+        .collect();
+    let cmd = command(name.to_string(), None, Mode::Diff);
+    if let Ok(Ok(matches)) = parse(cmd, None, &full, &[]) {
+        let input = matches.get_one::<PathBuf>("input").unwrap();
+        if let Ok(Some(schema)) = read_file_schema(input) {
+            return diff_schema(&schema, &schema_diff(&matches), false);
+        }
+    }
+    run_unified(Mode::Diff, args)
+}
+
+fn run_unified<I, T>(mode: Mode, args: I) -> Result<(), Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    convert(unified_conversion(mode, args, &[])?)?;
+    Ok(())
+}
+
+/// Why the arguments did not give a conversion.
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    /// The arguments are invalid, or help or the version were asked for.
+    Clap(clap::Error),
+    Arrow(ArrowError),
+}
+
+impl From<ArrowError> for ParseError {
+    fn from(error: ArrowError) -> Self {
+        ParseError::Arrow(error)
+    }
+}
+
+/// Errors of clap are kept as they are, for [`exit_code`](crate::error::exit_code) to show
+/// the help or the error the way clap does.
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Self {
+        match error {
+            ParseError::Clap(error) => Error::usage(ArrowError::ExternalError(Box::new(error))),
+            ParseError::Arrow(error) => Error::usage(error),
+        }
+    }
+}
+
+/// Parses the arguments of `arrow-tools convert` or `arrow-tools schema`, with the options
+/// of the `extra` configs taking precedence over the config file and each other in order.
+///
+/// The commands take the options of the input format, so the format is picked before
+/// parsing: from `--from`, or else from the extension of the input. As options can come
+/// before the input, each argument with a known extension is tried as the input until one
+/// parses.
+pub(crate) fn unified_conversion<I, T>(
+    mode: Mode,
+    args: I,
+    extra: &[Config],
+) -> Result<Conversion, ParseError>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    let name = match mode {
+        Mode::Schema => "arrow-tools schema",
+        Mode::Diff => "arrow-tools schema diff",
+        _ => "arrow-tools convert",
+    };
+    let args: Vec<OsString> = std::iter::once(OsString::from(name))
+        .chain(args.into_iter().map(Into::into))
+        .collect();
+    let parse = |format: Option<InputFormat>| {
+        parse(
+            command(name.to_string(), format, mode),
+            format,
+            &args,
+            extra,
+        )
+    };
+
+    if let Some(format) = from_argument(&args)? {
+        let matches = parse(Some(format))?.map_err(ParseError::Clap)?;
+        return conversion(format, mode, &matches);
+    }
+
+    let candidates: Vec<(&OsString, InputFormat)> = args[1..]
+        .iter()
+        .filter_map(|arg| InputFormat::from_path(Path::new(arg)).map(|format| (arg, format)))
+        .collect();
+
+    for &(arg, format) in &candidates {
+        match parse(Some(format))? {
+            Ok(matches) if matches.get_one::<PathBuf>("input") == Some(&PathBuf::from(arg)) => {
+                return conversion(format, mode, &matches);
+            }
+            Err(error)
+                if matches!(
+                    error.kind(),
+                    ErrorKind::DisplayHelp | ErrorKind::DisplayVersion
+                ) =>
+            {
+                return Err(ParseError::Clap(error))
+            }
+            _ => {}
+        }
+    }
+
+    let matches = match candidates.first() {
+        Some(&(_, format)) => parse(Some(format))?.map_err(ParseError::Clap)?,
+        None => match parse(None)? {
+            Ok(matches) => matches,
+            Err(error)
+                if matches!(
+                    error.kind(),
+                    ErrorKind::MissingRequiredArgument
+                        | ErrorKind::DisplayHelp
+                        | ErrorKind::DisplayVersion
+                ) =>
+            {
+                return Err(ParseError::Clap(error))
+            }
+            Err(_) => ArgMatches::default(),
+        },
+    };
+    let input = matches
+        .try_get_one::<PathBuf>("input")
+        .ok()
+        .flatten()
+        .map(|input| format!(" of {input:?}"))
+        .unwrap_or_default();
+    Err(ParseError::Arrow(ArrowError::InvalidArgumentError(
+        format!("Could not detect the format{input}, use --from"),
+    )))
+}
+
+/// Parses the arguments, with the options from the config files that are not given.
+fn parse(
+    cmd: Command,
+    format: Option<InputFormat>,
+    args: &[OsString],
+    extra: &[Config],
+) -> Result<clap::error::Result<ArgMatches>, ArrowError> {
+    let args = with_config(&cmd, format, args, extra)?;
+    Ok(cmd.try_get_matches_from(args))
+}
+
+/// Inserts the options from `--config`, or else `arrow-tools.toml`, and then those of the
+/// `extra` configs after the binary name. Options that are on the command line, or that conflict
+/// with one that is, are left out.
+fn with_config(
+    cmd: &Command,
+    format: Option<InputFormat>,
+    args: &[OsString],
+    extra: &[Config],
+) -> Result<Vec<OsString>, ArrowError> {
+    let mut cmd = cmd.clone().ignore_errors(true);
+    cmd.build();
+    // Help and version are shown without reading the config.
+    let Ok(matches) = cmd.clone().try_get_matches_from(args) else {
+        return Ok(args.to_vec());
+    };
+
+    let profile = matches.get_one::<String>("profile").map(String::as_str);
+    let path = match matches.get_one::<PathBuf>("config") {
+        Some(path) => Some(path.clone()),
+        None => match (find_config(), profile) {
+            (None, Some(profile)) => {
+                return Err(ArrowError::InvalidArgumentError(format!(
+                    "Profile \"{profile}\" needs a config file, use --config or add {CONFIG_FILE}"
+                )))
+            }
+            (path, _) => path,
+        },
+    };
+    let config = path.as_deref().map(Config::from_file).transpose()?;
+
+    let mut values = BTreeMap::new();
+    let configs = config.iter().map(|config| (config, profile));
+    for (config, profile) in configs.chain(extra.iter().map(|config| (config, None))) {
+        for (long, value) in config.options(format, profile)? {
+            values.insert(long, (value, config.path()));
+        }
+    }
+
+    let given =
+        |arg: &Arg| matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine);
+    let mut options = Vec::new();
+    for (long, (value, path)) in values {
+        let Some(arg) = cmd
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(long.as_str()))
+        else {
+            if known_options().contains(&long) {
+                continue;
+            }
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "Unknown option \"{long}\" in {path:?}"
+            )));
+        };
+        if long == "config" || long == "profile" {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "\"{long}\" can't be set in {path:?}"
+            )));
+        }
+        if given(arg) || cmd.get_arg_conflicts_with(arg).into_iter().any(given) {
+            continue;
+        }
+        options.extend(config::arguments(&long, arg.get_action(), &value)?);
+    }
+
+    Ok(args[..1]
+        .iter()
+        .cloned()
+        .chain(options.into_iter().map(OsString::from))
+        .chain(args[1..].iter().cloned())
+        .collect())
+}
+
+/// The long names of the options of all formats.
+fn known_options() -> Vec<String> {
+    InputFormat::ALL
+        .iter()
+        .flat_map(|&format| {
+            command(String::new(), Some(format), Mode::Convert)
+                .get_arguments()
+                .filter_map(|arg| arg.get_long().map(str::to_string))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// The value of `--from`, if given.
+fn from_argument(args: &[OsString]) -> Result<Option<InputFormat>, ArrowError> {
+    let mut args = args.iter().map(|arg| arg.to_string_lossy());
+    while let Some(arg) = args.next() {
+        let value = match arg.strip_prefix("--from") {
+            Some("") => args.next(),
+            Some(value) if value.starts_with('=') => Some(value[1..].to_string().into()),
+            _ => continue,
+        };
+        return match value {
+            Some(value) => InputFormat::from_str(&value, true).map(Some).map_err(|_| {
+                ArrowError::InvalidArgumentError(format!(
+                    "Unknown format \"{value}\", see arrow-tools formats"
+                ))
+            }),
+            None => Ok(None),
+        };
+    }
+    Ok(None)
+}
+
+/// The command of a converter. Without a format, it only has the options all formats share.
+fn command(name: String, format: Option<InputFormat>, mode: Mode) -> Command {
+    let mut cmd = Command::new(name)
+        .version(env!("CARGO_PKG_VERSION"))
+        .author(AUTHOR);
+
+    let input = Arg::new("input")
+        .value_parser(value_parser!(PathBuf))
+        .required(true);
+    cmd = cmd.arg(match format {
+        Some(format) => input
+            .value_name(format.value_name())
+            .help(format.input_help())
+            .value_hint(match format.is_file_based() {
+                true => clap::ValueHint::FilePath,
+                false => clap::ValueHint::AnyPath,
+            }),
+        None => input
+            .value_name("INPUT")
+            .help("Input file")
+            .value_hint(clap::ValueHint::AnyPath),
+    });
+
+    let output = Arg::new("output")
+        .value_parser(value_parser!(PathBuf))
+        .value_hint(clap::ValueHint::AnyPath);
+    cmd = match mode {
+        Mode::Alias(OutputFormat::Arrow) => cmd.arg(
+            output
+                .value_name("ARROW")
+                .help("Output file, stdout if not present"),
+        ),
+        Mode::Alias(OutputFormat::Parquet) => cmd.arg(
+            output
+                .value_name("PARQUET")
+                .help("Output file, not needed with --validate")
+                .required_unless_present("validate"),
+        ),
+        Mode::Convert => cmd.arg(output.value_name("OUTPUT").help(
+            "Output file, stdout if not present. The format is picked from the extension (.arrow or .parquet) unless --to is given",
+        )),
+        Mode::Schema | Mode::Diff => cmd,
+    };
+
+    if !matches!(mode, Mode::Alias(_)) {
+        cmd = cmd.arg(
+            Arg::new("from")
+                .long("from")
+                .value_name("FORMAT")
+                .value_parser(value_parser!(InputFormat))
+                .help("The input format. Picked from the extension of the input if not present"),
+        );
+    }
+    if mode == Mode::Convert {
+        cmd = cmd.arg(
+            Arg::new("to")
+                .long("to")
+                .value_name("FORMAT")
+                .value_parser(value_parser!(OutputFormat))
+                .help("The output format. Picked from the extension of the output if not present, Arrow for stdout"),
+        );
+    }
+
+    cmd = cmd
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("CONFIG")
+                .value_parser(value_parser!(PathBuf))
+                .value_hint(clap::ValueHint::FilePath)
+                .help("TOML file with default options. arrow-tools.toml in the current directory or a parent directory if not present"),
+        )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .value_name("PROFILE")
+                .help("Use the options of a [profiles.<PROFILE>] table of the config file"),
+        );
+
+    cmd = cmd.arg(
+        Arg::new("schema_file")
+            .short('s')
+            .long("schema-file")
+            .value_name("SCHEMA_FILE")
+            .value_parser(value_parser!(PathBuf))
+            .value_hint(clap::ValueHint::AnyPath)
+            .help(match format {
+                _ if mode == Mode::Diff => "File with the reference Arrow schema in JSON format",
+                #[cfg(feature = "protobuf")]
+                Some(InputFormat::Proto) => {
+                    "File with Arrow schema in JSON format. Derived from the message type if not present"
+                }
+                _ => "File with Arrow schema in JSON format",
+            }),
+    );
+    if mode != Mode::Diff {
+        cmd = cmd.arg(
+            Arg::new("schema_from")
+                .long("schema-from")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .value_hint(clap::ValueHint::FilePath)
+                .conflicts_with("schema_file")
+                .help("Parquet or Arrow file whose schema, with the metadata of its fields, is used instead of inferring one"),
+        );
+    }
+
+    cmd = match format {
+        Some(format) => augment_format_args(cmd, format),
+        None => cmd.after_help(
+            "The options of each input format are shown by --from <FORMAT> --help, or by --help after an input with a known extension",
+        ),
+    };
+
+    if mode.converts() {
+        cmd = cmd
+            .arg(
+                Arg::new("print_schema")
+                    .short('p')
+                    .long("print-schema")
+                    .action(ArgAction::SetTrue)
+                    .help("Print the schema to stderr"),
+            )
+            .arg(
+                Arg::new("dry")
+                    .short('n')
+                    .long("dry")
+                    .action(ArgAction::SetTrue)
+                    .help("Only print the schema"),
+            )
+            .arg(
+                Arg::new("progress")
+                    .long("progress")
+                    .value_name("FORMAT")
+                    .num_args(0..=1)
+                    .require_equals(true)
+                    .default_missing_value("human")
+                    .value_parser(value_parser!(ProgressFormat))
+                    .help("Show the bytes read, rows written, throughput and time left on stderr, as a line that is updated or as a JSON object per line"),
+            )
+            .arg(
+                Arg::new("report")
+                    .long("report")
+                    .value_name("FILE")
+                    .value_parser(value_parser!(PathBuf))
+                    .value_hint(clap::ValueHint::FilePath)
+                    .help("Write a JSON report of the input, schema, rows, row groups, column sizes and time of the conversion to this file"),
+            )
+            .arg(
+                Arg::new("validate")
+                    .long("validate")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all(["dry", "report"])
+                    .help("Only check that every record can be read with the schema, without writing output. Reports every record that can't be read, with its position, and fails if there are any"),
+            );
+    }
+
+    cmd = cmd.arg(
+        Arg::new("memory_limit")
+            .long("memory-limit")
+            .value_name("SIZE")
+            .value_parser(parse_size)
+            .help("Keep memory use to about this size, e.g. 512M or 2G, with smaller row groups and batches, and by buffering the input for schema inference in a temporary file"),
+    );
+
+    if mode == Mode::Diff {
+        cmd = cmd
+            .arg(
+                Arg::new("reference")
+                    .long("reference")
+                    .value_name("FILE")
+                    .value_parser(value_parser!(PathBuf))
+                    .value_hint(clap::ValueHint::FilePath)
+                    .help("Parquet or Arrow file with the reference schema"),
+            )
+            .arg(
+                Arg::new("strict")
+                    .long("strict")
+                    .action(ArgAction::SetTrue)
+                    .help("Fail if a field of the reference was removed, changed its type or became nullable. Nullability is only compared for Parquet and Arrow inputs, as inferred fields are always nullable"),
+            )
+            .group(
+                ArgGroup::new("reference_schema")
+                    .args(["schema_file", "reference"])
+                    .required(true),
+            );
+    }
+
+    cmd = match mode {
+        Mode::Alias(OutputFormat::Parquet) => ParquetOptions::augment_args(cmd),
+        Mode::Convert => ParquetOptions::augment_args(cmd.next_help_heading("Parquet Options")),
+        _ => cmd,
+    };
+
+    // The options structs set their doc comments as the about text.
+    cmd.about(match mode {
+        Mode::Alias(_) => None,
+        Mode::Convert => Some("Convert a file to Arrow or Parquet"),
+        Mode::Schema => Some("Print the inferred Arrow schema of a file"),
+        Mode::Diff => Some("Compare the schema of a file to a reference schema, and print the fields that were added, removed, reordered or changed"),
+    })
+    .long_about(None)
+}
+
+fn augment_format_args(cmd: Command, format: InputFormat) -> Command {
+    use crate::formats::*;
+
+    match format {
+        InputFormat::Csv => CsvOptions::augment_args(cmd),
+        InputFormat::Json => JsonOptions::augment_args(cmd),
+        #[cfg(feature = "xlsx")]
+        InputFormat::Xlsx => XlsxOptions::augment_args(cmd),
+        #[cfg(feature = "sqlite")]
+        InputFormat::Sqlite => SqliteOptions::augment_args(cmd),
+        InputFormat::Fwf => FwfOptions::augment_args(cmd),
+        InputFormat::Log => LogOptions::augment_args(cmd),
+        #[cfg(feature = "xml")]
+        InputFormat::Xml => XmlOptions::augment_args(cmd),
+        #[cfg(feature = "msgpack")]
+        InputFormat::Msgpack => InferOptions::augment_args(cmd),
+        #[cfg(feature = "cbor")]
+        InputFormat::Cbor => InferOptions::augment_args(cmd),
+        #[cfg(feature = "protobuf")]
+        InputFormat::Proto => ProtobufOptions::augment_args(cmd),
+        InputFormat::Pgcopy => PgCopyOptions::augment_args(cmd),
+        InputFormat::Bson => BsonOptions::augment_args(cmd),
+        #[cfg(feature = "yaml")]
+        InputFormat::Yaml => InferOptions::augment_args(cmd),
+    }
+}
+
+fn format_options(format: InputFormat, matches: &ArgMatches) -> Result<FormatOptions, clap::Error> {
+    use crate::formats::*;
+
+    Ok(match format {
+        InputFormat::Csv => FormatOptions::Csv(CsvOptions::from_arg_matches(matches)?),
+        InputFormat::Json => FormatOptions::Json(JsonOptions::from_arg_matches(matches)?),
+        #[cfg(feature = "xlsx")]
+        InputFormat::Xlsx => FormatOptions::Xlsx(XlsxOptions::from_arg_matches(matches)?),
+        #[cfg(feature = "sqlite")]
+        InputFormat::Sqlite => FormatOptions::Sqlite(SqliteOptions::from_arg_matches(matches)?),
+        InputFormat::Fwf => FormatOptions::Fwf(FwfOptions::from_arg_matches(matches)?),
+        InputFormat::Log => FormatOptions::Log(LogOptions::from_arg_matches(matches)?),
+        #[cfg(feature = "xml")]
+        InputFormat::Xml => FormatOptions::Xml(XmlOptions::from_arg_matches(matches)?),
+        #[cfg(feature = "msgpack")]
+        InputFormat::Msgpack => FormatOptions::Msgpack(InferOptions::from_arg_matches(matches)?),
+        #[cfg(feature = "cbor")]
+        InputFormat::Cbor => FormatOptions::Cbor(InferOptions::from_arg_matches(matches)?),
+        #[cfg(feature = "protobuf")]
+        InputFormat::Proto => FormatOptions::Proto(ProtobufOptions::from_arg_matches(matches)?),
+        InputFormat::Pgcopy => FormatOptions::Pgcopy(PgCopyOptions::from_arg_matches(matches)?),
+        InputFormat::Bson => FormatOptions::Bson(BsonOptions::from_arg_matches(matches)?),
+        #[cfg(feature = "yaml")]
+        InputFormat::Yaml => FormatOptions::Yaml(InferOptions::from_arg_matches(matches)?),
+    })
+}
+
+fn conversion(
+    format: InputFormat,
+    mode: Mode,
+    matches: &ArgMatches,
+) -> Result<Conversion, ParseError> {
+    let options = format_options(format, matches).map_err(ParseError::Clap)?;
+    let parquet = match mode {
+        Mode::Alias(OutputFormat::Parquet) | Mode::Convert => {
+            ParquetOptions::from_arg_matches(matches).map_err(ParseError::Clap)?
+        }
+        _ => ParquetOptions::default(),
+    };
+    let output = match mode {
+        Mode::Schema | Mode::Diff => None,
+        _ => matches.get_one::<PathBuf>("output").cloned(),
+    };
+    let output_format = match mode {
+        Mode::Alias(output_format) => output_format,
+        Mode::Schema | Mode::Diff => OutputFormat::Arrow,
+        Mode::Convert => match (matches.get_one::<OutputFormat>("to"), &output) {
+            (Some(output_format), _) => *output_format,
+            (None, None) => OutputFormat::Arrow,
+            (None, Some(path)) => OutputFormat::from_path(path).ok_or_else(|| {
+                ArrowError::InvalidArgumentError(format!(
+                    "Could not detect the format of {path:?}, use --to"
+                ))
+            })?,
+        },
+    };
+
+    Ok(Conversion {
+        input: matches.get_one::<PathBuf>("input").unwrap().clone(),
+        output,
+        output_format,
+        options,
+        parquet,
+        schema_file: match mode {
+            Mode::Diff => None,
+            _ => matches.get_one::<PathBuf>("schema_file").cloned(),
+        },
+        schema_from: match mode {
+            Mode::Diff => None,
+            _ => matches.get_one::<PathBuf>("schema_from").cloned(),
+        },
+        print_schema: mode.converts() && matches.get_flag("print_schema"),
+        dry: !mode.converts() || matches.get_flag("dry"),
+        memory_limit: matches.get_one::<usize>("memory_limit").copied(),
+        progress: match mode {
+            Mode::Schema | Mode::Diff => None,
+            _ => matches.get_one::<ProgressFormat>("progress").copied(),
+        },
+        report: match mode {
+            Mode::Schema | Mode::Diff => None,
+            _ => matches.get_one::<PathBuf>("report").cloned(),
+        },
+        validate: mode.converts() && matches.get_flag("validate"),
+        diff: (mode == Mode::Diff).then(|| schema_diff(matches)),
+    })
+}
+
+/// The reference and mode of `arrow-tools schema diff`.
+fn schema_diff(matches: &ArgMatches) -> SchemaDiff {
+    SchemaDiff {
+        reference: matches
+            .get_one::<PathBuf>("schema_file")
+            .or(matches.get_one::<PathBuf>("reference"))
+            .unwrap()
+            .clone(),
+        strict: matches.get_flag("strict"),
+    }
+}
+
+/// Parses a size in bytes with an optional K, M, G or T suffix for powers of 1024.
+fn parse_size(size: &str) -> Result<usize, String> {
+    let upper = size.trim().to_uppercase();
+    let number = upper.trim_end_matches("IB").trim_end_matches('B');
+    let (number, shift) = match number.char_indices().last() {
+        Some((index, 'K')) => (&number[..index], 10),
+        Some((index, 'M')) => (&number[..index], 20),
+        Some((index, 'G')) => (&number[..index], 30),
+        Some((index, 'T')) => (&number[..index], 40),
+        _ => (number, 0),
+    };
+    let invalid = || format!("invalid size \"{size}\", expected e.g. 512M or 2G");
+    let bytes = number.trim().parse::<f64>().map_err(|_| invalid())? * (1u64 << shift) as f64;
+    if bytes.is_finite() && bytes >= 1.0 {
+        Ok(bytes as usize)
+    } else {
+        Err(invalid())
+    }
+}
@@ -0,0 +1,173 @@
+//! Default options for the converters from a TOML config file.
+//!
+//! Keys are the long names of the options, with `-` or `_`. Keys at the top level apply to
+//! all converters, keys in a table named after an input format (e.g. `[csv]`) only to that
+//! format, and keys in `[profiles.<name>]` only with `--profile <name>`. A profile can have
+//! format tables too. Options that don't apply to a converter are ignored, and options on
+//! the command line override the config.
+//!
+//! ```toml
+//! compression = "zstd"
+//!
+//! [csv]
+//! delimiter = ";"
+//!
+//! [profiles.spark-compatible]
+//! compression = "snappy"
+//! created-by = "parquet-mr version 1.12.3"
+//! ```
+
+use crate::formats::InputFormat;
+use arrow::error::ArrowError;
+use clap::{ArgAction, ValueEnum};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// The name of the config file that is picked up from the current directory or its parents.
+pub const CONFIG_FILE: &str = "arrow-tools.toml";
+
+/// Finds `arrow-tools.toml` in the current directory or the closest parent that has one.
+pub fn find_config() -> Option<PathBuf> {
+    let dir = std::env::current_dir().ok()?;
+    dir.ancestors()
+        .map(|dir| dir.join(CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+/// A parsed config file.
+#[derive(Clone, Debug)]
+pub struct Config {
+    path: PathBuf,
+    table: Table,
+}
+
+impl Config {
+    pub fn from_file(path: &Path) -> Result<Self, ArrowError> {
+        let text = std::fs::read_to_string(path).map_err(|error| {
+            ArrowError::IoError(
+                format!("Error opening config file: {path:?}, message: {error}"),
+                error,
+            )
+        })?;
+        let table = text.parse::<Table>().map_err(|error| {
+            ArrowError::ParseError(format!("Error reading config file {path:?}: {error}"))
+        })?;
+        Ok(Config {
+            path: path.to_path_buf(),
+            table,
+        })
+    }
+
+    /// A config that was not read from a file, e.g. options from a batch manifest. `path`
+    /// is named in errors.
+    pub fn from_table(path: &Path, table: Table) -> Self {
+        Config {
+            path: path.to_path_buf(),
+            table,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The names of the profiles.
+    pub fn profiles(&self) -> Vec<&str> {
+        match self.table.get("profiles") {
+            Some(Value::Table(profiles)) => profiles.keys().map(String::as_str).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The options for a format and profile, by long name. Later tables override earlier
+    /// ones: the top level, the format table, the profile and the format table of the profile.
+    pub fn options(
+        &self,
+        format: Option<InputFormat>,
+        profile: Option<&str>,
+    ) -> Result<BTreeMap<String, Value>, ArrowError> {
+        let mut options = BTreeMap::new();
+        collect(&self.table, format, &mut options)?;
+
+        if let Some(profile) = profile {
+            match self
+                .table
+                .get("profiles")
+                .and_then(|profiles| profiles.get(profile))
+            {
+                Some(Value::Table(table)) => collect(table, format, &mut options)?,
+                _ => {
+                    return Err(ArrowError::InvalidArgumentError(format!(
+                        "Profile \"{profile}\" not found in {:?}, available profiles: {}",
+                        self.path,
+                        self.profiles().join(", ")
+                    )))
+                }
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+fn collect(
+    table: &Table,
+    format: Option<InputFormat>,
+    options: &mut BTreeMap<String, Value>,
+) -> Result<(), ArrowError> {
+    let mut format_table = None;
+    for (key, value) in table {
+        match value {
+            Value::Table(table) if Some(key.as_str()) == format.map(|format| format.name()) => {
+                format_table = Some(table)
+            }
+            Value::Table(_) if key == "profiles" || InputFormat::from_str(key, true).is_ok() => {}
+            Value::Table(_) => {
+                return Err(ArrowError::InvalidArgumentError(format!(
+                    "Unknown table [{key}] in config, expected an input format or profiles"
+                )))
+            }
+            value => {
+                options.insert(key.replace('_', "-"), value.clone());
+            }
+        }
+    }
+    if let Some(table) = format_table {
+        collect(table, None, options)?;
+    }
+    Ok(())
+}
+
+/// Turns a config value into command line arguments for an option with the given action.
+pub(crate) fn arguments(
+    long: &str,
+    action: &ArgAction,
+    value: &Value,
+) -> Result<Vec<String>, ArrowError> {
+    let scalar = |value: &Value| match value {
+        Value::String(value) => Ok(value.clone()),
+        Value::Integer(value) => Ok(value.to_string()),
+        Value::Float(value) => Ok(value.to_string()),
+        Value::Boolean(value) => Ok(value.to_string()),
+        Value::Datetime(value) => Ok(value.to_string()),
+        _ => Err(ArrowError::InvalidArgumentError(format!(
+            "Invalid value for \"{long}\" in config: {value}"
+        ))),
+    };
+
+    Ok(match (action, value) {
+        (ArgAction::SetTrue, Value::Boolean(true)) => vec![format!("--{long}")],
+        (ArgAction::SetTrue, Value::Boolean(false)) => vec![],
+        (ArgAction::SetTrue, value) => {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "Invalid value for \"{long}\" in config, expected true or false: {value}"
+            )))
+        }
+        (ArgAction::Append, Value::Array(values)) => values
+            .iter()
+            .map(|value| Ok(format!("--{long}={}", scalar(value)?)))
+            .collect::<Result<_, ArrowError>>()?,
+        (_, value) => vec![format!("--{long}={}", scalar(value)?)],
+    })
+}
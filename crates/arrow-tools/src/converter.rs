@@ -0,0 +1,426 @@
+//! Converting any input format to Arrow IPC or Parquet.
+//!
+//! ```no_run
+//! use arrow_tools::converter::Converter;
+//! use arrow_tools::formats::{CsvOptions, FormatOptions};
+//! use arrow_tools::writer::{OutputFormat, ParquetCompression, ParquetOptions};
+//! use std::fs::File;
+//!
+//! let options = CsvOptions {
+//!     delimiter: Some(';'),
+//!     ..Default::default()
+//! };
+//! let summary = Converter::new("data.csv", FormatOptions::Csv(options))
+//!     .with_output_format(OutputFormat::Parquet)
+//!     .with_parquet_options(ParquetOptions {
+//!         compression: Some(ParquetCompression::ZSTD),
+//!         ..Default::default()
+//!     })
+//!     .write(File::create("data.parquet")?)?;
+//! println!("Wrote {} rows", summary.rows);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::formats::{FormatOptions, Input, InputFormat, InputReader};
+use crate::input::{ReadOptions, Source};
+use crate::progress::Progress;
+use crate::rejects::Rejected;
+use crate::schema::{read_file_schema, read_schema_file};
+use crate::writer::{BatchWriter, OutputFormat, ParquetOptions};
+use arrow::datatypes::SchemaRef;
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::sync_channel;
+use std::sync::Arc;
+
+/// The number of batches that are read ahead of the writer.
+const BATCHES_IN_FLIGHT: usize = 4;
+
+/// Where the schema of the output comes from.
+#[derive(Clone, Debug, Default)]
+pub enum SchemaSource {
+    /// Inferred from the input.
+    #[default]
+    Infer,
+    /// A file in the JSON format that `--print-schema` writes.
+    File(PathBuf),
+    /// The schema of an existing Parquet or Arrow file, with the metadata of its fields.
+    Existing(PathBuf),
+    Schema(SchemaRef),
+}
+
+/// The error of a conversion, by the step that failed.
+#[derive(Debug)]
+pub enum ConvertError {
+    /// The input could not be opened, or the format options are invalid.
+    Input(ArrowError),
+    /// The schema could not be loaded or inferred.
+    Schema(ArrowError),
+    /// A record could not be read with the schema.
+    Read(ArrowError),
+    /// The output could not be written.
+    Write(ArrowError),
+}
+
+impl ConvertError {
+    pub fn into_inner(self) -> ArrowError {
+        match self {
+            ConvertError::Input(error)
+            | ConvertError::Schema(error)
+            | ConvertError::Read(error)
+            | ConvertError::Write(error) => error,
+        }
+    }
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertError::Input(error) => write!(f, "Error opening input: {error}"),
+            ConvertError::Schema(error) => write!(f, "Error loading schema: {error}"),
+            ConvertError::Read(error) => write!(f, "Error reading input: {error}"),
+            ConvertError::Write(error) => write!(f, "Error writing output: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ConvertError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConvertError::Input(error)
+            | ConvertError::Schema(error)
+            | ConvertError::Read(error)
+            | ConvertError::Write(error) => Some(error),
+        }
+    }
+}
+
+/// The binaries report the underlying error, as they did before conversions had steps.
+impl From<ConvertError> for ArrowError {
+    fn from(error: ConvertError) -> Self {
+        error.into_inner()
+    }
+}
+
+/// What a conversion wrote.
+#[derive(Clone, Debug)]
+pub struct ConversionSummary {
+    pub schema: SchemaRef,
+    pub rows: usize,
+    pub batches: usize,
+    /// Records that were skipped because they could not be read, e.g. log lines that did
+    /// not match.
+    pub skipped: usize,
+    /// Records that had values set to null because they could not be read.
+    pub nulled: usize,
+    /// Parquet row groups that were written before they were full to stay within the
+    /// memory limit.
+    pub early_row_groups: usize,
+    /// The number of Parquet row groups, 0 for Arrow files.
+    pub row_groups: usize,
+    /// The sizes of the Parquet columns, empty for Arrow files.
+    pub columns: Vec<ColumnSize>,
+}
+
+/// The size of a Parquet column over all row groups.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnSize {
+    /// The path of the leaf column, with the names of nested fields separated by dots.
+    pub path: String,
+    pub compressed_bytes: u64,
+    pub uncompressed_bytes: u64,
+}
+
+/// What a validation read.
+#[derive(Clone, Debug)]
+pub struct ValidationSummary {
+    pub schema: SchemaRef,
+    /// The records that could be read with the schema.
+    pub rows: usize,
+    /// The records that could not be read with the schema.
+    pub invalid: usize,
+}
+
+/// Builds and runs a conversion of one input.
+///
+/// The input is opened and the schema resolved on the first call to
+/// [`schema`](Converter::schema), or when the conversion runs.
+pub struct Converter {
+    source: Option<Source>,
+    options: FormatOptions,
+    schema_source: SchemaSource,
+    output_format: OutputFormat,
+    parquet: ParquetOptions,
+    read_options: ReadOptions,
+    input: Option<Input>,
+    schema: Option<SchemaRef>,
+}
+
+impl Converter {
+    pub fn new<S: Into<Source>>(source: S, options: FormatOptions) -> Self {
+        Converter {
+            source: Some(source.into()),
+            options,
+            schema_source: SchemaSource::Infer,
+            output_format: OutputFormat::Arrow,
+            parquet: ParquetOptions::default(),
+            read_options: ReadOptions::default(),
+            input: None,
+            schema: None,
+        }
+    }
+
+    /// A conversion of a file with the format picked from its extension and the default
+    /// options of that format.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, ConvertError> {
+        let path = path.as_ref();
+        let format = InputFormat::from_path(path).ok_or_else(|| {
+            ConvertError::Input(ArrowError::InvalidArgumentError(format!(
+                "Could not detect the format of {path:?}"
+            )))
+        })?;
+        Ok(Converter::new(path, FormatOptions::new(format)))
+    }
+
+    pub fn with_schema_source(mut self, schema_source: SchemaSource) -> Self {
+        self.schema_source = schema_source;
+        self
+    }
+
+    pub fn with_schema_file<P: Into<PathBuf>>(self, path: P) -> Self {
+        self.with_schema_source(SchemaSource::File(path.into()))
+    }
+
+    /// Converts with the schema of an existing Parquet or Arrow file.
+    pub fn with_schema_from<P: Into<PathBuf>>(self, path: P) -> Self {
+        self.with_schema_source(SchemaSource::Existing(path.into()))
+    }
+
+    pub fn with_schema(self, schema: SchemaRef) -> Self {
+        self.with_schema_source(SchemaSource::Schema(schema))
+    }
+
+    /// Sets the output format, Arrow IPC by default.
+    pub fn with_output_format(mut self, output_format: OutputFormat) -> Self {
+        self.output_format = output_format;
+        self
+    }
+
+    pub fn with_parquet_options(mut self, parquet: ParquetOptions) -> Self {
+        self.parquet = parquet;
+        self
+    }
+
+    /// Keeps the memory of the conversion to about `bytes`: half for the Parquet row group
+    /// that is being encoded, which is written early when it gets larger, a quarter for the
+    /// start of the input that is buffered for schema inference, which spills to a
+    /// temporary file beyond that, and a quarter for the batches that are read and written.
+    pub fn with_memory_limit(mut self, bytes: usize) -> Self {
+        self.read_options.memory_limit = Some(bytes);
+        self
+    }
+
+    /// Counts the bytes read and the rows and row groups written in `progress`, which can
+    /// be reported while the conversion runs.
+    pub fn with_progress(mut self, progress: Arc<Progress>) -> Self {
+        self.read_options.progress = Some(progress);
+        self
+    }
+
+    pub fn options(&self) -> &FormatOptions {
+        &self.options
+    }
+
+    pub fn output_format(&self) -> OutputFormat {
+        self.output_format
+    }
+
+    fn input(&mut self) -> Result<&mut Input, ConvertError> {
+        if self.input.is_none() {
+            let source = self.source.take().expect("the input is opened once");
+            let input = Input::open_with(source, self.options.clone(), self.read_options.clone());
+            self.input = Some(input.map_err(ConvertError::Input)?);
+        }
+        Ok(self.input.as_mut().unwrap())
+    }
+
+    /// Opens the input and returns the schema of the output.
+    pub fn schema(&mut self) -> Result<SchemaRef, ConvertError> {
+        if let Some(ref schema) = self.schema {
+            return Ok(schema.clone());
+        }
+        let schema = match self.schema_source {
+            SchemaSource::Infer => {
+                Arc::new(self.input()?.infer_schema().map_err(ConvertError::Schema)?)
+            }
+            SchemaSource::File(ref path) => {
+                Arc::new(read_schema_file(path).map_err(ConvertError::Schema)?)
+            }
+            SchemaSource::Existing(ref path) => {
+                let schema = read_file_schema(path).and_then(|schema| {
+                    schema.ok_or_else(|| {
+                        ArrowError::SchemaError(format!(
+                            "{path:?} is neither a Parquet nor an Arrow file"
+                        ))
+                    })
+                });
+                Arc::new(schema.map_err(ConvertError::Schema)?)
+            }
+            SchemaSource::Schema(ref schema) => schema.clone(),
+        };
+        self.schema = Some(schema.clone());
+        Ok(schema)
+    }
+
+    /// Reads the input into record batches instead of writing it to a file.
+    pub fn into_reader(mut self) -> Result<Box<dyn InputReader>, ConvertError> {
+        let schema = self.schema()?;
+        self.input()?;
+        self.input
+            .take()
+            .unwrap()
+            .into_reader(schema)
+            .map_err(ConvertError::Input)
+    }
+
+    /// Reads the whole input with the schema without writing it, and passes each CSV or JSON
+    /// record that can't be read to `listener`. Inputs of other formats fail at the first
+    /// record that can't be read.
+    pub fn validate<F: FnMut(Rejected) + 'static>(
+        mut self,
+        listener: F,
+    ) -> Result<ValidationSummary, ConvertError> {
+        // Records that can't be read are reported when reading, so inference leaves them out.
+        if self.input.is_none() {
+            self.options.skip_errors();
+        }
+        let schema = self.schema()?;
+        self.input()?;
+        let mut reader = self
+            .input
+            .take()
+            .unwrap()
+            .into_validating_reader(schema.clone(), listener)
+            .map_err(ConvertError::Input)?;
+        let mut rows = 0;
+        for batch in reader.by_ref() {
+            let batch = batch.map_err(ConvertError::Read)?;
+            rows += batch.num_rows();
+            if let Some(ref progress) = self.read_options.progress {
+                progress.add_rows(batch.num_rows());
+            }
+        }
+        Ok(ValidationSummary {
+            schema,
+            rows,
+            invalid: reader.skipped(),
+        })
+    }
+
+    /// Runs the conversion and writes the output file to `output`.
+    ///
+    /// The batches are encoded and written on a second thread while the next ones are read,
+    /// with a few batches in flight so that reading waits when writing falls behind.
+    pub fn write<W: Write + Send>(self, output: W) -> Result<ConversionSummary, ConvertError> {
+        let output_format = self.output_format;
+        let mut parquet = self.parquet.clone();
+        if let Some(limit) = self.read_options.memory_limit {
+            let max_bytes = parquet.max_row_group_bytes.unwrap_or(usize::MAX);
+            parquet.max_row_group_bytes = Some(max_bytes.min(limit / 2));
+        }
+        let progress = self.read_options.progress.clone();
+        let mut reader = self.into_reader()?;
+        let schema = reader.schema();
+
+        let mut writer = BatchWriter::try_new(output, schema.clone(), output_format, &parquet)
+            .map_err(ConvertError::Write)?;
+        let mut rows = 0;
+        let mut batches = 0;
+        let (sender, receiver) = sync_channel::<RecordBatch>(BATCHES_IN_FLIGHT);
+        let (read, written) = std::thread::scope(|scope| {
+            let progress = progress.as_ref();
+            let written = scope.spawn(move || {
+                for batch in receiver {
+                    writer.write(&batch)?;
+                    if let Some(progress) = progress {
+                        progress.add_rows(batch.num_rows());
+                        progress.set_row_groups(writer.row_groups());
+                    }
+                }
+                Ok(writer)
+            });
+            let read = reader.by_ref().try_for_each(|batch| {
+                let batch = batch.map_err(|error| Some(ConvertError::Read(error)))?;
+                rows += batch.num_rows();
+                batches += 1;
+                // The writer only stops early after an error, which it returns below.
+                sender.send(batch).map_err(|_| None)
+            });
+            drop(sender);
+            let written = written.join();
+            (
+                read,
+                written.unwrap_or_else(|panic| std::panic::resume_unwind(panic)),
+            )
+        });
+        let writer = written.map_err(ConvertError::Write)?;
+        if let Err(Some(error)) = read {
+            return Err(error);
+        }
+        let metadata = writer.finish().map_err(ConvertError::Write)?;
+        if let (Some(progress), Some(metadata)) = (&progress, &metadata) {
+            progress.set_row_groups(metadata.num_row_groups());
+        }
+
+        let mut columns = Vec::new();
+        if let Some(ref metadata) = metadata {
+            for row_group in metadata.row_groups() {
+                for (index, column) in row_group.columns().iter().enumerate() {
+                    if index == columns.len() {
+                        columns.push(ColumnSize {
+                            path: column.column_path().string(),
+                            compressed_bytes: 0,
+                            uncompressed_bytes: 0,
+                        });
+                    }
+                    columns[index].compressed_bytes += column.compressed_size() as u64;
+                    columns[index].uncompressed_bytes += column.uncompressed_size() as u64;
+                }
+            }
+        }
+
+        // Row groups other than the last are only smaller than the maximum rows when the
+        // maximum bytes cut them short.
+        let row_groups = metadata
+            .as_ref()
+            .map_or(0, |metadata| metadata.num_row_groups());
+        let early_row_groups = match metadata {
+            Some(metadata) if parquet.max_row_group_bytes.is_some() => {
+                let max_rows = parquet
+                    .writer_properties()
+                    .max_row_group_row_count()
+                    .unwrap_or(usize::MAX);
+                let row_groups = metadata.row_groups();
+                row_groups[..row_groups.len().saturating_sub(1)]
+                    .iter()
+                    .filter(|row_group| (row_group.num_rows() as usize) < max_rows)
+                    .count()
+            }
+            _ => 0,
+        };
+
+        Ok(ConversionSummary {
+            schema,
+            rows,
+            batches,
+            skipped: reader.skipped(),
+            nulled: reader.nulled(),
+            early_row_groups,
+            row_groups,
+            columns,
+        })
+    }
+}
@@ -0,0 +1,418 @@
+//! Parsing CSV files on one or several threads.
+//!
+//! On several threads, the input is read on the calling thread and split into chunks at
+//! record boundaries, which are parsed by a pool of worker threads. Batches are returned in
+//! the order of the input.
+//!
+//! Both readers keep the bytes of the records they parse until they are parsed, and when
+//! parsing fails, parse them again one record at a time to report the one that fails.
+
+use crate::formats::CsvOptions;
+use crate::rejects::{OnError, RecordFormat, TolerantReader};
+use arrow::csv::reader::{Decoder, Format};
+use arrow::datatypes::SchemaRef;
+use arrow::error::ArrowError;
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use std::collections::VecDeque;
+use std::io::{BufRead, Cursor, Read};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+/// The number of records per batch.
+const BATCH_SIZE: usize = 1024;
+/// The size of the chunks that are parsed on one thread.
+const CHUNK_SIZE: usize = 4 << 20;
+/// The most that is read at once while looking for the end of a chunk.
+const READ_SIZE: usize = 64 << 10;
+
+/// The batches of a chunk, or the error and the chunk to find the record that failed in.
+type Parsed = Result<Vec<RecordBatch>, (ArrowError, Vec<u8>)>;
+
+/// A chunk of whole records to parse on a worker.
+struct Job {
+    chunk: Vec<u8>,
+    /// Whether the chunk starts with the header.
+    header: bool,
+    batch_size: usize,
+    sender: Sender<Parsed>,
+}
+
+/// A chunk that is being parsed, and where it starts in the input.
+struct Pending {
+    batches: Receiver<Parsed>,
+    header: bool,
+    offset: u64,
+    line: u64,
+}
+
+/// Finds the ends of records, following quotes, escapes and comments.
+///
+/// Like arrow's reader, records end at `\n`, `\r\n` or a `\r` on its own. A `\r\n` ends
+/// the record at the `\r`, so the `\n` is left as an empty line that the reader skips.
+#[derive(Clone, Debug)]
+pub(crate) struct Splitter {
+    quote: u8,
+    escape: Option<u8>,
+    comment: Option<u8>,
+    quoted: bool,
+    escaped: bool,
+    pub(crate) commented: bool,
+    record_start: bool,
+}
+
+impl Splitter {
+    pub(crate) fn new(options: &CsvOptions) -> Self {
+        Splitter {
+            quote: options.quote.map_or(b'"', |quote| quote as u8),
+            escape: options.escape.map(|escape| escape as u8),
+            comment: options.comment.map(|comment| comment as u8),
+            quoted: false,
+            escaped: false,
+            commented: false,
+            record_start: true,
+        }
+    }
+
+    /// Scans `bytes` and returns the position after the last record that ends in them.
+    fn scan(&mut self, bytes: &[u8]) -> Option<usize> {
+        let mut end = None;
+        for (index, &byte) in bytes.iter().enumerate() {
+            if self.step(byte) {
+                end = Some(index + 1);
+            }
+        }
+        end
+    }
+
+    /// Scans the next byte and returns whether it ends a record.
+    pub(crate) fn step(&mut self, byte: u8) -> bool {
+        let record_start = std::mem::replace(&mut self.record_start, false);
+        if self.escaped {
+            self.escaped = false;
+        } else if self.commented {
+            self.commented = !is_line_end(byte);
+            self.record_start = !self.commented;
+        } else if record_start && Some(byte) == self.comment {
+            self.commented = true;
+        } else if byte == self.quote {
+            self.quoted = !self.quoted;
+        } else if self.quoted && Some(byte) == self.escape {
+            self.escaped = true;
+        } else if is_line_end(byte) && !self.quoted {
+            self.record_start = true;
+            return true;
+        }
+        false
+    }
+}
+
+pub(crate) fn is_line_end(byte: u8) -> bool {
+    byte == b'\n' || byte == b'\r'
+}
+
+/// Reads CSV records into record batches, parsing chunks of the input in parallel.
+pub struct ParallelCsvReader<R> {
+    reader: R,
+    schema: SchemaRef,
+    options: CsvOptions,
+    splitter: Splitter,
+    chunk_size: usize,
+    batch_size: usize,
+    threads: usize,
+    buffer: Vec<u8>,
+    scanned: usize,
+    header: bool,
+    done: bool,
+    jobs: Option<Sender<Job>>,
+    pending: VecDeque<Pending>,
+    batches: VecDeque<RecordBatch>,
+    /// The offset and line of the start of the next chunk.
+    offset: u64,
+    line: u64,
+    /// The records that were returned, to give the position of errors in the input.
+    records: u64,
+}
+
+impl<R: Read> ParallelCsvReader<R> {
+    pub fn new(reader: R, schema: SchemaRef, options: &CsvOptions, threads: usize) -> Self {
+        let threads = threads.max(1);
+        let (jobs, receiver) = channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..threads {
+            let receiver = receiver.clone();
+            let schema = schema.clone();
+            let format = options.format();
+            std::thread::spawn(move || loop {
+                let job = receiver.lock().unwrap().recv();
+                let Ok(job) = job else {
+                    break;
+                };
+                let sender = job.sender.clone();
+                let _ = sender.send(parse(job, schema.clone(), format.clone()));
+            });
+        }
+
+        ParallelCsvReader {
+            reader,
+            schema,
+            options: options.clone(),
+            splitter: Splitter::new(options),
+            chunk_size: CHUNK_SIZE,
+            batch_size: BATCH_SIZE,
+            threads,
+            buffer: Vec::new(),
+            scanned: 0,
+            header: options.header.unwrap_or(true),
+            done: false,
+            jobs: Some(jobs),
+            pending: VecDeque::new(),
+            batches: VecDeque::new(),
+            offset: 0,
+            line: 1,
+            records: 0,
+        }
+    }
+
+    /// Sets the number of records per batch, 1024 by default.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Sets the size of the chunks that are parsed on one thread, 4 MiB by default.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Reads the next chunk of whole records, or the rest of the input at its end.
+    fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, ArrowError> {
+        let mut end = None;
+        while !self.done {
+            if self.buffer.len() >= self.chunk_size && end.is_some() {
+                break;
+            }
+            let length = self.buffer.len();
+            let size = self.chunk_size.saturating_sub(length).max(READ_SIZE);
+            self.buffer.resize(length + size, 0);
+            let read = self.reader.read(&mut self.buffer[length..])?;
+            self.buffer.truncate(length + read);
+            if read == 0 {
+                self.done = true;
+                end = Some(self.buffer.len());
+            } else if let Some(offset) = self.splitter.scan(&self.buffer[self.scanned..]) {
+                end = Some(self.scanned + offset);
+            }
+            self.scanned = self.buffer.len();
+        }
+        match end {
+            Some(0) | None => Ok(None),
+            Some(end) => {
+                let rest = self.buffer.split_off(end);
+                self.scanned -= end;
+                Ok(Some(std::mem::replace(&mut self.buffer, rest)))
+            }
+        }
+    }
+
+    /// Sends chunks to the workers until each has two or the input ends.
+    fn dispatch(&mut self) -> Result<(), ArrowError> {
+        while self.jobs.is_some() && self.pending.len() < 2 * self.threads {
+            let Some(chunk) = self.next_chunk()? else {
+                self.jobs = None;
+                break;
+            };
+            let jobs = self.jobs.as_ref().unwrap();
+            let (sender, batches) = channel();
+            let header = std::mem::take(&mut self.header);
+            let pending = Pending {
+                batches,
+                header,
+                offset: self.offset,
+                line: self.line,
+            };
+            self.offset += chunk.len() as u64;
+            self.line += lines(&chunk);
+            let job = Job {
+                chunk,
+                header,
+                batch_size: self.batch_size,
+                sender,
+            };
+            jobs.send(job).map_err(|_| worker_error())?;
+            self.pending.push_back(pending);
+        }
+        Ok(())
+    }
+}
+
+fn parse(job: Job, schema: SchemaRef, format: Format) -> Parsed {
+    arrow::csv::ReaderBuilder::new(schema)
+        .with_format(format.with_header(job.header))
+        .with_batch_size(job.batch_size)
+        .build(&job.chunk[..])
+        .and_then(|reader| reader.collect())
+        .map_err(|error| (error, job.chunk))
+}
+
+impl<R> ParallelCsvReader<R> {
+    fn locate(&self, pending: Pending, chunk: Vec<u8>, error: ArrowError) -> ArrowError {
+        let start = (pending.offset, pending.line, self.records);
+        locate(
+            &self.options,
+            &self.schema,
+            pending.header,
+            chunk,
+            start,
+            error,
+        )
+    }
+}
+
+/// Parses the records of a part of the input that failed one at a time to find the first
+/// that fails, and returns its error with its position. `start` is the offset and line of
+/// the part, and the index of its first record.
+fn locate(
+    options: &CsvOptions,
+    schema: &SchemaRef,
+    header: bool,
+    bytes: Vec<u8>,
+    (offset, line, index): (u64, u64, u64),
+    error: ArrowError,
+) -> ArrowError {
+    let mut options = options.clone();
+    options.header = Some(header);
+    TolerantReader::new(
+        Cursor::new(bytes),
+        RecordFormat::Csv(options),
+        schema.clone(),
+        OnError::Fail,
+        None,
+        None,
+    )
+    .with_start(offset, line, index)
+    .find_map(Result::err)
+    .unwrap_or(error)
+}
+
+fn lines(bytes: &[u8]) -> u64 {
+    bytes.iter().filter(|&&byte| byte == b'\n').count() as u64
+}
+
+fn worker_error() -> ArrowError {
+    ArrowError::ExternalError("A CSV parsing thread stopped".into())
+}
+
+impl<R: Read> Iterator for ParallelCsvReader<R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(batch) = self.batches.pop_front() {
+                self.records += batch.num_rows() as u64;
+                return Some(Ok(batch));
+            }
+            if let Err(error) = self.dispatch() {
+                self.jobs = None;
+                self.pending.clear();
+                return Some(Err(error));
+            }
+            let pending = self.pending.pop_front()?;
+            match pending.batches.recv() {
+                Ok(Ok(batches)) => self.batches = batches.into(),
+                Ok(Err((error, chunk))) => return Some(Err(self.locate(pending, chunk, error))),
+                Err(_) => return Some(Err(worker_error())),
+            }
+        }
+    }
+}
+
+impl<R: Read> RecordBatchReader for ParallelCsvReader<R> {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// Reads CSV records into record batches on the calling thread, like arrow's reader, and
+/// reports errors with the record they happened in.
+pub struct CsvReader<R> {
+    reader: R,
+    schema: SchemaRef,
+    options: CsvOptions,
+    decoder: Decoder,
+    /// The bytes of the records in the decoder.
+    buffer: Vec<u8>,
+    /// The offset and line of the start of the buffer.
+    offset: u64,
+    line: u64,
+    /// The records that were returned, to give the position of errors in the input.
+    records: u64,
+}
+
+impl<R: BufRead> CsvReader<R> {
+    pub fn new(reader: R, schema: SchemaRef, options: &CsvOptions, batch_size: usize) -> Self {
+        let decoder = arrow::csv::ReaderBuilder::new(schema.clone())
+            .with_format(options.format())
+            .with_batch_size(batch_size)
+            .build_decoder();
+        CsvReader {
+            reader,
+            schema,
+            options: options.clone(),
+            decoder,
+            buffer: Vec::new(),
+            offset: 0,
+            line: 1,
+            records: 0,
+        }
+    }
+
+    fn read(&mut self) -> Result<Option<RecordBatch>, ArrowError> {
+        loop {
+            let buf = self.reader.fill_buf()?;
+            let decoded = match self.decoder.decode(buf) {
+                Ok(decoded) => decoded,
+                Err(error) => {
+                    self.buffer.extend_from_slice(buf);
+                    return Err(self.locate(error));
+                }
+            };
+            self.buffer.extend_from_slice(&buf[..decoded]);
+            self.reader.consume(decoded);
+            if decoded == 0 || self.decoder.capacity() == 0 {
+                break;
+            }
+        }
+        let batch = match self.decoder.flush() {
+            Ok(batch) => batch,
+            Err(error) => return Err(self.locate(error)),
+        };
+        self.records += batch.as_ref().map_or(0, |batch| batch.num_rows() as u64);
+        self.offset += self.buffer.len() as u64;
+        self.line += lines(&self.buffer);
+        self.buffer.clear();
+        Ok(batch)
+    }
+
+    fn locate(&mut self, error: ArrowError) -> ArrowError {
+        // Only the first batch starts with the header.
+        let header = self.offset == 0 && self.options.header.unwrap_or(true);
+        let bytes = std::mem::take(&mut self.buffer);
+        let start = (self.offset, self.line, self.records);
+        locate(&self.options, &self.schema, header, bytes, start, error)
+    }
+}
+
+impl<R: BufRead> Iterator for CsvReader<R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read().transpose()
+    }
+}
+
+impl<R: BufRead> RecordBatchReader for CsvReader<R> {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
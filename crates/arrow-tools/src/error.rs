@@ -0,0 +1,202 @@
+//! Errors of the converters with the position in the input where they happened, and the
+//! exit codes of the binaries.
+
+use crate::converter::ConvertError;
+use arrow::error::ArrowError;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// The longest snippet of the input that is shown, in characters.
+const SNIPPET_LENGTH: usize = 200;
+
+/// What failed, which decides the exit code of the binaries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A check found problems, e.g. breaking changes of a schema.
+    Check,
+    /// The arguments, config or manifest are invalid.
+    Usage,
+    /// The input could not be opened, or the format options are invalid.
+    Input,
+    /// The schema could not be loaded or inferred.
+    Schema,
+    /// A record could not be read with the schema.
+    Read,
+    /// The output could not be written.
+    Write,
+}
+
+impl ErrorKind {
+    /// 1 for problems that a check found, 2 for usage errors, as for invalid arguments, and 3
+    /// to 6 for the steps of a conversion.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            ErrorKind::Check => 1,
+            ErrorKind::Usage => 2,
+            ErrorKind::Input => 3,
+            ErrorKind::Schema => 4,
+            ErrorKind::Read => 5,
+            ErrorKind::Write => 6,
+        }
+    }
+}
+
+/// Where in the input an error happened, as far as it is known.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Position {
+    pub file: Option<PathBuf>,
+    /// The offset of the start of the record, after decompression.
+    pub byte_offset: Option<u64>,
+    /// The line of the start of the record, from 1.
+    pub line: Option<u64>,
+    /// The index of the record, from 0 and not counting a header.
+    pub record: Option<u64>,
+    pub column: Option<String>,
+}
+
+/// An error of a conversion with its position in the input and the line it happened in.
+#[derive(Debug)]
+pub struct Error {
+    pub kind: ErrorKind,
+    /// Boxed to keep results small, as most errors have no position.
+    pub position: Box<Position>,
+    pub snippet: Option<String>,
+    pub source: ArrowError,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, source: ArrowError) -> Self {
+        Error {
+            kind,
+            position: Box::default(),
+            snippet: None,
+            source,
+        }
+    }
+
+    pub fn usage(source: ArrowError) -> Self {
+        Error::new(ErrorKind::Usage, source)
+    }
+
+    /// The problems that a check found, shown as `message` without the prefix of an
+    /// [`ArrowError`].
+    pub fn check<M: Into<String>>(message: M) -> Self {
+        Error::new(
+            ErrorKind::Check,
+            ArrowError::ExternalError(message.into().into()),
+        )
+    }
+
+    /// Adds the input file to the position, and takes the position that a reader reported
+    /// by returning an [`Error`] as an external error.
+    pub fn locate(mut self, path: &Path) -> Self {
+        self.source = match self.source {
+            ArrowError::ExternalError(error) => match error.downcast::<Error>() {
+                Ok(inner) => {
+                    self.position = inner.position;
+                    self.snippet = inner.snippet;
+                    inner.source
+                }
+                Err(error) => ArrowError::ExternalError(error),
+            },
+            source => source,
+        };
+        self.position.file = Some(path.to_path_buf());
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::Check => match self.source {
+                ArrowError::ExternalError(ref message) => write!(f, "{message}")?,
+                ref source => write!(f, "{source}")?,
+            },
+            ErrorKind::Usage => write!(f, "{}", self.source)?,
+            ErrorKind::Input => write!(f, "Error opening input: {}", self.source)?,
+            ErrorKind::Schema => write!(f, "Error loading schema: {}", self.source)?,
+            ErrorKind::Read => write!(f, "Error reading input: {}", self.source)?,
+            ErrorKind::Write => write!(f, "Error writing output: {}", self.source)?,
+        }
+
+        write_position(f, &self.position, self.snippet.as_deref())
+    }
+}
+
+/// Writes the position of an error after its message, and the line it happened in.
+pub(crate) fn write_position(
+    f: &mut fmt::Formatter<'_>,
+    position: &Position,
+    snippet: Option<&str>,
+) -> fmt::Result {
+    let mut parts = Vec::new();
+    if let Some(line) = position.line {
+        parts.push(format!("line {line}"));
+    }
+    if let Some(record) = position.record {
+        parts.push(format!("record {record}"));
+    }
+    if let Some(ref column) = position.column {
+        parts.push(format!("column {column:?}"));
+    }
+    if let Some(offset) = position.byte_offset {
+        parts.push(format!("byte {offset}"));
+    }
+    if let Some(ref file) = position.file {
+        parts.insert(0, file.display().to_string());
+    }
+    if !parts.is_empty() {
+        write!(f, "\n  --> {}", parts.join(", "))?;
+    }
+
+    if let Some(snippet) = snippet {
+        let number = position
+            .line
+            .map(|line| line.to_string())
+            .unwrap_or_default();
+        let gutter = " ".repeat(number.len());
+        let mut shown: String = snippet.chars().take(SNIPPET_LENGTH).collect();
+        if shown.len() < snippet.len() {
+            shown.push_str("...");
+        }
+        write!(f, "\n {gutter} |\n {number} | {shown}")?;
+    }
+    Ok(())
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl From<ConvertError> for Error {
+    fn from(error: ConvertError) -> Self {
+        match error {
+            ConvertError::Input(error) => Error::new(ErrorKind::Input, error),
+            ConvertError::Schema(error) => Error::new(ErrorKind::Schema, error),
+            ConvertError::Read(error) => Error::new(ErrorKind::Read, error),
+            ConvertError::Write(error) => Error::new(ErrorKind::Write, error),
+        }
+    }
+}
+
+/// Prints the error of a binary, if any, and returns its exit code. Errors of the arguments
+/// that clap found, and asking for the help or version, exit the way clap does.
+pub fn exit_code(result: Result<(), Error>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            #[cfg(feature = "cli")]
+            if let ArrowError::ExternalError(ref source) = error.source {
+                if let Some(error) = source.downcast_ref::<clap::Error>() {
+                    error.exit();
+                }
+            }
+            eprintln!("Error: {error}");
+            ExitCode::from(error.kind.exit_code())
+        }
+    }
+}
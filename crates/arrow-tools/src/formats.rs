@@ -0,0 +1,1129 @@
+//! The input formats and their options, and reading any of them into record batches.
+//!
+//! With the `cli` feature, the options structs double as `clap` arguments so the binaries
+//! share their flags and help texts.
+
+use crate::bson::{self, BsonDocuments, ObjectIdType};
+#[cfg(feature = "cbor")]
+use crate::cbor::CborValues;
+use crate::csv::{CsvReader, ParallelCsvReader};
+use crate::fwf::{FixedWidthColumn, FixedWidthReader, Layout};
+use crate::input::{self, ReadOptions, Source};
+use crate::json::JsonReader;
+use crate::logs::{LogFormat, LogParser, LogReader};
+#[cfg(feature = "msgpack")]
+use crate::msgpack::MessagePackValues;
+use crate::pgcopy::{self, columns_schema, is_binary, CopyFormat, PgColumn, PgCopyReader, PgType};
+#[cfg(feature = "protobuf")]
+use crate::protobuf::{load_message, message_schema, ProtobufMessages};
+use crate::rejects::{self, OnError, RecordFormat, Rejected, TolerantReader};
+use crate::seekable_reader::SeekRead;
+#[cfg(feature = "sqlite")]
+use crate::sqlite::{self, table_query, SqliteReader};
+use crate::values::ValueReader;
+#[cfg(feature = "xlsx")]
+use crate::xlsx::{Sheet, SheetReader};
+#[cfg(feature = "xml")]
+use crate::xml::XmlRecords;
+#[cfg(feature = "yaml")]
+use crate::yaml::YamlDocuments;
+use arrow::csv::reader::Format;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatchReader;
+use regex::Regex;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The supported input formats. Formats that need an optional dependency are only
+/// available with the feature of the same name.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum InputFormat {
+    Csv,
+    Json,
+    #[cfg(feature = "xlsx")]
+    Xlsx,
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+    Fwf,
+    Log,
+    #[cfg(feature = "xml")]
+    Xml,
+    #[cfg(feature = "msgpack")]
+    Msgpack,
+    #[cfg(feature = "cbor")]
+    Cbor,
+    #[cfg(feature = "protobuf")]
+    Proto,
+    Pgcopy,
+    Bson,
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+impl InputFormat {
+    /// All formats that were compiled in.
+    pub const ALL: &'static [InputFormat] = &[
+        InputFormat::Csv,
+        InputFormat::Json,
+        #[cfg(feature = "xlsx")]
+        InputFormat::Xlsx,
+        #[cfg(feature = "sqlite")]
+        InputFormat::Sqlite,
+        InputFormat::Fwf,
+        InputFormat::Log,
+        #[cfg(feature = "xml")]
+        InputFormat::Xml,
+        #[cfg(feature = "msgpack")]
+        InputFormat::Msgpack,
+        #[cfg(feature = "cbor")]
+        InputFormat::Cbor,
+        #[cfg(feature = "protobuf")]
+        InputFormat::Proto,
+        InputFormat::Pgcopy,
+        InputFormat::Bson,
+        #[cfg(feature = "yaml")]
+        InputFormat::Yaml,
+    ];
+
+    /// The name of the format, as in the names of the binaries, e.g. `csv` in `csv2parquet`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            InputFormat::Csv => "csv",
+            InputFormat::Json => "json",
+            #[cfg(feature = "xlsx")]
+            InputFormat::Xlsx => "xlsx",
+            #[cfg(feature = "sqlite")]
+            InputFormat::Sqlite => "sqlite",
+            InputFormat::Fwf => "fwf",
+            InputFormat::Log => "log",
+            #[cfg(feature = "xml")]
+            InputFormat::Xml => "xml",
+            #[cfg(feature = "msgpack")]
+            InputFormat::Msgpack => "msgpack",
+            #[cfg(feature = "cbor")]
+            InputFormat::Cbor => "cbor",
+            #[cfg(feature = "protobuf")]
+            InputFormat::Proto => "proto",
+            InputFormat::Pgcopy => "pgcopy",
+            InputFormat::Bson => "bson",
+            #[cfg(feature = "yaml")]
+            InputFormat::Yaml => "yaml",
+        }
+    }
+
+    /// The file extensions of the format, without compression suffixes.
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            InputFormat::Csv => &["csv"],
+            InputFormat::Json => &["json", "jsonl", "ndjson"],
+            #[cfg(feature = "xlsx")]
+            InputFormat::Xlsx => &["xlsx", "xlsm", "xlsb", "xls", "ods"],
+            #[cfg(feature = "sqlite")]
+            InputFormat::Sqlite => &["sqlite", "sqlite3", "db"],
+            InputFormat::Fwf => &["fwf"],
+            InputFormat::Log => &["log"],
+            #[cfg(feature = "xml")]
+            InputFormat::Xml => &["xml"],
+            #[cfg(feature = "msgpack")]
+            InputFormat::Msgpack => &["msgpack", "mpk"],
+            #[cfg(feature = "cbor")]
+            InputFormat::Cbor => &["cbor"],
+            #[cfg(feature = "protobuf")]
+            InputFormat::Proto => &["pb", "protobuf"],
+            InputFormat::Pgcopy => &["copy", "pgcopy"],
+            InputFormat::Bson => &["bson"],
+            #[cfg(feature = "yaml")]
+            InputFormat::Yaml => &["yaml", "yml"],
+        }
+    }
+
+    /// Picks the format from the extension of the input file, ignoring a `.gz` suffix.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let path = match input::is_gzip(path) {
+            true => Path::new(path.file_stem()?),
+            false => path,
+        };
+        let extension = path.extension().and_then(OsStr::to_str)?.to_lowercase();
+        InputFormat::ALL
+            .iter()
+            .find(|format| format.extensions().contains(&extension.as_str()))
+            .copied()
+    }
+
+    /// The name of the input argument in the help, e.g. `<CSV>`.
+    pub fn value_name(&self) -> &'static str {
+        match self {
+            InputFormat::Csv => "CSV",
+            InputFormat::Json => "JSON",
+            #[cfg(feature = "xlsx")]
+            InputFormat::Xlsx => "XLSX",
+            #[cfg(feature = "sqlite")]
+            InputFormat::Sqlite => "SQLITE",
+            InputFormat::Fwf => "FWF",
+            InputFormat::Log => "LOG",
+            #[cfg(feature = "xml")]
+            InputFormat::Xml => "XML",
+            #[cfg(feature = "msgpack")]
+            InputFormat::Msgpack => "MSGPACK",
+            #[cfg(feature = "cbor")]
+            InputFormat::Cbor => "CBOR",
+            #[cfg(feature = "protobuf")]
+            InputFormat::Proto => "PROTOBUF",
+            InputFormat::Pgcopy => "COPY",
+            InputFormat::Bson => "BSON",
+            #[cfg(feature = "yaml")]
+            InputFormat::Yaml => "YAML",
+        }
+    }
+
+    /// The help of the input argument.
+    pub fn input_help(&self) -> &'static str {
+        match self {
+            InputFormat::Csv => "Input CSV file, stdin if not present",
+            InputFormat::Json => "Input JSON file, stdin if not present",
+            #[cfg(feature = "xlsx")]
+            InputFormat::Xlsx => "Input spreadsheet (.xlsx, .xlsm, .xlsb, .xls or .ods)",
+            #[cfg(feature = "sqlite")]
+            InputFormat::Sqlite => "Input SQLite database",
+            InputFormat::Fwf => "Input fixed-width file",
+            InputFormat::Log => "Input log file",
+            #[cfg(feature = "xml")]
+            InputFormat::Xml => "Input XML file",
+            #[cfg(feature = "msgpack")]
+            InputFormat::Msgpack => "Input file with concatenated MessagePack documents",
+            #[cfg(feature = "cbor")]
+            InputFormat::Cbor => "Input file with concatenated CBOR documents",
+            #[cfg(feature = "protobuf")]
+            InputFormat::Proto => "Input file with length-delimited Protobuf messages",
+            InputFormat::Pgcopy => "Input file written by COPY ... TO",
+            InputFormat::Bson => {
+                "Input file with concatenated BSON documents, e.g. from mongodump"
+            }
+            #[cfg(feature = "yaml")]
+            InputFormat::Yaml => "Input YAML file with one or more documents. Each document, or each element of a document that is a sequence, is a record",
+        }
+    }
+
+    /// Whether the input is a file that is opened by path rather than read as a stream.
+    pub fn is_file_based(&self) -> bool {
+        match self {
+            #[cfg(feature = "xlsx")]
+            InputFormat::Xlsx => true,
+            #[cfg(feature = "sqlite")]
+            InputFormat::Sqlite => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct CsvOptions {
+    /// The number of records to infer the schema from. All rows if not present. Setting max-read-records to zero will stop schema inference and all columns will be string typed.
+    #[cfg_attr(feature = "cli", arg(short, long))]
+    pub max_read_records: Option<usize>,
+
+    /// Set whether the CSV file has headers.
+    #[cfg_attr(feature = "cli", arg(long, default_value = "true"))]
+    pub header: Option<bool>,
+
+    /// Set the CSV file's column delimiter as a byte character.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub delimiter: Option<char>,
+
+    /// Specify an escape character.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub escape: Option<char>,
+
+    /// Specify a custom quote character.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub quote: Option<char>,
+
+    /// Specify a comment character.
+    ///
+    /// Lines starting with this character will be ignored
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub comment: Option<char>,
+
+    /// Provide a regex to match null values.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub null_regex: Option<Regex>,
+
+    /// The number of threads that parse the file. The input is split into chunks at record boundaries that are parsed in parallel. 1 if not present.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub threads: Option<usize>,
+
+    /// What to do with records that can't be read with the schema. Reading past them is done on one thread.
+    #[cfg_attr(feature = "cli", arg(long, value_enum, default_value_t = OnError::Fail))]
+    pub on_error: OnError,
+
+    /// The number of records that can't be read to allow before failing. No limit if not present.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub max_errors: Option<usize>,
+
+    /// File to write records that can't be read to, as JSON lines with the line, the error and the record.
+    #[cfg_attr(feature = "cli", arg(long, value_hint = clap::ValueHint::AnyPath))]
+    pub reject_file: Option<PathBuf>,
+}
+
+impl CsvOptions {
+    /// The format of the Arrow CSV reader. Files have a header unless `header` is false.
+    pub fn format(&self) -> Format {
+        let mut format = Format::default().with_header(self.header.unwrap_or(true));
+
+        if let Some(delimiter) = self.delimiter {
+            format = format.with_delimiter(delimiter as u8);
+        }
+
+        if let Some(escape) = self.escape {
+            format = format.with_escape(escape as u8);
+        }
+
+        if let Some(quote) = self.quote {
+            format = format.with_quote(quote as u8);
+        }
+
+        if let Some(comment) = self.comment {
+            format = format.with_comment(comment as u8);
+        }
+
+        if let Some(ref regex) = self.null_regex {
+            format = format.with_null_regex(regex.clone());
+        }
+
+        format
+    }
+}
+
+/// Options of formats that only need the number of records to infer the schema from.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct InferOptions {
+    /// The number of records to infer the schema from. All records if not present.
+    #[cfg_attr(feature = "cli", arg(short, long))]
+    pub max_read_records: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct JsonOptions {
+    /// The number of records to infer the schema from. All records if not present.
+    #[cfg_attr(feature = "cli", arg(short, long))]
+    pub max_read_records: Option<usize>,
+
+    /// What to do with records that can't be read with the schema.
+    #[cfg_attr(feature = "cli", arg(long, value_enum, default_value_t = OnError::Fail))]
+    pub on_error: OnError,
+
+    /// The number of records that can't be read to allow before failing. No limit if not present.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub max_errors: Option<usize>,
+
+    /// File to write records that can't be read to, as JSON lines with the line, the error and the record.
+    #[cfg_attr(feature = "cli", arg(long, value_hint = clap::ValueHint::AnyPath))]
+    pub reject_file: Option<PathBuf>,
+}
+
+#[cfg(feature = "xlsx")]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct XlsxOptions {
+    /// The number of records to infer the schema from. All rows if not present. Setting max-read-records to zero will stop schema inference and all columns will be string typed.
+    #[cfg_attr(feature = "cli", arg(short, long))]
+    pub max_read_records: Option<usize>,
+
+    /// Name or zero-based index of the sheet to read. The first sheet if not present.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub sheet: Option<String>,
+
+    /// Cell range to read, e.g. "B2:F100", or "B2" for everything from that cell on.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub range: Option<String>,
+
+    /// Set whether the first row of the range has headers.
+    #[cfg_attr(feature = "cli", arg(long, default_value = "true"))]
+    pub header: Option<bool>,
+
+    /// Provide a regex to match null values.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub null_regex: Option<Regex>,
+}
+
+#[cfg(feature = "sqlite")]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+#[cfg_attr(
+    feature = "cli",
+    command(group(clap::ArgGroup::new("source").required(true).args(["table", "query"])))
+)]
+pub struct SqliteOptions {
+    /// Table to export.
+    #[cfg_attr(feature = "cli", arg(short, long))]
+    pub table: Option<String>,
+
+    /// SQL query to export the results of.
+    #[cfg_attr(feature = "cli", arg(short, long))]
+    pub query: Option<String>,
+
+    /// The number of rows to sample for the storage classes of each column. All rows if not present. Setting max-read-records to zero will only use the declared column types.
+    #[cfg_attr(feature = "cli", arg(short, long))]
+    pub max_read_records: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct FwfOptions {
+    /// File with the column layout, one "name:start:width[:type[:implied_decimals]]" per line.
+    #[cfg_attr(feature = "cli", arg(short, long, value_hint = clap::ValueHint::FilePath))]
+    pub layout: Option<PathBuf>,
+
+    /// Add a column as "name:start:width[:type[:implied_decimals]]", after the columns of the layout file.
+    ///
+    /// Start is the one-based byte offset of the column. The type is an Arrow type such as Int64 or "Decimal128(9, 2)" and inferred if empty. Implied decimals insert a decimal point into values without one, e.g. 0001234 with 2 becomes 12.34.
+    #[cfg_attr(feature = "cli", arg(long = "column"))]
+    pub columns: Vec<FixedWidthColumn>,
+
+    /// The number of records to infer the schema from. All rows if not present. Setting max-read-records to zero will stop schema inference and all columns will be string typed.
+    #[cfg_attr(feature = "cli", arg(short, long))]
+    pub max_read_records: Option<usize>,
+
+    /// Provide a regex to match null values.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub null_regex: Option<Regex>,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct LogOptions {
+    /// The format of the log lines.
+    #[cfg_attr(
+        feature = "cli",
+        arg(short, long, value_enum, default_value_t = LogFormat::Combined)
+    )]
+    pub format: LogFormat,
+
+    /// Regex with named capture groups, e.g. "(?P<level>\w+): (?P<message>.*)". Each group becomes a column. Overrides the format.
+    #[cfg_attr(feature = "cli", arg(short, long, conflicts_with = "format"))]
+    pub regex: Option<Regex>,
+
+    /// File to write lines that don't match to.
+    #[cfg_attr(feature = "cli", arg(long, value_hint = clap::ValueHint::AnyPath))]
+    pub reject_file: Option<PathBuf>,
+
+    /// The number of matching lines to infer the schema from. All lines if not present. Setting max-read-records to zero will stop schema inference and all columns will be string typed.
+    #[cfg_attr(feature = "cli", arg(short, long))]
+    pub max_read_records: Option<usize>,
+
+    /// Provide a regex to match null values. Defaults to "^-$" for the common and combined formats.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub null_regex: Option<Regex>,
+}
+
+impl LogOptions {
+    pub fn parser(&self) -> Result<LogParser, ArrowError> {
+        match self.regex {
+            Some(ref regex) => LogParser::new(regex.clone(), self.null_regex.clone()),
+            None => LogParser::new(
+                Regex::new(self.format.pattern()).unwrap(),
+                self.null_regex.clone().or_else(|| {
+                    self.format
+                        .null_pattern()
+                        .map(|pattern| Regex::new(pattern).unwrap())
+                }),
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "xml")]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct XmlOptions {
+    /// Path of the elements that are records, e.g. "catalog/book". Matches at any depth unless it starts with "/".
+    ///
+    /// Attributes and child elements of each record become columns, nested elements become structs and repeated elements become lists. Text of elements that also have attributes or children is stored in a "#text" field.
+    #[cfg_attr(feature = "cli", arg(short, long))]
+    pub record_path: String,
+
+    /// The number of records to infer the schema from. All records if not present.
+    #[cfg_attr(feature = "cli", arg(short, long))]
+    pub max_read_records: Option<usize>,
+}
+
+#[cfg(feature = "protobuf")]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct ProtobufOptions {
+    /// Compiled FileDescriptorSet with the message type, e.g. from "protoc --include_imports --descriptor_set_out".
+    #[cfg_attr(feature = "cli", arg(short, long, value_hint = clap::ValueHint::FilePath))]
+    pub descriptor_set: PathBuf,
+
+    /// Fully qualified name of the message type, e.g. "example.Event".
+    #[cfg_attr(feature = "cli", arg(short, long))]
+    pub message: String,
+}
+
+/// The two formats of `COPY ... TO`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum PgCopyFormat {
+    Text,
+    Binary,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct PgCopyOptions {
+    /// The COPY format. Detected from the signature of the binary format if not present.
+    #[cfg_attr(feature = "cli", arg(short, long, value_enum))]
+    pub format: Option<PgCopyFormat>,
+
+    /// Add a column as "name:type" with a PostgreSQL type such as int8, text, "numeric(10,2)" or timestamptz. The binary format needs the types of all columns, from columns or a schema file.
+    #[cfg_attr(feature = "cli", arg(long = "column"))]
+    pub columns: Vec<PgColumn>,
+
+    /// Set the column delimiter of the text format.
+    #[cfg_attr(feature = "cli", arg(short, long, default_value = "\t"))]
+    pub delimiter: char,
+
+    /// Set the string that represents null values in the text format.
+    #[cfg_attr(feature = "cli", arg(long, default_value = "\\N"))]
+    pub null: String,
+
+    /// The number of rows to infer the schema from in the text format. All rows if not present. Setting max-read-records to zero will stop schema inference and all columns will be string typed.
+    #[cfg_attr(feature = "cli", arg(short, long))]
+    pub max_read_records: Option<usize>,
+}
+
+impl Default for PgCopyOptions {
+    fn default() -> Self {
+        PgCopyOptions {
+            format: None,
+            columns: Vec::new(),
+            delimiter: '\t',
+            null: "\\N".to_string(),
+            max_read_records: None,
+        }
+    }
+}
+
+impl PgCopyOptions {
+    fn text_format(&self) -> CopyFormat {
+        CopyFormat::Text {
+            delimiter: self.delimiter as u8,
+            null: self.null.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct BsonOptions {
+    /// The type of inferred ObjectId columns.
+    #[cfg_attr(
+        feature = "cli",
+        arg(long, value_enum, default_value_t = ObjectIdType::FixedSizeBinary)
+    )]
+    pub object_id: ObjectIdType,
+
+    /// The number of documents to infer the schema from. All documents if not present.
+    #[cfg_attr(feature = "cli", arg(short, long))]
+    pub max_read_records: Option<usize>,
+}
+
+/// The options of an input format.
+#[derive(Clone, Debug)]
+pub enum FormatOptions {
+    Csv(CsvOptions),
+    Json(JsonOptions),
+    #[cfg(feature = "xlsx")]
+    Xlsx(XlsxOptions),
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqliteOptions),
+    Fwf(FwfOptions),
+    Log(LogOptions),
+    #[cfg(feature = "xml")]
+    Xml(XmlOptions),
+    #[cfg(feature = "msgpack")]
+    Msgpack(InferOptions),
+    #[cfg(feature = "cbor")]
+    Cbor(InferOptions),
+    #[cfg(feature = "protobuf")]
+    Proto(ProtobufOptions),
+    Pgcopy(PgCopyOptions),
+    Bson(BsonOptions),
+    #[cfg(feature = "yaml")]
+    Yaml(InferOptions),
+}
+
+impl FormatOptions {
+    /// The default options of a format.
+    pub fn new(format: InputFormat) -> Self {
+        match format {
+            InputFormat::Csv => FormatOptions::Csv(CsvOptions::default()),
+            InputFormat::Json => FormatOptions::Json(JsonOptions::default()),
+            #[cfg(feature = "xlsx")]
+            InputFormat::Xlsx => FormatOptions::Xlsx(XlsxOptions::default()),
+            #[cfg(feature = "sqlite")]
+            InputFormat::Sqlite => FormatOptions::Sqlite(SqliteOptions::default()),
+            InputFormat::Fwf => FormatOptions::Fwf(FwfOptions::default()),
+            InputFormat::Log => FormatOptions::Log(LogOptions::default()),
+            #[cfg(feature = "xml")]
+            InputFormat::Xml => FormatOptions::Xml(XmlOptions::default()),
+            #[cfg(feature = "msgpack")]
+            InputFormat::Msgpack => FormatOptions::Msgpack(InferOptions::default()),
+            #[cfg(feature = "cbor")]
+            InputFormat::Cbor => FormatOptions::Cbor(InferOptions::default()),
+            #[cfg(feature = "protobuf")]
+            InputFormat::Proto => FormatOptions::Proto(ProtobufOptions::default()),
+            InputFormat::Pgcopy => FormatOptions::Pgcopy(PgCopyOptions::default()),
+            InputFormat::Bson => FormatOptions::Bson(BsonOptions::default()),
+            #[cfg(feature = "yaml")]
+            InputFormat::Yaml => FormatOptions::Yaml(InferOptions::default()),
+        }
+    }
+
+    pub fn format(&self) -> InputFormat {
+        match self {
+            FormatOptions::Csv(_) => InputFormat::Csv,
+            FormatOptions::Json(_) => InputFormat::Json,
+            #[cfg(feature = "xlsx")]
+            FormatOptions::Xlsx(_) => InputFormat::Xlsx,
+            #[cfg(feature = "sqlite")]
+            FormatOptions::Sqlite(_) => InputFormat::Sqlite,
+            FormatOptions::Fwf(_) => InputFormat::Fwf,
+            FormatOptions::Log(_) => InputFormat::Log,
+            #[cfg(feature = "xml")]
+            FormatOptions::Xml(_) => InputFormat::Xml,
+            #[cfg(feature = "msgpack")]
+            FormatOptions::Msgpack(_) => InputFormat::Msgpack,
+            #[cfg(feature = "cbor")]
+            FormatOptions::Cbor(_) => InputFormat::Cbor,
+            #[cfg(feature = "protobuf")]
+            FormatOptions::Proto(_) => InputFormat::Proto,
+            FormatOptions::Pgcopy(_) => InputFormat::Pgcopy,
+            FormatOptions::Bson(_) => InputFormat::Bson,
+            #[cfg(feature = "yaml")]
+            FormatOptions::Yaml(_) => InputFormat::Yaml,
+        }
+    }
+
+    /// What to do with records that can't be read, the number of them to allow and the
+    /// file to write them to, for the formats that can read past them.
+    fn on_error(&self) -> Option<(OnError, Option<usize>, Option<&PathBuf>)> {
+        match self {
+            FormatOptions::Csv(options) => Some((
+                options.on_error,
+                options.max_errors,
+                options.reject_file.as_ref(),
+            )),
+            FormatOptions::Json(options) => Some((
+                options.on_error,
+                options.max_errors,
+                options.reject_file.as_ref(),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Reads past the records that can't be read instead of failing, for the formats that
+    /// can.
+    pub(crate) fn skip_errors(&mut self) {
+        match self {
+            FormatOptions::Csv(CsvOptions { on_error, .. })
+            | FormatOptions::Json(JsonOptions { on_error, .. })
+                if *on_error == OnError::Fail =>
+            {
+                *on_error = OnError::Skip
+            }
+            _ => {}
+        }
+    }
+
+    /// The number of records to infer the schema from, which is also how much of an input
+    /// that can't seek is buffered.
+    pub fn max_read_records(&self) -> Option<usize> {
+        match self {
+            FormatOptions::Csv(options) => options.max_read_records,
+            FormatOptions::Json(options) => options.max_read_records,
+            #[cfg(feature = "xlsx")]
+            FormatOptions::Xlsx(options) => options.max_read_records,
+            #[cfg(feature = "sqlite")]
+            FormatOptions::Sqlite(options) => options.max_read_records,
+            FormatOptions::Fwf(options) => options.max_read_records,
+            FormatOptions::Log(options) => options.max_read_records,
+            #[cfg(feature = "xml")]
+            FormatOptions::Xml(options) => options.max_read_records,
+            #[cfg(feature = "msgpack")]
+            FormatOptions::Msgpack(options) => options.max_read_records,
+            #[cfg(feature = "cbor")]
+            FormatOptions::Cbor(options) => options.max_read_records,
+            #[cfg(feature = "protobuf")]
+            FormatOptions::Proto(_) => None,
+            FormatOptions::Pgcopy(options) => options.max_read_records,
+            FormatOptions::Bson(options) => options.max_read_records,
+            #[cfg(feature = "yaml")]
+            FormatOptions::Yaml(options) => options.max_read_records,
+        }
+    }
+}
+
+/// A reader of record batches from an input.
+pub trait InputReader: RecordBatchReader {
+    /// The number of records that were skipped so far because they could not be read.
+    fn skipped(&self) -> usize {
+        0
+    }
+
+    /// The number of records that had values set to null so far because they could not be
+    /// read.
+    fn nulled(&self) -> usize {
+        0
+    }
+}
+
+impl<R: std::io::Read> InputReader for ParallelCsvReader<R> {}
+impl<R: std::io::BufRead> InputReader for JsonReader<R> {}
+impl<R: std::io::BufRead> InputReader for CsvReader<R> {}
+#[cfg(feature = "xlsx")]
+impl InputReader for SheetReader {}
+#[cfg(feature = "sqlite")]
+impl InputReader for SqliteReader {}
+impl<R: std::io::BufRead> InputReader for FixedWidthReader<R> {}
+impl<R: std::io::BufRead> InputReader for PgCopyReader<R> {}
+impl<I: Iterator<Item = Result<serde_json::Value, ArrowError>>> InputReader for ValueReader<I> {}
+
+impl<R: std::io::BufRead> InputReader for TolerantReader<R> {
+    fn skipped(&self) -> usize {
+        self.skipped()
+    }
+
+    fn nulled(&self) -> usize {
+        self.nulled()
+    }
+}
+
+impl<R: std::io::BufRead> InputReader for LogReader<R> {
+    fn skipped(&self) -> usize {
+        self.rejected()
+    }
+}
+
+type Stream = BufReader<Box<dyn SeekRead>>;
+
+/// Finds the CSV record that failed schema inference, by reading the records with the
+/// columns of the header as strings.
+fn locate_csv(stream: &mut Stream, options: &CsvOptions, error: ArrowError) -> ArrowError {
+    let header = stream
+        .rewind()
+        .map_err(ArrowError::from)
+        .and_then(|()| options.format().infer_schema(&mut *stream, Some(0)));
+    let Ok((header, _)) = header else {
+        return error;
+    };
+    let fields: Vec<Field> = header
+        .fields()
+        .iter()
+        .map(|field| Field::new(field.name(), DataType::Utf8, true))
+        .collect();
+    if stream.rewind().is_err() {
+        return error;
+    }
+    let format = RecordFormat::Csv(options.clone());
+    let schema = Arc::new(Schema::new(fields));
+    TolerantReader::new(stream, format, schema, OnError::Fail, None, None)
+        .find_map(Result::err)
+        .unwrap_or(error)
+}
+
+/// Finds the JSON record that failed schema inference, by reading the records without
+/// fields.
+fn locate_json(stream: &mut Stream, error: ArrowError) -> ArrowError {
+    if stream.rewind().is_err() {
+        return error;
+    }
+    JsonReader::try_new(stream, Arc::new(Schema::empty()), BATCH_SIZE)
+        .ok()
+        .and_then(|mut reader| reader.find_map(Result::err))
+        .unwrap_or(error)
+}
+
+/// State that is prepared when the input is opened and used for both schema inference and
+/// reading.
+enum Prepared {
+    Stream(Stream),
+    #[cfg(feature = "xlsx")]
+    Sheet(Sheet),
+    #[cfg(feature = "sqlite")]
+    Query(String),
+    Fwf(Stream, Layout),
+    Log(Stream, LogParser),
+    #[cfg(feature = "protobuf")]
+    Proto(
+        BufReader<Box<dyn std::io::Read>>,
+        prost_reflect::MessageDescriptor,
+    ),
+    Pgcopy(Stream, bool),
+}
+
+/// An opened input.
+pub struct Input {
+    path: Option<PathBuf>,
+    options: FormatOptions,
+    prepared: Prepared,
+    memory_limit: Option<usize>,
+}
+
+impl Input {
+    /// Opens the input and checks the options that don't depend on the data, e.g. that a
+    /// layout was given for fixed-width files.
+    pub fn open<S: Into<Source>>(source: S, options: FormatOptions) -> Result<Self, ArrowError> {
+        Self::open_with(source, options, ReadOptions::default())
+    }
+
+    /// Opens the input to be read with a memory limit or progress reporting. With a memory
+    /// limit, a quarter of it goes to the start of the input that is buffered for schema
+    /// inference, which spills to a temporary file beyond that, and a quarter to the batches
+    /// that are read.
+    pub fn open_with<S: Into<Source>>(
+        source: S,
+        options: FormatOptions,
+        read_options: ReadOptions,
+    ) -> Result<Self, ArrowError> {
+        let source = source.into();
+        let path = source.path().map(Path::to_path_buf);
+        let max_read_records = options.max_read_records();
+        let stream = |source: Source| -> Result<Stream, ArrowError> {
+            Ok(BufReader::new(
+                source.open(max_read_records, &read_options)?,
+            ))
+        };
+        let prepared = match &options {
+            #[cfg(feature = "xlsx")]
+            FormatOptions::Xlsx(options) => Prepared::Sheet(Sheet::open(
+                file_path(&path, InputFormat::Xlsx)?,
+                options.sheet.as_deref(),
+                options.range.as_deref(),
+                options.header.unwrap_or(true),
+                options.null_regex.clone(),
+            )?),
+            #[cfg(feature = "sqlite")]
+            FormatOptions::Sqlite(options) => {
+                file_path(&path, InputFormat::Sqlite)?;
+                match (&options.query, &options.table) {
+                    (Some(query), _) => Prepared::Query(query.clone()),
+                    (None, Some(table)) => Prepared::Query(table_query(table)),
+                    (None, None) => {
+                        return Err(ArrowError::InvalidArgumentError(
+                            "Either a table or a query is required".to_string(),
+                        ))
+                    }
+                }
+            }
+            FormatOptions::Fwf(options) => {
+                let mut layout = match options.layout {
+                    Some(ref path) => Layout::from_file(path)?,
+                    None => Layout::default(),
+                };
+                for column in &options.columns {
+                    layout.push(column.clone());
+                }
+                if layout.is_empty() {
+                    return Err(ArrowError::InvalidArgumentError(
+                        "No columns given, use --layout or --column".to_string(),
+                    ));
+                }
+                Prepared::Fwf(stream(source)?, layout)
+            }
+            FormatOptions::Log(options) => Prepared::Log(stream(source)?, options.parser()?),
+            #[cfg(feature = "protobuf")]
+            FormatOptions::Proto(options) => Prepared::Proto(
+                BufReader::new(source.open_stream(&read_options)?),
+                load_message(&options.descriptor_set, &options.message)?,
+            ),
+            FormatOptions::Pgcopy(options) => {
+                let mut stream = stream(source)?;
+                let binary = match options.format {
+                    Some(format) => format == PgCopyFormat::Binary,
+                    None => is_binary(&mut stream)?,
+                };
+                Prepared::Pgcopy(stream, binary)
+            }
+            _ => Prepared::Stream(stream(source)?),
+        };
+        Ok(Input {
+            path,
+            options,
+            prepared,
+            memory_limit: read_options.memory_limit,
+        })
+    }
+
+    /// The path of the input, if it was opened from a file.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn options(&self) -> &FormatOptions {
+        &self.options
+    }
+
+    /// Infers the schema and rewinds the input so it can be read afterwards. With
+    /// `on_error` other than fail, CSV records with too few or too many fields and JSON
+    /// lines that aren't objects are left out of inference, to be rejected when reading.
+    pub fn infer_schema(&mut self) -> Result<Schema, ArrowError> {
+        let max_read_records = self.options.max_read_records();
+        // Records that can't be read are left to the reader when it reads past them.
+        let tolerant = self
+            .options
+            .on_error()
+            .is_some_and(|(on_error, ..)| on_error != OnError::Fail);
+        let schema = match (&self.options, &mut self.prepared) {
+            (FormatOptions::Csv(options), Prepared::Stream(stream)) => options
+                .format()
+                .with_truncated_rows(tolerant)
+                .infer_schema(&mut *stream, max_read_records)
+                .map(|(schema, _)| schema)
+                .map_err(|error| locate_csv(stream, options, error)),
+            (FormatOptions::Json(_), Prepared::Stream(stream)) if tolerant => {
+                rejects::infer_json_schema(&mut *stream, max_read_records)
+            }
+            (FormatOptions::Json(_), Prepared::Stream(stream)) => {
+                arrow::json::reader::infer_json_schema_from_seekable(&mut *stream, max_read_records)
+                    .map(|(schema, _)| schema)
+                    .map_err(|error| locate_json(stream, error))
+            }
+            #[cfg(feature = "xlsx")]
+            (_, Prepared::Sheet(sheet)) => return Ok(sheet.infer_schema(max_read_records)),
+            #[cfg(feature = "sqlite")]
+            (_, Prepared::Query(query)) => {
+                sqlite::infer_schema(self.path.as_ref().unwrap(), query, max_read_records)
+            }
+            (FormatOptions::Fwf(options), Prepared::Fwf(stream, layout)) => {
+                layout.infer_schema(stream, max_read_records, options.null_regex.as_ref())
+            }
+            (_, Prepared::Log(stream, parser)) => parser.infer_schema(stream, max_read_records),
+            #[cfg(feature = "xml")]
+            (FormatOptions::Xml(options), Prepared::Stream(stream)) => crate::values::infer_schema(
+                XmlRecords::new(&mut *stream, &options.record_path)?,
+                max_read_records,
+            ),
+            #[cfg(feature = "msgpack")]
+            (FormatOptions::Msgpack(_), Prepared::Stream(stream)) => {
+                crate::values::infer_schema(MessagePackValues::new(&mut *stream), max_read_records)
+            }
+            #[cfg(feature = "cbor")]
+            (FormatOptions::Cbor(_), Prepared::Stream(stream)) => {
+                crate::values::infer_schema(CborValues::new(&mut *stream), max_read_records)
+            }
+            #[cfg(feature = "protobuf")]
+            (_, Prepared::Proto(_, message)) => return message_schema(message),
+            (FormatOptions::Pgcopy(options), Prepared::Pgcopy(stream, binary)) => {
+                if !options.columns.is_empty() {
+                    return Ok(columns_schema(&options.columns));
+                }
+                if *binary {
+                    return Err(ArrowError::InvalidArgumentError(
+                        "The binary format needs the column types, use --column or --schema-file"
+                            .to_string(),
+                    ));
+                }
+                pgcopy::infer_schema(&mut *stream, options.text_format(), max_read_records)
+            }
+            (FormatOptions::Bson(options), Prepared::Stream(stream)) => {
+                bson::infer_schema(&mut *stream, max_read_records, options.object_id)
+            }
+            #[cfg(feature = "yaml")]
+            (FormatOptions::Yaml(_), Prepared::Stream(stream)) => {
+                crate::values::infer_schema(YamlDocuments::new(&mut *stream), max_read_records)
+            }
+            _ => unreachable!("the input is prepared for its format"),
+        }?;
+        self.rewind()?;
+        Ok(schema)
+    }
+
+    fn rewind(&mut self) -> Result<(), ArrowError> {
+        match &mut self.prepared {
+            Prepared::Stream(stream)
+            | Prepared::Fwf(stream, _)
+            | Prepared::Log(stream, _)
+            | Prepared::Pgcopy(stream, _) => stream.rewind()?,
+            #[allow(unreachable_patterns)]
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Reads the input into record batches with the given schema.
+    pub fn into_reader(self, schema: SchemaRef) -> Result<Box<dyn InputReader>, ArrowError> {
+        self.open_reader(schema, None)
+    }
+
+    /// Reads the input like [`into_reader`](Input::into_reader), but reads past the CSV and
+    /// JSON records that can't be read with the schema and passes each of them to
+    /// `listener`. The readers of other formats stop at the first error.
+    pub fn into_validating_reader<F: FnMut(Rejected) + 'static>(
+        self,
+        schema: SchemaRef,
+        listener: F,
+    ) -> Result<Box<dyn InputReader>, ArrowError> {
+        self.open_reader(schema, Some(Box::new(listener)))
+    }
+
+    fn open_reader(
+        self,
+        schema: SchemaRef,
+        listener: Option<Box<dyn FnMut(Rejected)>>,
+    ) -> Result<Box<dyn InputReader>, ArrowError> {
+        let batch_memory = self.memory_limit.map(|limit| limit / 4);
+        let batch_size = batch_memory.map_or(BATCH_SIZE, |memory| batch_size(&schema, memory));
+        Ok(match (self.options, self.prepared) {
+            (options, Prepared::Stream(stream))
+                if options.on_error().is_some_and(|(on_error, ..)| {
+                    on_error != OnError::Fail || listener.is_some()
+                }) =>
+            {
+                let (on_error, max_errors, reject_file) = options.on_error().unwrap();
+                // Validating reports every record that can't be read.
+                let on_error = match listener {
+                    Some(_) => OnError::Skip,
+                    None => on_error,
+                };
+                let rejects = create_rejects(reject_file)?;
+                let format = match options {
+                    FormatOptions::Csv(ref options) => RecordFormat::Csv(options.clone()),
+                    _ => RecordFormat::Json,
+                };
+                let mut reader =
+                    TolerantReader::new(stream, format, schema, on_error, max_errors, rejects)
+                        .with_batch_size(batch_size);
+                if let Some(listener) = listener {
+                    reader = reader.with_listener(listener);
+                }
+                Box::new(reader)
+            }
+            (FormatOptions::Csv(options), Prepared::Stream(stream)) => match options.threads {
+                Some(threads) if threads > 1 => {
+                    let reader = ParallelCsvReader::new(stream, schema, &options, threads)
+                        .with_batch_size(batch_size);
+                    match batch_memory {
+                        // Each thread parses up to two chunks at a time.
+                        Some(memory) => Box::new(reader.with_chunk_size(memory / threads / 2)),
+                        None => Box::new(reader),
+                    }
+                }
+                _ => Box::new(CsvReader::new(stream, schema, &options, batch_size)),
+            },
+            (FormatOptions::Json(_), Prepared::Stream(stream)) => {
+                Box::new(JsonReader::try_new(stream, schema, batch_size)?)
+            }
+            #[cfg(feature = "xlsx")]
+            (_, Prepared::Sheet(sheet)) => Box::new(sheet.into_reader(schema)),
+            #[cfg(feature = "sqlite")]
+            (_, Prepared::Query(query)) => {
+                Box::new(SqliteReader::new(self.path.unwrap(), query, schema))
+            }
+            (FormatOptions::Fwf(options), Prepared::Fwf(stream, layout)) => Box::new(
+                FixedWidthReader::new(stream, layout, schema, options.null_regex),
+            ),
+            (FormatOptions::Log(options), Prepared::Log(stream, parser)) => {
+                let rejects = create_rejects(options.reject_file.as_ref())?;
+                Box::new(LogReader::new(stream, parser, schema, rejects))
+            }
+            #[cfg(feature = "xml")]
+            (FormatOptions::Xml(options), Prepared::Stream(stream)) => Box::new(
+                ValueReader::try_new(XmlRecords::new(stream, &options.record_path)?, schema)?,
+            ),
+            #[cfg(feature = "msgpack")]
+            (FormatOptions::Msgpack(_), Prepared::Stream(stream)) => Box::new(
+                ValueReader::try_new(MessagePackValues::new(stream), schema)?,
+            ),
+            #[cfg(feature = "cbor")]
+            (FormatOptions::Cbor(_), Prepared::Stream(stream)) => {
+                Box::new(ValueReader::try_new(CborValues::new(stream), schema)?)
+            }
+            #[cfg(feature = "protobuf")]
+            (_, Prepared::Proto(stream, message)) => Box::new(ValueReader::try_new(
+                ProtobufMessages::new(stream, message),
+                schema,
+            )?),
+            (FormatOptions::Pgcopy(options), Prepared::Pgcopy(stream, binary)) => {
+                let format = if !binary {
+                    options.text_format()
+                } else if options.columns.is_empty() {
+                    CopyFormat::Binary(
+                        schema
+                            .fields()
+                            .iter()
+                            .map(|field| {
+                                PgType::from_data_type(field.data_type()).ok_or_else(|| {
+                                    ArrowError::SchemaError(format!(
+                                        "No binary COPY type for column \"{}\" of type {}",
+                                        field.name(),
+                                        field.data_type()
+                                    ))
+                                })
+                            })
+                            .collect::<Result<Vec<_>, _>>()?,
+                    )
+                } else {
+                    CopyFormat::Binary(
+                        options
+                            .columns
+                            .iter()
+                            .map(|column| column.pg_type)
+                            .collect(),
+                    )
+                };
+                Box::new(PgCopyReader::new(stream, format, schema))
+            }
+            (FormatOptions::Bson(_), Prepared::Stream(stream)) => {
+                Box::new(ValueReader::try_new(BsonDocuments::new(stream), schema)?)
+            }
+            #[cfg(feature = "yaml")]
+            (FormatOptions::Yaml(_), Prepared::Stream(stream)) => {
+                Box::new(ValueReader::try_new(YamlDocuments::new(stream), schema)?)
+            }
+            _ => unreachable!("the input is prepared for its format"),
+        })
+    }
+}
+
+/// The number of rows in a batch of the Arrow CSV and JSON readers.
+const BATCH_SIZE: usize = 1024;
+
+/// The number of rows of batches that fit in `memory` bytes, with a few batches in flight
+/// between reading and writing and a guess for columns without a fixed width.
+fn batch_size(schema: &Schema, memory: usize) -> usize {
+    let row: usize = schema
+        .fields()
+        .iter()
+        .map(|field| field.data_type().primitive_width().unwrap_or(64))
+        .sum();
+    (memory / 8 / row.max(1)).clamp(1, BATCH_SIZE)
+}
+
+/// Creates the file that records which can't be read are written to, if there is one.
+fn create_rejects(path: Option<&PathBuf>) -> Result<Option<Box<dyn Write>>, ArrowError> {
+    Ok(match path {
+        Some(path) => Some(Box::new(BufWriter::new(File::create(path)?))),
+        None => None,
+    })
+}
+
+/// The path of an input that can only be read from a file.
+#[cfg(any(feature = "xlsx", feature = "sqlite"))]
+fn file_path(path: &Option<PathBuf>, format: InputFormat) -> Result<&Path, ArrowError> {
+    path.as_deref().ok_or_else(|| {
+        ArrowError::InvalidArgumentError(format!(
+            "The {} format can only be read from a file",
+            format.name()
+        ))
+    })
+}
@@ -0,0 +1,255 @@
+//! Reading fixed-width text files as Arrow record batches.
+
+use crate::infer::{InferredDataType, StringBatchBuilder};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use regex::Regex;
+use std::borrow::Cow;
+use std::fs;
+use std::io::BufRead;
+use std::path::Path;
+use std::str::FromStr;
+
+const BATCH_SIZE: usize = 1024;
+
+/// The position and type of one column in a fixed-width record.
+///
+/// Parsed from `name:start:width[:type[:implied_decimals]]`, where `start` is the one-based
+/// byte offset of the column, `type` is an Arrow data type such as `Int64` or
+/// `Decimal128(9, 2)` (inferred if empty), and `implied_decimals` is the number of digits
+/// after an implied decimal point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FixedWidthColumn {
+    pub name: String,
+    pub start: usize,
+    pub width: usize,
+    pub data_type: Option<DataType>,
+    pub implied_decimals: Option<usize>,
+}
+
+impl FromStr for FixedWidthColumn {
+    type Err = ArrowError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            ArrowError::InvalidArgumentError(format!("Invalid column \"{spec}\": {reason}"))
+        };
+        let mut parts = spec.trim().splitn(5, ':');
+        let name = parts.next().filter(|name| !name.is_empty());
+        let name = name.ok_or_else(|| invalid("missing name"))?.to_string();
+        let start = parts
+            .next()
+            .and_then(|start| start.trim().parse::<usize>().ok())
+            .filter(|start| *start > 0)
+            .ok_or_else(|| invalid("start must be a positive number"))?;
+        let width = parts
+            .next()
+            .and_then(|width| width.trim().parse::<usize>().ok())
+            .filter(|width| *width > 0)
+            .ok_or_else(|| invalid("width must be a positive number"))?;
+        let data_type = match parts.next().map(str::trim) {
+            None | Some("") => None,
+            Some(data_type) => Some(DataType::from_str(data_type)?),
+        };
+        let implied_decimals = match parts.next().map(str::trim) {
+            None | Some("") => None,
+            Some(decimals) => Some(
+                decimals
+                    .parse::<usize>()
+                    .map_err(|_| invalid("implied decimals must be a number"))?,
+            ),
+        };
+        Ok(FixedWidthColumn {
+            name,
+            start: start - 1,
+            width,
+            data_type,
+            implied_decimals,
+        })
+    }
+}
+
+impl FixedWidthColumn {
+    /// Extracts the trimmed value of this column from a record, `None` if it is blank.
+    fn value<'a>(&self, record: &'a [u8]) -> Option<Cow<'a, str>> {
+        let end = record.len().min(self.start + self.width);
+        let bytes = record.get(self.start..end)?;
+        let value = match String::from_utf8_lossy(bytes) {
+            Cow::Borrowed(value) => Cow::Borrowed(value.trim()),
+            Cow::Owned(value) => Cow::Owned(value.trim().to_string()),
+        };
+        if value.is_empty() {
+            return None;
+        }
+        match self.implied_decimals {
+            Some(decimals) if !value.contains('.') => {
+                Some(Cow::Owned(insert_decimal_point(&value, decimals)))
+            }
+            _ => Some(value),
+        }
+    }
+}
+
+/// Turns `-0001234` into `-00012.34` for two implied decimals.
+fn insert_decimal_point(value: &str, decimals: usize) -> String {
+    let (sign, digits) = match value.strip_prefix(['-', '+']) {
+        Some(digits) => (&value[..1], digits),
+        None => ("", value),
+    };
+    let digits = format!("{digits:0>width$}", width = decimals + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - decimals);
+    format!("{sign}{integer}.{fraction}")
+}
+
+/// The columns of a fixed-width file.
+#[derive(Clone, Debug, Default)]
+pub struct Layout {
+    columns: Vec<FixedWidthColumn>,
+}
+
+impl Layout {
+    pub fn new(columns: Vec<FixedWidthColumn>) -> Self {
+        Layout { columns }
+    }
+
+    /// Reads a layout file with one column per line in the format of [`FixedWidthColumn`].
+    /// Blank lines and lines starting with `#` are ignored.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ArrowError> {
+        let path = path.as_ref();
+        let layout = fs::read_to_string(path).map_err(|error| {
+            ArrowError::IoError(
+                format!("Error opening layout file: {path:?}, message: {error}"),
+                error,
+            )
+        })?;
+        let columns = layout
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(FixedWidthColumn::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Layout { columns })
+    }
+
+    pub fn push(&mut self, column: FixedWidthColumn) {
+        self.columns.push(column);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    fn values<'a>(
+        &'a self,
+        record: &'a [u8],
+        null_regex: Option<&'a Regex>,
+    ) -> impl Iterator<Item = Option<Cow<'a, str>>> + 'a {
+        self.columns.iter().map(move |column| {
+            column
+                .value(record)
+                .filter(|value| !null_regex.is_some_and(|regex| regex.is_match(value)))
+        })
+    }
+
+    /// Infers the schema from the first `max_read_records` records, or all records if
+    /// `None`. Columns with a type in the layout keep that type.
+    pub fn infer_schema<R: BufRead>(
+        &self,
+        reader: &mut R,
+        max_read_records: Option<usize>,
+        null_regex: Option<&Regex>,
+    ) -> Result<Schema, ArrowError> {
+        let mut types = vec![InferredDataType::default(); self.columns.len()];
+        let mut record = Vec::new();
+        let mut read = 0;
+        while max_read_records.is_none_or(|max| read < max) && next_record(reader, &mut record)? {
+            for (inferred, value) in types.iter_mut().zip(self.values(&record, null_regex)) {
+                if let Some(value) = value {
+                    inferred.update(&value);
+                }
+            }
+            read += 1;
+        }
+        if max_read_records == Some(0) {
+            types.iter_mut().for_each(InferredDataType::update_utf8);
+        }
+        Ok(Schema::new(
+            self.columns
+                .iter()
+                .zip(types)
+                .map(|(column, inferred)| {
+                    let data_type = column
+                        .data_type
+                        .clone()
+                        .unwrap_or_else(|| inferred.data_type());
+                    Field::new(&column.name, data_type, true)
+                })
+                .collect::<Vec<_>>(),
+        ))
+    }
+}
+
+/// Reads the next line into `record` without the line terminator.
+fn next_record<R: BufRead>(reader: &mut R, record: &mut Vec<u8>) -> Result<bool, ArrowError> {
+    record.clear();
+    if reader.read_until(b'\n', record)? == 0 {
+        return Ok(false);
+    }
+    if record.last() == Some(&b'\n') {
+        record.pop();
+        if record.last() == Some(&b'\r') {
+            record.pop();
+        }
+    }
+    Ok(true)
+}
+
+/// Iterates over the records of a fixed-width file in record batches.
+pub struct FixedWidthReader<R> {
+    reader: R,
+    layout: Layout,
+    null_regex: Option<Regex>,
+    schema: SchemaRef,
+    builder: StringBatchBuilder,
+    record: Vec<u8>,
+}
+
+impl<R: BufRead> FixedWidthReader<R> {
+    pub fn new(reader: R, layout: Layout, schema: SchemaRef, null_regex: Option<Regex>) -> Self {
+        FixedWidthReader {
+            reader,
+            layout,
+            null_regex,
+            builder: StringBatchBuilder::new(schema.clone()),
+            schema,
+            record: Vec::new(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for FixedWidthReader<R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.builder.len() < BATCH_SIZE {
+            match next_record(&mut self.reader, &mut self.record) {
+                Ok(true) if self.record.is_empty() => continue,
+                Ok(true) => self
+                    .builder
+                    .append_row(self.layout.values(&self.record, self.null_regex.as_ref())),
+                Ok(false) => break,
+                Err(error) => return Some(Err(error)),
+            }
+        }
+        if self.builder.is_empty() {
+            return None;
+        }
+        Some(self.builder.finish())
+    }
+}
+
+impl<R: BufRead> RecordBatchReader for FixedWidthReader<R> {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
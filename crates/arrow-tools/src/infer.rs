@@ -0,0 +1,162 @@
+//! Schema inference and conversion for sources that yield text values,
+//! using the same rules as the Arrow CSV reader.
+
+use arrow::array::{new_null_array, Array, ArrayRef, StringBuilder};
+use arrow::compute::{cast_with_options, CastOptions};
+use arrow::csv::reader::Format;
+use arrow::datatypes::{DataType, SchemaRef, TimeUnit};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use regex::Regex;
+use std::sync::{Arc, LazyLock};
+
+/// The number of values that are collected before they are inferred with the CSV reader.
+const CHUNK_SIZE: usize = 1024;
+
+/// Matches no value, as callers pass values they read as null as `None`.
+static NO_NULLS: LazyLock<Regex> = LazyLock::new(|| Regex::new("[^\\s\\S]").unwrap());
+
+/// The narrowest Arrow type that fits every value seen so far in a column.
+///
+/// Values are written to a one-column CSV and inferred by the Arrow CSV reader in chunks.
+/// Each inferred chunk is replaced by a value of its type, so that the types of later chunks
+/// are merged the way the reader would have merged their values.
+#[derive(Default, Clone, Debug)]
+pub struct InferredDataType {
+    csv: Vec<u8>,
+    values: usize,
+    utf8: bool,
+}
+
+impl InferredDataType {
+    /// Returns the inferred data type, `Null` if no value has been seen.
+    pub fn data_type(&self) -> DataType {
+        if self.utf8 {
+            return DataType::Utf8;
+        }
+        Format::default()
+            .with_header(false)
+            .with_null_regex(NO_NULLS.clone())
+            .infer_schema(self.csv.as_slice(), None)
+            .ok()
+            .and_then(|(schema, _)| Some(schema.fields().first()?.data_type().clone()))
+            .unwrap_or(DataType::Null)
+    }
+
+    /// Updates the inferred type with a text value.
+    pub fn update(&mut self, value: &str) {
+        if self.utf8 {
+            return;
+        }
+        if self.values == CHUNK_SIZE {
+            let data_type = self.data_type();
+            self.csv.clear();
+            self.values = 0;
+            self.push(sample(&data_type));
+        }
+        self.push(value);
+    }
+
+    fn push(&mut self, value: &str) {
+        self.csv.push(b'"');
+        self.csv
+            .extend_from_slice(value.replace('"', "\"\"").as_bytes());
+        self.csv.extend_from_slice(b"\"\n");
+        self.values += 1;
+    }
+
+    /// Forces the column to be string typed, for values that are known to be text.
+    pub fn update_utf8(&mut self) {
+        self.utf8 = true;
+        self.csv = Vec::new();
+    }
+}
+
+/// A value that the CSV reader infers as the given type.
+fn sample(data_type: &DataType) -> &'static str {
+    match data_type {
+        DataType::Boolean => "true",
+        DataType::Int64 => "0",
+        DataType::Float64 => "0.5",
+        DataType::Date32 => "2000-01-01",
+        DataType::Timestamp(TimeUnit::Second, _) => "2000-01-01T00:00:00",
+        DataType::Timestamp(TimeUnit::Millisecond, _) => "2000-01-01T00:00:00.000",
+        DataType::Timestamp(TimeUnit::Microsecond, _) => "2000-01-01T00:00:00.000000",
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => "2000-01-01T00:00:00.000000000",
+        _ => "",
+    }
+}
+
+/// Collects rows of text values and converts them into record batches of a given schema.
+///
+/// Values are parsed with Arrow's cast kernels, so anything the CSV reader accepts for a
+/// type is accepted here as well.
+pub struct StringBatchBuilder {
+    schema: SchemaRef,
+    columns: Vec<StringBuilder>,
+    len: usize,
+}
+
+impl StringBatchBuilder {
+    pub fn new(schema: SchemaRef) -> Self {
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|_| StringBuilder::new())
+            .collect();
+        StringBatchBuilder {
+            schema,
+            columns,
+            len: 0,
+        }
+    }
+
+    /// Appends a row. Missing trailing values are null and extra values are ignored.
+    pub fn append_row<I, S>(&mut self, row: I)
+    where
+        I: IntoIterator<Item = Option<S>>,
+        S: AsRef<str>,
+    {
+        let mut values = row.into_iter();
+        for column in self.columns.iter_mut() {
+            column.append_option(values.next().flatten());
+        }
+        self.len += 1;
+    }
+
+    /// The number of rows appended since the last call to [`StringBatchBuilder::finish`].
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Converts the buffered rows into a record batch and resets the builder.
+    pub fn finish(&mut self) -> Result<RecordBatch, ArrowError> {
+        let options = CastOptions {
+            safe: false,
+            ..Default::default()
+        };
+        let columns = self
+            .columns
+            .iter_mut()
+            .zip(self.schema.fields())
+            .map(|(column, field)| {
+                let strings: ArrayRef = Arc::new(column.finish());
+                if field.data_type() == &DataType::Null {
+                    return Ok(new_null_array(&DataType::Null, strings.len()));
+                }
+                cast_with_options(&strings, field.data_type(), &options).map_err(|error| {
+                    ArrowError::CastError(format!(
+                        "Error converting column \"{}\": {error}",
+                        field.name()
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.len = 0;
+        RecordBatch::try_new(self.schema.clone(), columns)
+    }
+}
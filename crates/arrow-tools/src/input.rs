@@ -0,0 +1,397 @@
+//! Opening input files, with transparent decompression of gzipped files.
+
+use crate::progress::{Counted, CountingReader, Progress};
+use crate::seekable_reader::{SeekRead, SeekableReader};
+use flate2::read::MultiGzDecoder;
+use std::ffi::OsStr;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::Arc;
+
+/// The size of the blocks that are read ahead.
+const BLOCK_SIZE: usize = 256 << 10;
+/// The number of blocks that are read ahead before the reading thread waits.
+const BLOCKS_AHEAD: usize = 4;
+
+/// How an input is read, apart from the options of its format.
+#[derive(Clone, Debug, Default)]
+pub struct ReadOptions {
+    /// The memory in bytes to keep to, see
+    /// [`Converter::with_memory_limit`](crate::converter::Converter::with_memory_limit).
+    pub memory_limit: Option<usize>,
+    /// Counts the bytes that are read.
+    pub progress: Option<Arc<Progress>>,
+}
+
+impl ReadOptions {
+    /// The size in bytes past which the start of the input that is buffered for schema
+    /// inference is moved to a temporary file.
+    pub fn spill_limit(&self) -> Option<usize> {
+        self.memory_limit.map(|limit| limit / 4)
+    }
+
+    fn count<R: Read + 'static>(&self, reader: R, counted: Counted) -> Box<dyn Read> {
+        match self.progress {
+            Some(ref progress) => Box::new(CountingReader::new(reader, progress.clone(), counted)),
+            None => Box::new(reader),
+        }
+    }
+
+    fn count_send<R: Read + Send + 'static>(
+        &self,
+        reader: R,
+        counted: Counted,
+    ) -> Box<dyn Read + Send> {
+        match self.progress {
+            Some(ref progress) => Box::new(CountingReader::new(reader, progress.clone(), counted)),
+            None => Box::new(reader),
+        }
+    }
+}
+
+/// Where an input is read from.
+pub enum Source {
+    /// A file, decompressed if its name ends in `.gz`.
+    Path(PathBuf),
+    /// Any reader, e.g. stdin or a request body. Formats that infer the schema from the data
+    /// buffer it in memory up to `max_read_records` lines, or completely if `None`.
+    Reader(Box<dyn Read>),
+}
+
+impl Source {
+    /// The path of the file, if the source is a file.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Source::Path(path) => Some(path),
+            Source::Reader(_) => None,
+        }
+    }
+
+    /// Opens the source so it can be rewound after schema inference.
+    pub fn open(
+        self,
+        max_read_records: Option<usize>,
+        options: &ReadOptions,
+    ) -> io::Result<Box<dyn SeekRead>> {
+        match self {
+            Source::Path(path) => open(&path, max_read_records, options),
+            Source::Reader(reader) => {
+                let reader = options.count(reader, Counted::Both);
+                buffered(reader, max_read_records, options.spill_limit())
+            }
+        }
+    }
+
+    /// Opens the source to be read once from start to end.
+    pub fn open_stream(self, options: &ReadOptions) -> io::Result<Box<dyn Read>> {
+        match self {
+            Source::Path(path) => open_stream(&path, options),
+            Source::Reader(reader) => Ok(options.count(reader, Counted::Both)),
+        }
+    }
+}
+
+impl From<PathBuf> for Source {
+    fn from(path: PathBuf) -> Self {
+        Source::Path(path)
+    }
+}
+
+impl From<&Path> for Source {
+    fn from(path: &Path) -> Self {
+        Source::Path(path.to_path_buf())
+    }
+}
+
+impl From<&PathBuf> for Source {
+    fn from(path: &PathBuf) -> Self {
+        Source::Path(path.clone())
+    }
+}
+
+impl From<&str> for Source {
+    fn from(path: &str) -> Self {
+        Source::Path(PathBuf::from(path))
+    }
+}
+
+impl From<Box<dyn Read>> for Source {
+    fn from(reader: Box<dyn Read>) -> Self {
+        Source::Reader(reader)
+    }
+}
+
+/// Returns whether the file is gzipped, judging by its extension.
+pub fn is_gzip(path: &Path) -> bool {
+    path.extension() == Some(OsStr::new("gz"))
+}
+
+/// Opens an input file that can be rewound after schema inference.
+///
+/// Gzipped files and files that can't seek, like pipes, are buffered in memory up to
+/// `max_read_records` lines, or completely if `None`. With a memory limit, large buffers
+/// are kept in a temporary file instead.
+pub fn open(
+    path: &Path,
+    max_read_records: Option<usize>,
+    options: &ReadOptions,
+) -> io::Result<Box<dyn SeekRead>> {
+    let mut file = File::open(path)?;
+    let spill_limit = options.spill_limit();
+    if is_gzip(path) {
+        let decoder = ReadAhead::new(decoder(file, options));
+        buffered(decoder, max_read_records, spill_limit)
+    } else if file.rewind().is_ok() {
+        Ok(match options.progress {
+            Some(ref progress) => {
+                Box::new(CountingReader::new(file, progress.clone(), Counted::Both))
+            }
+            None => Box::new(file),
+        })
+    } else {
+        buffered(
+            options.count(file, Counted::Both),
+            max_read_records,
+            spill_limit,
+        )
+    }
+}
+
+/// Decompresses a gzipped file, counting the bytes on both sides.
+fn decoder(file: File, options: &ReadOptions) -> Box<dyn Read + Send> {
+    let file = options.count_send(file, Counted::Read);
+    options.count_send(MultiGzDecoder::new(file), Counted::Decompressed)
+}
+
+fn buffered<R: Read + 'static>(
+    reader: R,
+    max_read_records: Option<usize>,
+    spill_limit: Option<usize>,
+) -> io::Result<Box<dyn SeekRead>> {
+    Ok(match spill_limit {
+        Some(spill_limit) => Box::new(SpillingReader::new(reader, max_read_records, spill_limit)?),
+        None => Box::new(SeekableReader::from_unbuffered_reader(
+            reader,
+            max_read_records,
+        )?),
+    })
+}
+
+/// Opens an input file that is read once from start to end.
+pub fn open_stream(path: &Path, options: &ReadOptions) -> io::Result<Box<dyn Read>> {
+    let file = File::open(path)?;
+    Ok(if is_gzip(path) {
+        Box::new(ReadAhead::new(decoder(file, options)))
+    } else {
+        options.count(file, Counted::Both)
+    })
+}
+
+/// Reads from a reader on its own thread, a few blocks ahead of the consumer, so that e.g.
+/// decompression overlaps with parsing.
+pub struct ReadAhead {
+    blocks: Receiver<io::Result<Vec<u8>>>,
+    block: Vec<u8>,
+    position: usize,
+}
+
+impl ReadAhead {
+    pub fn new<R: Read + Send + 'static>(mut reader: R) -> Self {
+        let (sender, blocks) = sync_channel(BLOCKS_AHEAD);
+        std::thread::spawn(move || loop {
+            let mut block = Vec::with_capacity(BLOCK_SIZE);
+            let result = reader
+                .by_ref()
+                .take(BLOCK_SIZE as u64)
+                .read_to_end(&mut block);
+            let end = !matches!(result, Ok(length) if length > 0);
+            if sender.send(result.map(|_| block)).is_err() || end {
+                break;
+            }
+        });
+        ReadAhead {
+            blocks,
+            block: Vec::new(),
+            position: 0,
+        }
+    }
+}
+
+impl Read for ReadAhead {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.block.len() {
+            match self.blocks.recv() {
+                Ok(block) => {
+                    self.block = block?;
+                    self.position = 0;
+                }
+                // The thread stops after the end of the input.
+                Err(_) => return Ok(0),
+            }
+        }
+        let length = buf.len().min(self.block.len() - self.position);
+        buf[..length].copy_from_slice(&self.block[self.position..self.position + length]);
+        self.position += length;
+        Ok(length)
+    }
+}
+
+/// The start of a stream that is buffered so it can be read again, in memory or in a
+/// temporary file.
+trait Buffer: Read + Seek {}
+
+impl Buffer for Cursor<Vec<u8>> {}
+impl Buffer for SpillFile {}
+
+/// Like [`SeekableReader`], but moves the buffer to a temporary file once it grows past
+/// `spill_limit` bytes.
+pub struct SpillingReader<R> {
+    inner: R,
+    buffer: Box<dyn Buffer>,
+    buffered: u64,
+    seekable: bool,
+}
+
+impl<R: Read> SpillingReader<R> {
+    /// Buffers the first `lines_to_buffer` lines, plus one for a header, or all of them if
+    /// `None`.
+    pub fn new(
+        mut inner: R,
+        lines_to_buffer: Option<usize>,
+        spill_limit: usize,
+    ) -> io::Result<Self> {
+        let mut memory = Vec::new();
+        let mut spill: Option<SpillFile> = None;
+        let mut block = vec![0; BLOCK_SIZE];
+        let mut lines = 0;
+        let mut buffered = 0;
+        while lines_to_buffer.is_none_or(|lines_to_buffer| lines <= lines_to_buffer + 1) {
+            let read = match inner.read(&mut block) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            };
+            lines += block[..read].iter().filter(|&&byte| byte == b'\n').count();
+            buffered += read as u64;
+            match spill {
+                Some(ref mut file) => file.write_all(&block[..read])?,
+                None => {
+                    memory.extend_from_slice(&block[..read]);
+                    if memory.len() > spill_limit {
+                        let mut file = SpillFile::create()?;
+                        file.write_all(&std::mem::take(&mut memory))?;
+                        spill = Some(file);
+                    }
+                }
+            }
+        }
+
+        let buffer: Box<dyn Buffer> = match spill {
+            Some(mut file) => {
+                file.rewind()?;
+                Box::new(file)
+            }
+            None => Box::new(Cursor::new(memory)),
+        };
+        Ok(SpillingReader {
+            inner,
+            buffer,
+            buffered,
+            seekable: true,
+        })
+    }
+}
+
+impl<R: Read> Read for SpillingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.seekable {
+            let read = self.buffer.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+        }
+        let read = self.inner.read(buf)?;
+        // Reaching the end of a fully buffered input still allows a rewind.
+        if read > 0 {
+            self.seekable = false;
+        }
+        Ok(read)
+    }
+}
+
+impl<R: Read> Seek for SpillingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(position) => Some(position),
+            SeekFrom::Current(offset) => self.buffer.stream_position()?.checked_add_signed(offset),
+            SeekFrom::End(_) => None,
+        };
+        match position {
+            Some(position) if self.seekable && position <= self.buffered => {
+                self.buffer.seek(SeekFrom::Start(position))
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Seeking outside of the buffered start of the input",
+            )),
+        }
+    }
+}
+
+impl<R: Read> SeekRead for SpillingReader<R> {}
+impl<R: Read + Seek> SeekRead for CountingReader<R> {}
+
+/// A temporary file that is deleted when it is dropped.
+struct SpillFile {
+    file: File,
+    path: PathBuf,
+}
+
+impl SpillFile {
+    fn create() -> io::Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "arrow-tools-{}-{}.spill",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok(SpillFile { file, path })
+    }
+}
+
+impl Read for SpillFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Write for SpillFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Seek for SpillFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
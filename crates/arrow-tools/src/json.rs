@@ -0,0 +1,132 @@
+//! Reading JSON records with the position of the record that can't be read.
+//!
+//! The bytes of the records in a batch are kept until the batch is decoded. When decoding
+//! fails, they are decoded again one record at a time to find the one that fails.
+
+use crate::rejects::{Record, RecordFormat};
+use arrow::datatypes::SchemaRef;
+use arrow::error::ArrowError;
+use arrow::json::reader::Decoder;
+use arrow::json::ReaderBuilder;
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use std::io::BufRead;
+
+/// Reads JSON records into record batches, like arrow's reader, and reports errors with the
+/// record they happened in.
+pub struct JsonReader<R> {
+    reader: R,
+    schema: SchemaRef,
+    decoder: Decoder,
+    /// The bytes of the records in the decoder.
+    buffer: Vec<u8>,
+    /// The offset and line of the start of the buffer.
+    offset: u64,
+    line: u64,
+    /// The records that were returned, to give the position of errors in the input.
+    records: u64,
+}
+
+impl<R: BufRead> JsonReader<R> {
+    pub fn try_new(reader: R, schema: SchemaRef, batch_size: usize) -> Result<Self, ArrowError> {
+        let decoder = ReaderBuilder::new(schema.clone())
+            .with_batch_size(batch_size)
+            .build_decoder()?;
+        Ok(JsonReader {
+            reader,
+            schema,
+            decoder,
+            buffer: Vec::new(),
+            offset: 0,
+            line: 1,
+            records: 0,
+        })
+    }
+
+    fn read(&mut self) -> Result<Option<RecordBatch>, ArrowError> {
+        loop {
+            let buf = self.reader.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+            let read = buf.len();
+            let decoded = match self.decoder.decode(buf) {
+                Ok(decoded) => decoded,
+                Err(error) => {
+                    self.buffer.extend_from_slice(buf);
+                    return Err(self.locate(error));
+                }
+            };
+            self.buffer.extend_from_slice(&buf[..decoded]);
+            self.reader.consume(decoded);
+            if decoded != read {
+                break;
+            }
+        }
+        let batch = match self.decoder.flush() {
+            Ok(batch) => batch,
+            Err(error) => return Err(self.locate(error)),
+        };
+        self.records += batch.as_ref().map_or(0, |batch| batch.num_rows() as u64);
+        self.offset += self.buffer.len() as u64;
+        self.line += lines(&self.buffer);
+        self.buffer.clear();
+        Ok(batch)
+    }
+
+    /// Decodes the records in the buffer one at a time to find the first that fails, and
+    /// returns its error with its position.
+    fn locate(&self, error: ArrowError) -> ArrowError {
+        let Ok(mut decoder) = ReaderBuilder::new(self.schema.clone())
+            .with_batch_size(1)
+            .build_decoder()
+        else {
+            return error;
+        };
+        let mut position = 0;
+        let mut index = self.records;
+        while position < self.buffer.len() {
+            let rest = &self.buffer[position..];
+            let start = position + rest.iter().take_while(|b| b.is_ascii_whitespace()).count();
+            if start == self.buffer.len() {
+                break;
+            }
+            // A record that isn't valid JSON is shown up to the end of the buffer.
+            let (result, end) = match decoder.decode(rest) {
+                Ok(read) => {
+                    position += read;
+                    (decoder.flush(), position)
+                }
+                Err(error) => (Err(error), self.buffer.len()),
+            };
+            if let Err(error) = result {
+                let record = Record {
+                    offset: self.offset + start as u64,
+                    line: self.line + lines(&self.buffer[..start]),
+                    index,
+                    bytes: self.buffer[start..end].trim_ascii_end().to_vec(),
+                };
+                return record.error(&RecordFormat::Json, &self.schema, error);
+            }
+            index += 1;
+        }
+        error
+    }
+}
+
+fn lines(bytes: &[u8]) -> u64 {
+    bytes.iter().filter(|&&byte| byte == b'\n').count() as u64
+}
+
+impl<R: BufRead> Iterator for JsonReader<R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read().transpose()
+    }
+}
+
+impl<R: BufRead> RecordBatchReader for JsonReader<R> {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
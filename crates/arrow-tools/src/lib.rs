@@ -2,6 +2,44 @@
 //! This crate serves a general util library to go along
 //! with all of the crates within the arrow-tools suite.
 
+#[cfg(feature = "cli")]
+pub mod batch;
+pub mod bson;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+#[cfg(feature = "cli")]
+pub mod cli;
+#[cfg(feature = "cli")]
+pub mod config;
+pub mod converter;
+pub mod csv;
+pub mod error;
+pub mod formats;
+pub mod fwf;
+pub mod infer;
+pub mod input;
+pub mod json;
+pub mod logs;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+pub mod pgcopy;
+pub mod progress;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
+pub mod rejects;
+pub mod report;
+pub mod schema;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod values;
+pub mod writer;
+#[cfg(feature = "xlsx")]
+pub mod xlsx;
+#[cfg(feature = "xml")]
+pub mod xml;
+#[cfg(feature = "yaml")]
+pub mod yaml;
+
 pub mod seekable_reader {
     use std::fs;
     use std::io;
@@ -18,12 +56,14 @@ pub mod seekable_reader {
     }
 
     impl SeekRead for fs::File {}
-    impl SeekRead for SeekableReader<fs::File> {}
-    impl SeekRead for SeekableReader<flate2::read::MultiGzDecoder<fs::File>> {}
+    impl<R: io::Read> SeekRead for SeekableReader<R> {}
 
     const BUFFER_SIZE: usize = 8192;
     impl<R: std::io::Read> SeekableReader<R> {
-        pub fn from_unbuffered_reader(reader: R, lines_to_buffer: Option<usize>) -> Self {
+        pub fn from_unbuffered_reader(
+            reader: R,
+            lines_to_buffer: Option<usize>,
+        ) -> io::Result<Self> {
             let mut inner = reader;
             let mut buffer = Vec::<u8>::with_capacity(BUFFER_SIZE);
             let mut lines = 0;
@@ -31,9 +71,7 @@ pub mod seekable_reader {
             loop {
                 let bytes_before = bytes_read;
                 buffer.append(&mut vec![0; BUFFER_SIZE - (buffer.len() - bytes_read)]);
-                bytes_read += inner
-                    .read(&mut buffer[bytes_read..bytes_read + BUFFER_SIZE])
-                    .unwrap();
+                bytes_read += inner.read(&mut buffer[bytes_read..bytes_read + BUFFER_SIZE])?;
                 lines += buffer[bytes_before..bytes_read]
                     .iter()
                     .filter(|&&x| x == 10)
@@ -48,20 +86,20 @@ pub mod seekable_reader {
                     break;
                 }
             }
-            SeekableReader {
+            Ok(SeekableReader {
                 inner,
                 buffer,
                 buffered_bytes: bytes_read,
                 pos: 0,
                 seekable: true,
-            }
+            })
         }
     }
 
     impl<R: std::io::Read> std::io::Read for SeekableReader<R> {
         fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
             let buf_len = buf.len();
-            if self.pos <= self.buffered_bytes {
+            if self.pos < self.buffered_bytes {
                 if self.buffered_bytes - self.pos < buf_len {
                     buf[..self.buffered_bytes - self.pos]
                         .copy_from_slice(&self.buffer[self.pos..self.buffered_bytes]);
@@ -74,8 +112,12 @@ pub mod seekable_reader {
                     Ok(buf_len)
                 }
             } else {
-                self.seekable = false;
-                self.inner.read(buf)
+                let len_read = self.inner.read(buf)?;
+                // Reaching the end of a fully buffered input still allows a rewind
+                if len_read > 0 {
+                    self.seekable = false;
+                }
+                Ok(len_read)
             }
         }
     }
@@ -89,7 +131,7 @@ pub mod seekable_reader {
             if self.seekable {
                 match pos {
                     io::SeekFrom::Start(pos) => {
-                        if pos >= self.buffered_bytes as u64 {
+                        if pos > self.buffered_bytes as u64 {
                             error
                         } else {
                             self.pos = pos as usize;
@@ -98,7 +140,7 @@ pub mod seekable_reader {
                     }
                     io::SeekFrom::Current(pos) => {
                         let new_pos = self.pos as i64 + pos;
-                        if 0 <= new_pos && new_pos < self.buffered_bytes as i64 {
+                        if 0 <= new_pos && new_pos <= self.buffered_bytes as i64 {
                             self.pos = new_pos as usize;
                             Ok(new_pos as u64)
                         } else {
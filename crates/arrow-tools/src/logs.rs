@@ -0,0 +1,219 @@
+//! Parsing line-oriented log files into Arrow record batches with regular expressions.
+
+use crate::infer::{InferredDataType, StringBatchBuilder};
+use arrow::datatypes::{Field, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use regex::Regex;
+use std::io::{BufRead, Write};
+
+const BATCH_SIZE: usize = 1024;
+
+/// Well-known log formats.
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum LogFormat {
+    /// The Apache/Nginx common log format.
+    Common,
+    /// The Apache/Nginx combined log format, i.e. common plus referer and user agent.
+    #[default]
+    Combined,
+    /// BSD syslog lines as described in RFC 3164.
+    Syslog,
+}
+
+impl LogFormat {
+    /// The regular expression with one named capture group per column.
+    pub fn pattern(&self) -> &'static str {
+        match self {
+            LogFormat::Common => {
+                r#"^(?P<host>\S+) (?P<ident>\S+) (?P<user>\S+) \[(?P<time>[^\]]+)\] "(?P<method>[A-Z]+) (?P<path>\S+) (?P<protocol>[^"]*)" (?P<status>\d{3}) (?P<size>\S+)$"#
+            }
+            LogFormat::Combined => {
+                r#"^(?P<host>\S+) (?P<ident>\S+) (?P<user>\S+) \[(?P<time>[^\]]+)\] "(?P<method>[A-Z]+) (?P<path>\S+) (?P<protocol>[^"]*)" (?P<status>\d{3}) (?P<size>\S+) "(?P<referer>[^"]*)" "(?P<user_agent>[^"]*)"$"#
+            }
+            LogFormat::Syslog => {
+                r"^(?:<(?P<priority>\d{1,3})>)?(?P<timestamp>[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}) (?P<host>\S+) (?P<program>[^:\[\s]+)(?:\[(?P<pid>\d+)\])?: (?P<message>.*)$"
+            }
+        }
+    }
+
+    /// The default pattern for null values. The Apache formats use `-` for missing fields.
+    pub fn null_pattern(&self) -> Option<&'static str> {
+        match self {
+            LogFormat::Common | LogFormat::Combined => Some("^-$"),
+            LogFormat::Syslog => None,
+        }
+    }
+}
+
+/// Splits log lines into columns with the named capture groups of a regular expression.
+pub struct LogParser {
+    regex: Regex,
+    null_regex: Option<Regex>,
+}
+
+impl LogParser {
+    pub fn new(regex: Regex, null_regex: Option<Regex>) -> Result<Self, ArrowError> {
+        let unnamed = regex.capture_names().skip(1).any(|name| name.is_none());
+        if regex.capture_names().flatten().next().is_none() || unnamed {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "Every capture group of the regex needs a name, e.g. (?P<status>\\d+): {regex}"
+            )));
+        }
+        Ok(LogParser { regex, null_regex })
+    }
+
+    /// The column names, in the order of the capture groups.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.regex.capture_names().flatten()
+    }
+
+    /// Returns the values of a line, or `None` if it doesn't match.
+    fn values<'a>(&self, line: &'a str) -> Option<Vec<Option<&'a str>>> {
+        let captures = self.regex.captures(line)?;
+        let values = captures.iter().skip(1).map(|value| {
+            value.map(|value| value.as_str()).filter(|value| {
+                !value.is_empty() && !self.null_regex.as_ref().is_some_and(|r| r.is_match(value))
+            })
+        });
+        Some(values.collect())
+    }
+
+    /// Infers the schema from the first `max_read_records` matching lines, or all lines if
+    /// `None`.
+    pub fn infer_schema<R: BufRead>(
+        &self,
+        reader: &mut R,
+        max_read_records: Option<usize>,
+    ) -> Result<Schema, ArrowError> {
+        let mut types = vec![InferredDataType::default(); self.names().count()];
+        let mut line = Vec::new();
+        let mut read = 0;
+        while max_read_records.is_none_or(|max| read < max) && next_line(reader, &mut line)? {
+            let line = String::from_utf8_lossy(&line);
+            if let Some(values) = self.values(&line) {
+                for (inferred, value) in types.iter_mut().zip(values) {
+                    if let Some(value) = value {
+                        inferred.update(value);
+                    }
+                }
+                read += 1;
+            }
+        }
+        if max_read_records == Some(0) {
+            types.iter_mut().for_each(InferredDataType::update_utf8);
+        }
+        Ok(Schema::new(
+            self.names()
+                .zip(types)
+                .map(|(name, inferred)| Field::new(name, inferred.data_type(), true))
+                .collect::<Vec<_>>(),
+        ))
+    }
+}
+
+/// Reads the next line into `line` without the line terminator.
+fn next_line<R: BufRead>(reader: &mut R, line: &mut Vec<u8>) -> Result<bool, ArrowError> {
+    line.clear();
+    if reader.read_until(b'\n', line)? == 0 {
+        return Ok(false);
+    }
+    if line.last() == Some(&b'\n') {
+        line.pop();
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+    }
+    Ok(true)
+}
+
+/// Iterates over the matching lines of a log file in record batches.
+///
+/// Lines that don't match are counted and written to the reject writer if there is one.
+pub struct LogReader<R> {
+    reader: R,
+    parser: LogParser,
+    schema: SchemaRef,
+    builder: StringBatchBuilder,
+    rejects: Option<Box<dyn Write>>,
+    rejected: usize,
+    line: Vec<u8>,
+}
+
+impl<R: BufRead> LogReader<R> {
+    pub fn new(
+        reader: R,
+        parser: LogParser,
+        schema: SchemaRef,
+        rejects: Option<Box<dyn Write>>,
+    ) -> Self {
+        LogReader {
+            reader,
+            parser,
+            builder: StringBatchBuilder::new(schema.clone()),
+            schema,
+            rejects,
+            rejected: 0,
+            line: Vec::new(),
+        }
+    }
+
+    /// The number of lines that did not match so far.
+    pub fn rejected(&self) -> usize {
+        self.rejected
+    }
+
+    fn reject(&mut self) -> Result<(), ArrowError> {
+        self.rejected += 1;
+        if let Some(rejects) = self.rejects.as_mut() {
+            rejects.write_all(&self.line)?;
+            rejects.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for LogReader<R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.builder.len() < BATCH_SIZE {
+            match next_line(&mut self.reader, &mut self.line) {
+                Ok(true) if self.line.is_empty() => continue,
+                Ok(true) => {
+                    let matched = {
+                        let line = String::from_utf8_lossy(&self.line);
+                        match self.parser.values(&line) {
+                            Some(values) => {
+                                self.builder.append_row(values);
+                                true
+                            }
+                            None => false,
+                        }
+                    };
+                    if !matched {
+                        if let Err(error) = self.reject() {
+                            return Some(Err(error));
+                        }
+                    }
+                }
+                Ok(false) => break,
+                Err(error) => return Some(Err(error)),
+            }
+        }
+        if let Some(Err(error)) = self.rejects.as_mut().map(|rejects| rejects.flush()) {
+            return Some(Err(error.into()));
+        }
+        if self.builder.is_empty() {
+            return None;
+        }
+        Some(self.builder.finish())
+    }
+}
+
+impl<R: BufRead> RecordBatchReader for LogReader<R> {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
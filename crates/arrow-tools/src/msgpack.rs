@@ -0,0 +1,117 @@
+//! Reading concatenated MessagePack documents as JSON values.
+
+use crate::values::{float, hex, timestamp};
+use arrow::error::ArrowError;
+use rmpv::Value as MessagePackValue;
+use serde_json::{Map, Value};
+use std::io::BufRead;
+
+/// The extension type of MessagePack timestamps.
+const TIMESTAMP_EXT: i8 = -1;
+
+/// Iterates over a stream of concatenated MessagePack documents.
+///
+/// Binary data and unknown extension types become hex strings, timestamps become RFC 3339
+/// strings and map keys that aren't strings are converted to strings.
+pub struct MessagePackValues<R> {
+    reader: R,
+    offset: u64,
+}
+
+impl<R: BufRead> MessagePackValues<R> {
+    pub fn new(reader: R) -> Self {
+        MessagePackValues { reader, offset: 0 }
+    }
+}
+
+impl<R: BufRead> Iterator for MessagePackValues<R> {
+    type Item = Result<Value, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.reader.fill_buf() {
+            Ok([]) => return None,
+            Ok(_) => {}
+            Err(error) => return Some(Err(error.into())),
+        }
+        let mut reader = CountingReader {
+            inner: &mut self.reader,
+            count: 0,
+        };
+        let value = rmpv::decode::read_value(&mut reader);
+        let start = self.offset;
+        self.offset += reader.count;
+        Some(value.map(to_json).map_err(|error| {
+            ArrowError::ParseError(format!(
+                "Error reading MessagePack document at byte {start}: {error}"
+            ))
+        }))
+    }
+}
+
+/// Counts the bytes read so errors can point at the document that failed.
+struct CountingReader<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R: std::io::Read> std::io::Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count += read as u64;
+        Ok(read)
+    }
+}
+
+fn to_json(value: MessagePackValue) -> Value {
+    match value {
+        MessagePackValue::Nil => Value::Null,
+        MessagePackValue::Boolean(value) => Value::Bool(value),
+        MessagePackValue::Integer(value) => match (value.as_i64(), value.as_u64()) {
+            (Some(value), _) => Value::from(value),
+            (None, Some(value)) => Value::from(value),
+            (None, None) => Value::Null,
+        },
+        MessagePackValue::F32(value) => float(value.into()),
+        MessagePackValue::F64(value) => float(value),
+        MessagePackValue::String(value) => match value.into_str() {
+            Some(value) => Value::String(value),
+            None => Value::Null,
+        },
+        MessagePackValue::Binary(value) => hex(&value),
+        MessagePackValue::Array(values) => Value::Array(values.into_iter().map(to_json).collect()),
+        MessagePackValue::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key_string(key), to_json(value)))
+                .collect::<Map<_, _>>(),
+        ),
+        MessagePackValue::Ext(TIMESTAMP_EXT, data) => ext_timestamp(&data),
+        MessagePackValue::Ext(_, data) => hex(&data),
+    }
+}
+
+fn key_string(key: MessagePackValue) -> String {
+    match key {
+        MessagePackValue::String(key) if key.is_str() => key.into_str().unwrap(),
+        key => key.to_string(),
+    }
+}
+
+/// Decodes the 32, 64 and 96 bit timestamp extension formats.
+fn ext_timestamp(data: &[u8]) -> Value {
+    match data.len() {
+        4 => timestamp(u32::from_be_bytes(data.try_into().unwrap()).into(), 0),
+        8 => {
+            let value = u64::from_be_bytes(data.try_into().unwrap());
+            timestamp((value & 0x3_ffff_ffff) as i64, (value >> 34) as u32)
+        }
+        12 => {
+            let nanoseconds = u32::from_be_bytes(data[..4].try_into().unwrap());
+            timestamp(
+                i64::from_be_bytes(data[4..].try_into().unwrap()),
+                nanoseconds,
+            )
+        }
+        _ => hex(data),
+    }
+}
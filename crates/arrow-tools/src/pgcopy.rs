@@ -0,0 +1,663 @@
+//! Reading the text and binary formats of PostgreSQL `COPY ... TO`.
+//!
+//! Values of both formats are converted to their text representation and then cast to the
+//! Arrow type of the column, so both formats accept the same types.
+
+use crate::infer::InferredDataType;
+use arrow::array::{new_null_array, Array, ArrayRef, BinaryBuilder, StringArray, StringBuilder};
+use arrow::compute::{cast_with_options, CastOptions};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use arrow::error::ArrowError;
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use chrono::{DateTime, NaiveTime};
+use std::fmt::Write as _;
+use std::io::BufRead;
+use std::str::FromStr;
+use std::sync::Arc;
+
+const BATCH_SIZE: usize = 1024;
+
+/// The signature at the start of the binary format.
+pub const BINARY_SIGNATURE: &[u8; 11] = b"PGCOPY\n\xff\r\n\0";
+
+/// Microseconds between the Unix and PostgreSQL epochs, 1970-01-01 and 2000-01-01.
+const EPOCH_OFFSET_MICROS: i64 = 946_684_800_000_000;
+/// Days between the Unix and PostgreSQL epochs.
+const EPOCH_OFFSET_DAYS: i64 = 10_957;
+
+/// A PostgreSQL column type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PgType {
+    Bool,
+    Int2,
+    Int4,
+    Int8,
+    Float4,
+    Float8,
+    /// `numeric(precision, scale)` or `numeric` without a precision, which is kept as text.
+    Numeric(Option<(u8, i8)>),
+    Text,
+    Bytea,
+    Date,
+    Time,
+    Timestamp,
+    TimestampTz,
+    Uuid,
+    Json,
+    Jsonb,
+}
+
+impl FromStr for PgType {
+    type Err = ArrowError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let name = name.trim().to_lowercase();
+        let (base, modifier) = match name.split_once('(') {
+            Some((base, modifier)) => (base.trim(), Some(modifier.trim_end_matches(')'))),
+            None => (name.as_str(), None),
+        };
+        Ok(match base {
+            "bool" | "boolean" => PgType::Bool,
+            "int2" | "smallint" => PgType::Int2,
+            "int4" | "int" | "integer" => PgType::Int4,
+            "int8" | "bigint" => PgType::Int8,
+            "float4" | "real" => PgType::Float4,
+            "float8" | "double precision" => PgType::Float8,
+            "numeric" | "decimal" => PgType::Numeric(match modifier {
+                None => None,
+                Some(modifier) => {
+                    let mut parts = modifier.split(',').map(|part| part.trim().parse::<u8>());
+                    match (parts.next(), parts.next()) {
+                        (Some(Ok(precision)), None) => Some((precision, 0)),
+                        (Some(Ok(precision)), Some(Ok(scale))) => Some((precision, scale as i8)),
+                        _ => {
+                            return Err(ArrowError::InvalidArgumentError(format!(
+                                "Invalid numeric type \"{name}\""
+                            )))
+                        }
+                    }
+                }
+            }),
+            "text" | "varchar" | "character varying" | "char" | "character" | "bpchar" | "name"
+            | "citext" => PgType::Text,
+            "bytea" => PgType::Bytea,
+            "date" => PgType::Date,
+            "time" | "time without time zone" => PgType::Time,
+            "timestamp" | "timestamp without time zone" => PgType::Timestamp,
+            "timestamptz" | "timestamp with time zone" => PgType::TimestampTz,
+            "uuid" => PgType::Uuid,
+            "json" => PgType::Json,
+            "jsonb" => PgType::Jsonb,
+            _ => {
+                return Err(ArrowError::InvalidArgumentError(format!(
+                    "Unsupported PostgreSQL type \"{name}\""
+                )))
+            }
+        })
+    }
+}
+
+impl PgType {
+    /// The Arrow type the column is converted to.
+    pub fn data_type(&self) -> DataType {
+        match self {
+            PgType::Bool => DataType::Boolean,
+            PgType::Int2 => DataType::Int16,
+            PgType::Int4 => DataType::Int32,
+            PgType::Int8 => DataType::Int64,
+            PgType::Float4 => DataType::Float32,
+            PgType::Float8 => DataType::Float64,
+            PgType::Numeric(Some((precision, scale))) => DataType::Decimal128(*precision, *scale),
+            PgType::Numeric(None) | PgType::Text | PgType::Uuid | PgType::Json | PgType::Jsonb => {
+                DataType::Utf8
+            }
+            PgType::Bytea => DataType::Binary,
+            PgType::Date => DataType::Date32,
+            PgType::Time => DataType::Time64(TimeUnit::Microsecond),
+            PgType::Timestamp => DataType::Timestamp(TimeUnit::Microsecond, None),
+            PgType::TimestampTz => {
+                DataType::Timestamp(TimeUnit::Microsecond, Some("+00:00".into()))
+            }
+        }
+    }
+
+    /// The PostgreSQL type for an Arrow type, used to read the binary format with a schema
+    /// file. Strings are read as `text`, so `jsonb` columns need to be given explicitly.
+    pub fn from_data_type(data_type: &DataType) -> Option<Self> {
+        Some(match data_type {
+            DataType::Boolean => PgType::Bool,
+            DataType::Int16 => PgType::Int2,
+            DataType::Int32 => PgType::Int4,
+            DataType::Int64 => PgType::Int8,
+            DataType::Float32 => PgType::Float4,
+            DataType::Float64 => PgType::Float8,
+            DataType::Decimal128(precision, scale) => PgType::Numeric(Some((*precision, *scale))),
+            DataType::Utf8 => PgType::Text,
+            DataType::Binary => PgType::Bytea,
+            DataType::Date32 => PgType::Date,
+            DataType::Time64(TimeUnit::Microsecond) => PgType::Time,
+            DataType::Timestamp(TimeUnit::Microsecond, None) => PgType::Timestamp,
+            DataType::Timestamp(TimeUnit::Microsecond, Some(_)) => PgType::TimestampTz,
+            _ => return None,
+        })
+    }
+
+    /// Converts a value of the binary format to its text representation.
+    fn binary_to_text(&self, value: &[u8]) -> Result<String, ArrowError> {
+        let invalid = || {
+            ArrowError::ParseError(format!(
+                "Invalid binary value for type {self:?}: {} bytes",
+                value.len()
+            ))
+        };
+        macro_rules! number {
+            ($type:ty) => {
+                <$type>::from_be_bytes(value.try_into().map_err(|_| invalid())?)
+            };
+        }
+        Ok(match self {
+            PgType::Bool => (value.first().ok_or_else(invalid)? != &0).to_string(),
+            PgType::Int2 => number!(i16).to_string(),
+            PgType::Int4 => number!(i32).to_string(),
+            PgType::Int8 => number!(i64).to_string(),
+            PgType::Float4 => number!(f32).to_string(),
+            PgType::Float8 => number!(f64).to_string(),
+            PgType::Numeric(_) => numeric_to_text(value).ok_or_else(invalid)?,
+            PgType::Text | PgType::Json => String::from_utf8_lossy(value).into_owned(),
+            PgType::Jsonb => {
+                String::from_utf8_lossy(value.get(1..).ok_or_else(invalid)?).into_owned()
+            }
+            PgType::Bytea => format!("\\x{}", hex(value)),
+            PgType::Uuid => {
+                let hex = hex(value);
+                if hex.len() != 32 {
+                    return Err(invalid());
+                }
+                format!(
+                    "{}-{}-{}-{}-{}",
+                    &hex[..8],
+                    &hex[8..12],
+                    &hex[12..16],
+                    &hex[16..20],
+                    &hex[20..]
+                )
+            }
+            PgType::Date => {
+                let days = i64::from(number!(i32)) + EPOCH_OFFSET_DAYS;
+                let date = DateTime::from_timestamp(days * 86_400, 0).ok_or_else(invalid)?;
+                date.date_naive().to_string()
+            }
+            PgType::Time => {
+                let micros = number!(i64);
+                let seconds = u32::try_from(micros / 1_000_000).map_err(|_| invalid())?;
+                let nanos = (micros % 1_000_000) as u32 * 1_000;
+                let time = NaiveTime::from_num_seconds_from_midnight_opt(seconds, nanos);
+                time.ok_or_else(invalid)?.format("%H:%M:%S%.f").to_string()
+            }
+            PgType::Timestamp | PgType::TimestampTz => {
+                let micros = number!(i64).checked_add(EPOCH_OFFSET_MICROS);
+                let time = micros.and_then(DateTime::from_timestamp_micros);
+                let time = time.ok_or_else(invalid)?.naive_utc();
+                let time = time.format("%Y-%m-%dT%H:%M:%S%.f").to_string();
+                match self {
+                    PgType::TimestampTz => time + "+00:00",
+                    _ => time,
+                }
+            }
+        })
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+/// Formats the binary `numeric` representation: digits in base 10000 with the weight of the
+/// first digit, a sign and the number of decimal digits to display.
+fn numeric_to_text(value: &[u8]) -> Option<String> {
+    let word = |index: usize| -> Option<i16> {
+        Some(i16::from_be_bytes(
+            value.get(index * 2..index * 2 + 2)?.try_into().ok()?,
+        ))
+    };
+    let digit_count = usize::try_from(word(0)?).ok()?;
+    let weight = i32::from(word(1)?);
+    let sign = word(2)? as u16;
+    let scale = usize::try_from(word(3)?).ok()?;
+    let digits = (0..digit_count)
+        .map(|index| word(index + 4))
+        .collect::<Option<Vec<_>>>()?;
+    match sign {
+        0xC000 => return Some("NaN".to_string()),
+        0xD000 => return Some("Infinity".to_string()),
+        0xF000 => return Some("-Infinity".to_string()),
+        _ => {}
+    }
+    // digit at position `weight - index` is `digits[index]`
+    let digit = |position: i32| -> i16 {
+        usize::try_from(weight - position)
+            .ok()
+            .and_then(|index| digits.get(index).copied())
+            .unwrap_or(0)
+    };
+    let mut text = String::new();
+    if sign == 0x4000 {
+        text.push('-');
+    }
+    if weight < 0 {
+        text.push('0');
+    } else {
+        let _ = write!(text, "{}", digit(weight));
+        for position in (0..weight).rev() {
+            let _ = write!(text, "{:04}", digit(position));
+        }
+    }
+    if scale > 0 {
+        let mut fraction = String::new();
+        let mut position = -1;
+        while fraction.len() < scale {
+            let _ = write!(fraction, "{:04}", digit(position));
+            position -= 1;
+        }
+        fraction.truncate(scale);
+        text.push('.');
+        text.push_str(&fraction);
+    }
+    Some(text)
+}
+
+/// Decodes the backslash escapes of the text format. Escaped bytes can be parts of UTF-8
+/// sequences, so the value is decoded after unescaping.
+fn unescape(value: &str) -> Result<String, ArrowError> {
+    let mut unescaped = Vec::with_capacity(value.len());
+    let mut bytes = value.bytes().peekable();
+    while let Some(byte) = bytes.next() {
+        if byte != b'\\' {
+            unescaped.push(byte);
+            continue;
+        }
+        let Some(escaped) = bytes.next() else {
+            unescaped.push(b'\\');
+            break;
+        };
+        match escaped {
+            b'b' => unescaped.push(b'\x08'),
+            b'f' => unescaped.push(b'\x0c'),
+            b'n' => unescaped.push(b'\n'),
+            b'r' => unescaped.push(b'\r'),
+            b't' => unescaped.push(b'\t'),
+            b'v' => unescaped.push(b'\x0b'),
+            b'x' if bytes.peek().is_some_and(u8::is_ascii_hexdigit) => {
+                let mut code = 0;
+                for _ in 0..2 {
+                    match bytes.peek().and_then(|&byte| char::from(byte).to_digit(16)) {
+                        Some(digit) => {
+                            code = code * 16 + digit;
+                            bytes.next();
+                        }
+                        None => break,
+                    }
+                }
+                unescaped.push(code as u8);
+            }
+            b'0'..=b'7' => {
+                let mut code = u32::from(escaped - b'0');
+                for _ in 0..2 {
+                    match bytes.peek().and_then(|&byte| char::from(byte).to_digit(8)) {
+                        Some(digit) => {
+                            code = code * 8 + digit;
+                            bytes.next();
+                        }
+                        None => break,
+                    }
+                }
+                unescaped.push(code as u8);
+            }
+            other => unescaped.push(other),
+        }
+    }
+    String::from_utf8(unescaped).map_err(|error| {
+        ArrowError::ParseError(format!(
+            "Invalid UTF-8 in the COPY value \"{value}\": {error}"
+        ))
+    })
+}
+
+/// Splits a line of the text format at the delimiters that aren't escaped with a backslash.
+/// The values keep their escapes.
+fn split_values(line: &str, delimiter: char) -> Vec<&str> {
+    let mut values = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (index, char) in line.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if char == '\\' {
+            escaped = true;
+        } else if char == delimiter {
+            values.push(&line[start..index]);
+            start = index + char.len_utf8();
+        }
+    }
+    values.push(&line[start..]);
+    values
+}
+
+/// The format of a `COPY` file.
+#[derive(Clone, Debug)]
+pub enum CopyFormat {
+    /// Delimited lines with backslash escapes and a marker for null values.
+    Text { delimiter: u8, null: String },
+    /// The binary format, which needs the column types to decode values.
+    Binary(Vec<PgType>),
+}
+
+impl CopyFormat {
+    /// The defaults of `COPY ... TO` for the text format, tabs and `\N`.
+    pub fn text() -> Self {
+        CopyFormat::Text {
+            delimiter: b'\t',
+            null: "\\N".to_string(),
+        }
+    }
+}
+
+/// Returns whether the input starts with the signature of the binary format.
+pub fn is_binary<R: BufRead>(reader: &mut R) -> Result<bool, ArrowError> {
+    Ok(reader.fill_buf()?.starts_with(BINARY_SIGNATURE))
+}
+
+/// Reads rows of text values from either format.
+struct Rows<R> {
+    reader: R,
+    format: CopyFormat,
+    started: bool,
+    line: Vec<u8>,
+}
+
+impl<R: BufRead> Rows<R> {
+    fn new(reader: R, format: CopyFormat) -> Self {
+        Rows {
+            reader,
+            format,
+            started: false,
+            line: Vec::new(),
+        }
+    }
+
+    fn next_row(&mut self) -> Result<Option<Vec<Option<String>>>, ArrowError> {
+        match &self.format {
+            CopyFormat::Text { .. } => self.next_text_row(),
+            CopyFormat::Binary(_) => self.next_binary_row(),
+        }
+    }
+
+    fn next_text_row(&mut self) -> Result<Option<Vec<Option<String>>>, ArrowError> {
+        let CopyFormat::Text { delimiter, null } = &self.format else {
+            unreachable!()
+        };
+        self.line.clear();
+        if self.reader.read_until(b'\n', &mut self.line)? == 0 {
+            return Ok(None);
+        }
+        if self.line.last() == Some(&b'\n') {
+            self.line.pop();
+            if self.line.last() == Some(&b'\r') {
+                self.line.pop();
+            }
+        }
+        if self.line == b"\\." {
+            return Ok(None);
+        }
+        let line = String::from_utf8_lossy(&self.line);
+        split_values(&line, char::from(*delimiter))
+            .into_iter()
+            .map(|value| (value != null).then(|| unescape(value)).transpose())
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some)
+    }
+
+    fn read_bytes(&mut self, length: usize) -> Result<Vec<u8>, ArrowError> {
+        let mut bytes = vec![0; length];
+        self.reader.read_exact(&mut bytes).map_err(|error| {
+            ArrowError::ParseError(format!("Unexpected end of binary COPY data: {error}"))
+        })?;
+        Ok(bytes)
+    }
+
+    fn read_i32(&mut self) -> Result<i32, ArrowError> {
+        Ok(i32::from_be_bytes(self.read_bytes(4)?.try_into().unwrap()))
+    }
+
+    fn next_binary_row(&mut self) -> Result<Option<Vec<Option<String>>>, ArrowError> {
+        if !self.started {
+            if self.read_bytes(BINARY_SIGNATURE.len())? != BINARY_SIGNATURE {
+                return Err(ArrowError::ParseError(
+                    "Missing signature of the binary COPY format".to_string(),
+                ));
+            }
+            let _flags = self.read_i32()?;
+            let extension_length = self.read_i32()?;
+            if extension_length < 0 {
+                return Err(ArrowError::ParseError(format!(
+                    "Invalid length {extension_length} of the binary COPY header extension"
+                )));
+            }
+            self.read_bytes(extension_length as usize)?;
+            self.started = true;
+        }
+        let mut count = [0; 2];
+        if self.reader.read(&mut count[..1])? == 0 {
+            return Ok(None);
+        }
+        self.reader.read_exact(&mut count[1..])?;
+        let count = i16::from_be_bytes(count);
+        if count == -1 {
+            return Ok(None);
+        }
+        let CopyFormat::Binary(types) = self.format.clone() else {
+            unreachable!()
+        };
+        if count < 0 || count as usize != types.len() {
+            return Err(ArrowError::ParseError(format!(
+                "Expected {} columns but found a row with {count}",
+                types.len()
+            )));
+        }
+        types
+            .iter()
+            .map(|pg_type| match self.read_i32()? {
+                -1 => Ok(None),
+                length if length < 0 => Err(ArrowError::ParseError(format!(
+                    "Invalid length {length} of a binary COPY value"
+                ))),
+                length => {
+                    let value = self.read_bytes(length as usize)?;
+                    pg_type.binary_to_text(&value).map(Some)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some)
+    }
+}
+
+/// A named column, parsed from `name:type`, e.g. `price:numeric(10,2)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgColumn {
+    pub name: String,
+    pub pg_type: PgType,
+}
+
+impl FromStr for PgColumn {
+    type Err = ArrowError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        match spec.split_once(':') {
+            Some((name, pg_type)) if !name.trim().is_empty() => Ok(PgColumn {
+                name: name.trim().to_string(),
+                pg_type: pg_type.parse()?,
+            }),
+            _ => Err(ArrowError::InvalidArgumentError(format!(
+                "Invalid column \"{spec}\", expected name:type"
+            ))),
+        }
+    }
+}
+
+/// The schema of the given columns.
+pub fn columns_schema(columns: &[PgColumn]) -> Schema {
+    Schema::new(
+        columns
+            .iter()
+            .map(|column| Field::new(&column.name, column.pg_type.data_type(), true))
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Infers the schema of the text format from the first `max_read_records` rows, or all rows
+/// if `None`, with the same rules as the CSV reader. Columns are named `column_1`, `column_2`
+/// and so on.
+pub fn infer_schema<R: BufRead>(
+    reader: R,
+    format: CopyFormat,
+    max_read_records: Option<usize>,
+) -> Result<Schema, ArrowError> {
+    let mut rows = Rows::new(reader, format);
+    let mut types: Vec<InferredDataType> = Vec::new();
+    let mut read = 0;
+    while max_read_records.is_none_or(|max| read < max) {
+        let Some(row) = rows.next_row()? else {
+            break;
+        };
+        if types.len() < row.len() {
+            types.resize(row.len(), InferredDataType::default());
+        }
+        for (inferred, value) in types.iter_mut().zip(row) {
+            if let Some(value) = value {
+                inferred.update(&value);
+            }
+        }
+        read += 1;
+    }
+    if max_read_records == Some(0) {
+        types.iter_mut().for_each(InferredDataType::update_utf8);
+    }
+    Ok(Schema::new(
+        types
+            .iter()
+            .enumerate()
+            .map(|(index, inferred)| {
+                Field::new(format!("column_{}", index + 1), inferred.data_type(), true)
+            })
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// Converts a column of text values to its Arrow type. Binary values are read from the
+/// `\x` hex format that PostgreSQL uses for `bytea`.
+fn convert(strings: StringArray, data_type: &DataType) -> Result<ArrayRef, ArrowError> {
+    match data_type {
+        DataType::Null => Ok(new_null_array(data_type, strings.len())),
+        DataType::Binary => {
+            let mut builder = BinaryBuilder::new();
+            for value in &strings {
+                match value {
+                    Some(value) => builder.append_value(decode_bytea(value)?),
+                    None => builder.append_null(),
+                }
+            }
+            Ok(Arc::new(builder.finish()))
+        }
+        _ => {
+            let options = CastOptions {
+                safe: false,
+                ..Default::default()
+            };
+            cast_with_options(&strings, data_type, &options)
+        }
+    }
+}
+
+fn decode_bytea(value: &str) -> Result<Vec<u8>, ArrowError> {
+    let Some(hex) = value.strip_prefix("\\x") else {
+        return Ok(value.as_bytes().to_vec());
+    };
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| {
+            hex.get(index..index + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| ArrowError::ParseError(format!("Invalid bytea value: {value}")))
+        })
+        .collect()
+}
+
+/// Iterates over the rows of a `COPY` file in record batches.
+pub struct PgCopyReader<R> {
+    rows: Rows<R>,
+    schema: SchemaRef,
+    columns: Vec<StringBuilder>,
+}
+
+impl<R: BufRead> PgCopyReader<R> {
+    pub fn new(reader: R, format: CopyFormat, schema: SchemaRef) -> Self {
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|_| StringBuilder::new())
+            .collect();
+        PgCopyReader {
+            rows: Rows::new(reader, format),
+            schema,
+            columns,
+        }
+    }
+
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>, ArrowError> {
+        let mut len = 0;
+        while len < BATCH_SIZE {
+            let Some(row) = self.rows.next_row()? else {
+                break;
+            };
+            let mut values = row.into_iter();
+            for column in self.columns.iter_mut() {
+                column.append_option(values.next().flatten());
+            }
+            len += 1;
+        }
+        if len == 0 {
+            return Ok(None);
+        }
+        let columns = self
+            .columns
+            .iter_mut()
+            .zip(self.schema.fields())
+            .map(|(column, field)| {
+                convert(column.finish(), field.data_type()).map_err(|error| {
+                    ArrowError::CastError(format!(
+                        "Error converting column \"{}\": {error}",
+                        field.name()
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        RecordBatch::try_new(self.schema.clone(), columns).map(Some)
+    }
+}
+
+impl<R: BufRead> Iterator for PgCopyReader<R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_batch().transpose()
+    }
+}
+
+impl<R: BufRead> RecordBatchReader for PgCopyReader<R> {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
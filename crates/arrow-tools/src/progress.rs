@@ -0,0 +1,250 @@
+//! Reporting the progress of a conversion on stderr.
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How often the progress is reported.
+const INTERVAL: Duration = Duration::from_millis(500);
+
+/// How the progress is shown: as a line that is updated in place, or as a JSON object per
+/// line.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ProgressFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+/// Counters of a running conversion, updated by the reading and writing threads.
+#[derive(Debug, Default)]
+pub struct Progress {
+    /// The size of the input file, if known.
+    total_bytes: AtomicU64,
+    /// Bytes read from the input file, before decompression.
+    bytes_read: AtomicU64,
+    /// Bytes of the input after decompression.
+    bytes_decompressed: AtomicU64,
+    rows: AtomicU64,
+    row_groups: AtomicU64,
+}
+
+/// A snapshot of the counters.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ProgressState {
+    pub total_bytes: Option<u64>,
+    pub bytes_read: u64,
+    pub bytes_decompressed: u64,
+    pub rows: u64,
+    pub row_groups: u64,
+}
+
+impl Progress {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Progress::default())
+    }
+
+    pub fn set_total_bytes(&self, bytes: u64) {
+        self.total_bytes.store(bytes, Ordering::Relaxed);
+    }
+
+    pub fn add_rows(&self, rows: usize) {
+        self.rows.fetch_add(rows as u64, Ordering::Relaxed);
+    }
+
+    pub fn set_row_groups(&self, row_groups: usize) {
+        self.row_groups.store(row_groups as u64, Ordering::Relaxed);
+    }
+
+    pub fn state(&self) -> ProgressState {
+        let total_bytes = self.total_bytes.load(Ordering::Relaxed);
+        ProgressState {
+            total_bytes: (total_bytes > 0).then_some(total_bytes),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            bytes_decompressed: self.bytes_decompressed.load(Ordering::Relaxed),
+            rows: self.rows.load(Ordering::Relaxed),
+            row_groups: self.row_groups.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Shows the progress on stderr until the reporter is dropped, which shows it a last time.
+    pub fn report(self: &Arc<Self>, format: ProgressFormat) -> ProgressReporter {
+        let (stop, stopped) = channel::<()>();
+        let progress = self.clone();
+        let start = Instant::now();
+        let thread = std::thread::spawn(move || loop {
+            let done = !matches!(
+                stopped.recv_timeout(INTERVAL),
+                Err(RecvTimeoutError::Timeout)
+            );
+            let line = progress.state().line(format, start.elapsed(), done);
+            let mut stderr = io::stderr().lock();
+            let _ = match format {
+                ProgressFormat::Human if done => writeln!(stderr, "\r{line}\x1b[K"),
+                ProgressFormat::Human => write!(stderr, "\r{line}\x1b[K"),
+                ProgressFormat::Json => writeln!(stderr, "{line}"),
+            };
+            if done {
+                break;
+            }
+        });
+        ProgressReporter {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+impl ProgressState {
+    /// The estimated time until the input is read, from the share of it that was read.
+    pub fn eta(&self, elapsed: Duration) -> Option<Duration> {
+        let total = self.total_bytes?;
+        if self.bytes_read == 0 || self.bytes_read > total {
+            return None;
+        }
+        let remaining = (total - self.bytes_read) as f64 / self.bytes_read as f64;
+        Some(elapsed.mul_f64(remaining))
+    }
+
+    fn line(&self, format: ProgressFormat, elapsed: Duration, done: bool) -> String {
+        let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+        let throughput = self.bytes_decompressed as f64 / seconds;
+        let eta = self.eta(elapsed).filter(|_| !done);
+        match format {
+            ProgressFormat::Human => {
+                let mut line = format!("{} read", bytes(self.bytes_read));
+                if let Some(total) = self.total_bytes {
+                    let percent = 100.0 * self.bytes_read as f64 / total.max(1) as f64;
+                    line += &format!(" of {} ({percent:.0}%)", bytes(total));
+                }
+                if self.bytes_decompressed != self.bytes_read {
+                    line += &format!(", {} decompressed", bytes(self.bytes_decompressed));
+                }
+                line += &format!(", {} rows written", self.rows);
+                if self.row_groups > 0 {
+                    line += &format!(", {} row groups", self.row_groups);
+                }
+                line += &format!(", {}/s", bytes(throughput as u64));
+                match eta {
+                    Some(eta) => line += &format!(", ETA {}", duration(eta)),
+                    None if done => line += &format!(", done in {}", duration(elapsed)),
+                    None => {}
+                }
+                line
+            }
+            ProgressFormat::Json => serde_json::json!({
+                "event": if done { "done" } else { "progress" },
+                "total_bytes": self.total_bytes,
+                "bytes_read": self.bytes_read,
+                "bytes_decompressed": self.bytes_decompressed,
+                "rows": self.rows,
+                "row_groups": self.row_groups,
+                "elapsed_seconds": elapsed.as_secs_f64(),
+                "bytes_per_second": throughput,
+                "eta_seconds": eta.map(|eta| eta.as_secs_f64()),
+            })
+            .to_string(),
+        }
+    }
+}
+
+/// Shows the progress until it is dropped.
+pub struct ProgressReporter {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for ProgressReporter {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{bytes} B"),
+        _ => format!("{value:.1} {}", UNITS[unit]),
+    }
+}
+
+fn duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds {
+        0..60 => format!("{seconds}s"),
+        60..3600 => format!("{}m {:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
+/// Which counters a [`CountingReader`] adds to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Counted {
+    /// Bytes of the input file.
+    Read,
+    /// Bytes after decompression.
+    Decompressed,
+    /// Bytes of an input that is not compressed.
+    Both,
+}
+
+/// Counts the bytes that are read from a reader. Seeking sets the count to the new
+/// position, so that reading the start again after schema inference isn't counted twice.
+pub(crate) struct CountingReader<R> {
+    inner: R,
+    progress: Arc<Progress>,
+    counted: Counted,
+}
+
+impl<R> CountingReader<R> {
+    pub(crate) fn new(inner: R, progress: Arc<Progress>, counted: Counted) -> Self {
+        CountingReader {
+            inner,
+            progress,
+            counted,
+        }
+    }
+
+    fn counters(&self) -> impl Iterator<Item = &AtomicU64> {
+        let read = matches!(self.counted, Counted::Read | Counted::Both);
+        let decompressed = matches!(self.counted, Counted::Decompressed | Counted::Both);
+        [
+            read.then_some(&self.progress.bytes_read),
+            decompressed.then_some(&self.progress.bytes_decompressed),
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.counters().for_each(|counter| {
+            counter.fetch_add(read as u64, Ordering::Relaxed);
+        });
+        Ok(read)
+    }
+}
+
+impl<R: Seek> Seek for CountingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = self.inner.seek(pos)?;
+        self.counters()
+            .for_each(|counter| counter.store(position, Ordering::Relaxed));
+        Ok(position)
+    }
+}
@@ -0,0 +1,298 @@
+//! Reading length-delimited Protobuf messages with a descriptor set.
+//!
+//! The Arrow schema is derived from the message descriptor: repeated fields become lists,
+//! maps become maps with string keys, nested messages become structs and enums become
+//! strings with the name of the value. Each `oneof` gets an extra string column with the
+//! name of the field that is set. `google.protobuf.Timestamp` and `google.protobuf.Duration`
+//! become timestamps and durations in nanoseconds and wrapper types become nullable values.
+
+use crate::values::{float, hex, timestamp};
+use arrow::datatypes::{DataType, Field, Fields, Schema, TimeUnit};
+use arrow::error::ArrowError;
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, FieldDescriptor, Kind, MapKey, MessageDescriptor,
+    ReflectMessage, Value as ProtobufValue,
+};
+use serde_json::{Map, Value};
+use std::fs;
+use std::io::{BufRead, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+const TIMESTAMP: &str = "google.protobuf.Timestamp";
+const DURATION: &str = "google.protobuf.Duration";
+const WRAPPERS: [&str; 9] = [
+    "google.protobuf.DoubleValue",
+    "google.protobuf.FloatValue",
+    "google.protobuf.Int64Value",
+    "google.protobuf.UInt64Value",
+    "google.protobuf.Int32Value",
+    "google.protobuf.UInt32Value",
+    "google.protobuf.BoolValue",
+    "google.protobuf.StringValue",
+    "google.protobuf.BytesValue",
+];
+
+/// Loads a message type from a compiled `FileDescriptorSet`, e.g. from
+/// `protoc --include_imports --descriptor_set_out`.
+pub fn load_message<P: AsRef<Path>>(
+    descriptor_set: P,
+    message: &str,
+) -> Result<MessageDescriptor, ArrowError> {
+    let path = descriptor_set.as_ref();
+    let bytes = fs::read(path).map_err(|error| {
+        ArrowError::IoError(
+            format!("Error opening descriptor set: {path:?}, message: {error}"),
+            error,
+        )
+    })?;
+    let mut pool = DescriptorPool::global();
+    pool.decode_file_descriptor_set(bytes.as_slice())
+        .map_err(|error| {
+            ArrowError::ParseError(format!("Error reading descriptor set: {error}"))
+        })?;
+    pool.get_message_by_name(message.trim_start_matches('.'))
+        .ok_or_else(|| {
+            ArrowError::InvalidArgumentError(format!(
+                "Message \"{message}\" not found in descriptor set {path:?}"
+            ))
+        })
+}
+
+/// Derives the Arrow schema of a message type.
+pub fn message_schema(message: &MessageDescriptor) -> Result<Schema, ArrowError> {
+    Ok(Schema::new(message_fields(message, &mut Vec::new())?))
+}
+
+fn message_fields(
+    message: &MessageDescriptor,
+    parents: &mut Vec<String>,
+) -> Result<Fields, ArrowError> {
+    if parents.iter().any(|parent| parent == message.full_name()) {
+        return Err(ArrowError::SchemaError(format!(
+            "Recursive message type {} is not supported",
+            message.full_name()
+        )));
+    }
+    parents.push(message.full_name().to_string());
+    let mut fields = message
+        .fields()
+        .map(|field| Ok(Field::new(field.name(), field_type(&field, parents)?, true)))
+        .collect::<Result<Vec<_>, ArrowError>>()?;
+    for oneof in message.oneofs().filter(|oneof| !oneof.is_synthetic()) {
+        fields.push(Field::new(oneof.name(), DataType::Utf8, true));
+    }
+    parents.pop();
+    Ok(fields.into())
+}
+
+fn field_type(field: &FieldDescriptor, parents: &mut Vec<String>) -> Result<DataType, ArrowError> {
+    match field.kind() {
+        Kind::Message(entry) if field.is_map() => {
+            let value = entry.map_entry_value_field();
+            Ok(DataType::Map(
+                Arc::new(Field::new(
+                    "entries",
+                    DataType::Struct(Fields::from(vec![
+                        Field::new("keys", DataType::Utf8, false),
+                        Field::new("values", kind_type(&value.kind(), parents)?, true),
+                    ])),
+                    false,
+                )),
+                false,
+            ))
+        }
+        kind if field.is_list() => Ok(DataType::List(Arc::new(Field::new_list_field(
+            kind_type(&kind, parents)?,
+            true,
+        )))),
+        kind => kind_type(&kind, parents),
+    }
+}
+
+fn kind_type(kind: &Kind, parents: &mut Vec<String>) -> Result<DataType, ArrowError> {
+    Ok(match kind {
+        Kind::Double => DataType::Float64,
+        Kind::Float => DataType::Float32,
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => DataType::Int32,
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => DataType::Int64,
+        Kind::Uint32 | Kind::Fixed32 => DataType::UInt32,
+        Kind::Uint64 | Kind::Fixed64 => DataType::UInt64,
+        Kind::Bool => DataType::Boolean,
+        Kind::String | Kind::Enum(_) => DataType::Utf8,
+        Kind::Bytes => DataType::Binary,
+        Kind::Message(message) => match message.full_name() {
+            TIMESTAMP => DataType::Timestamp(TimeUnit::Nanosecond, Some("+00:00".into())),
+            DURATION => DataType::Duration(TimeUnit::Nanosecond),
+            name if WRAPPERS.contains(&name) => {
+                kind_type(&message.get_field(1).unwrap().kind(), parents)?
+            }
+            _ => DataType::Struct(message_fields(message, parents)?),
+        },
+    })
+}
+
+fn message_to_json(message: &DynamicMessage) -> Value {
+    let descriptor = message.descriptor();
+    let mut object = Map::new();
+    for field in descriptor.fields() {
+        let value = if field.supports_presence() && !message.has_field(&field) {
+            Value::Null
+        } else {
+            value_to_json(&message.get_field(&field), &field.kind())
+        };
+        object.insert(field.name().to_string(), value);
+    }
+    for oneof in descriptor.oneofs().filter(|oneof| !oneof.is_synthetic()) {
+        let case = oneof.fields().find(|field| message.has_field(field));
+        let case = case.map_or(Value::Null, |field| Value::from(field.name()));
+        object.insert(oneof.name().to_string(), case);
+    }
+    Value::Object(object)
+}
+
+fn value_to_json(value: &ProtobufValue, kind: &Kind) -> Value {
+    match value {
+        ProtobufValue::Bool(value) => Value::Bool(*value),
+        ProtobufValue::I32(value) => Value::from(*value),
+        ProtobufValue::I64(value) => Value::from(*value),
+        ProtobufValue::U32(value) => Value::from(*value),
+        ProtobufValue::U64(value) => Value::from(*value),
+        ProtobufValue::F32(value) => float((*value).into()),
+        ProtobufValue::F64(value) => float(*value),
+        ProtobufValue::String(value) => Value::from(value.as_str()),
+        ProtobufValue::Bytes(value) => hex(value),
+        ProtobufValue::EnumNumber(number) => match kind {
+            Kind::Enum(descriptor) => match descriptor.get_value(*number) {
+                Some(value) => Value::from(value.name()),
+                None => Value::from(number.to_string()),
+            },
+            _ => Value::from(*number),
+        },
+        ProtobufValue::Message(message) => {
+            let field = |number| message.get_field_by_number(number).unwrap();
+            match message.descriptor().full_name() {
+                TIMESTAMP => {
+                    let seconds = field(1).as_i64().unwrap_or_default();
+                    let nanoseconds = field(2).as_i32().unwrap_or_default();
+                    timestamp(seconds, nanoseconds.max(0) as u32)
+                }
+                DURATION => {
+                    let seconds = field(1).as_i64().unwrap_or_default();
+                    let nanoseconds = field(2).as_i32().unwrap_or_default();
+                    seconds
+                        .checked_mul(1_000_000_000)
+                        .and_then(|value| value.checked_add(nanoseconds.into()))
+                        .map_or(Value::Null, Value::from)
+                }
+                name if WRAPPERS.contains(&name) => {
+                    let descriptor = message.descriptor().get_field(1).unwrap();
+                    value_to_json(&field(1), &descriptor.kind())
+                }
+                _ => message_to_json(message),
+            }
+        }
+        ProtobufValue::List(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| value_to_json(value, kind))
+                .collect(),
+        ),
+        ProtobufValue::Map(entries) => {
+            let value_kind = match kind {
+                Kind::Message(entry) => entry.map_entry_value_field().kind(),
+                kind => kind.clone(),
+            };
+            Value::Object(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key_string(key), value_to_json(value, &value_kind)))
+                    .collect(),
+            )
+        }
+    }
+}
+
+fn key_string(key: &MapKey) -> String {
+    match key {
+        MapKey::Bool(key) => key.to_string(),
+        MapKey::I32(key) => key.to_string(),
+        MapKey::I64(key) => key.to_string(),
+        MapKey::U32(key) => key.to_string(),
+        MapKey::U64(key) => key.to_string(),
+        MapKey::String(key) => key.clone(),
+    }
+}
+
+/// Iterates over length-delimited messages, each prefixed with its size as a varint.
+pub struct ProtobufMessages<R> {
+    reader: R,
+    message: MessageDescriptor,
+    index: usize,
+    buf: Vec<u8>,
+}
+
+impl<R: BufRead> ProtobufMessages<R> {
+    pub fn new(reader: R, message: MessageDescriptor) -> Self {
+        ProtobufMessages {
+            reader,
+            message,
+            index: 0,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Reads the size of the next message, `None` at the end of the input.
+    fn read_length(&mut self) -> Result<Option<u64>, ArrowError> {
+        let mut length = 0;
+        for shift in (0..64).step_by(7) {
+            let mut byte = [0];
+            if self.reader.read(&mut byte)? == 0 {
+                if shift == 0 {
+                    return Ok(None);
+                }
+                break;
+            }
+            length |= u64::from(byte[0] & 0x7f) << shift;
+            if byte[0] & 0x80 == 0 {
+                return Ok(Some(length));
+            }
+        }
+        Err(ArrowError::ParseError(format!(
+            "Invalid length of message {}",
+            self.index
+        )))
+    }
+
+    fn next_message(&mut self) -> Result<Option<Value>, ArrowError> {
+        let Some(length) = self.read_length()? else {
+            return Ok(None);
+        };
+        self.buf.clear();
+        let read = self
+            .reader
+            .by_ref()
+            .take(length)
+            .read_to_end(&mut self.buf)?;
+        if read as u64 != length {
+            return Err(ArrowError::ParseError(format!(
+                "Message {} is truncated, expected {length} bytes but found {read}",
+                self.index
+            )));
+        }
+        let message =
+            DynamicMessage::decode(self.message.clone(), self.buf.as_slice()).map_err(|error| {
+                ArrowError::ParseError(format!("Error decoding message {}: {error}", self.index))
+            })?;
+        self.index += 1;
+        Ok(Some(message_to_json(&message)))
+    }
+}
+
+impl<R: BufRead> Iterator for ProtobufMessages<R> {
+    type Item = Result<Value, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_message().transpose()
+    }
+}
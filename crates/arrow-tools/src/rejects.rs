@@ -0,0 +1,577 @@
+//! Reading past records that can't be read with the schema, for `--on-error`.
+//!
+//! The input is split into records, which are parsed a batch at a time. When a batch
+//! fails, its records are parsed one at a time to find those that can't be read, which are
+//! left out or have the values that fail set to null, and are written to the reject file.
+
+use crate::csv::{is_line_end, Splitter};
+use crate::error::{write_position, Error, ErrorKind, Position};
+use crate::formats::CsvOptions;
+use arrow::array::new_null_array;
+use arrow::compute::concat_batches;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use regex::Regex;
+use serde_json::json;
+use std::fmt;
+use std::io::{self, BufRead, Cursor, Write};
+use std::sync::{Arc, LazyLock};
+
+/// The line in the messages of arrow's CSV reader, which counts from the record that failed
+/// as records are parsed one at a time.
+static RELATIVE_LINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r" (at|for) line \d+").unwrap());
+
+/// What to do with a record that can't be read with the schema: leave it out, set the
+/// values that fail to null and leave it out if that doesn't help, or stop the conversion.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum OnError {
+    Skip,
+    Null,
+    #[default]
+    Fail,
+}
+
+/// How the records of the input are split and parsed.
+#[derive(Clone, Debug)]
+pub enum RecordFormat {
+    Csv(CsvOptions),
+    /// JSON with one record per line.
+    Json,
+}
+
+/// A record of the input without its line break.
+pub(crate) struct Record {
+    /// The offset of its start.
+    pub(crate) offset: u64,
+    /// The line of its start, from 1.
+    pub(crate) line: u64,
+    /// Its index, from 0 and not counting a header.
+    pub(crate) index: u64,
+    pub(crate) bytes: Vec<u8>,
+}
+
+impl Record {
+    /// The position of the record, with the first field that fails in it.
+    fn position(&self, format: &RecordFormat, schema: &Schema) -> Position {
+        Position {
+            byte_offset: Some(self.offset),
+            line: Some(self.line),
+            record: Some(self.index),
+            column: failing_fields(format, schema, &self.bytes)
+                .first()
+                .map(|path| path.join(".")),
+            ..Default::default()
+        }
+    }
+
+    /// The error of reading the record, with its position and first line.
+    pub(crate) fn error(
+        &self,
+        format: &RecordFormat,
+        schema: &Schema,
+        error: ArrowError,
+    ) -> ArrowError {
+        let error = match error {
+            ArrowError::ParseError(message) => {
+                ArrowError::ParseError(RELATIVE_LINE.replace_all(&message, "").into_owned())
+            }
+            ArrowError::CsvError(message) => {
+                ArrowError::CsvError(RELATIVE_LINE.replace_all(&message, "").into_owned())
+            }
+            error => error,
+        };
+        let mut error = Error::new(ErrorKind::Read, error);
+        *error.position = self.position(format, schema);
+        error.snippet = String::from_utf8_lossy(&self.bytes)
+            .lines()
+            .next()
+            .map(str::to_string);
+        ArrowError::ExternalError(Box::new(error))
+    }
+}
+
+/// A record that can't be read with the schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rejected {
+    /// The position of the record, without the file.
+    pub position: Position,
+    pub error: String,
+    /// The record as it is in the input.
+    pub data: String,
+    /// Whether the values that failed were set to null instead of leaving out the record.
+    pub nulled: bool,
+}
+
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid record: {}", self.error)?;
+        write_position(f, &self.position, self.data.lines().next())
+    }
+}
+
+/// Infers the schema of JSON with one record per line from the lines that are objects,
+/// leaving out the others.
+pub(crate) fn infer_json_schema<R: BufRead>(
+    reader: R,
+    max_read_records: Option<usize>,
+) -> Result<Schema, ArrowError> {
+    let mut records = Vec::new();
+    for line in reader.split(b'\n') {
+        if records.len() == max_read_records.unwrap_or(usize::MAX) {
+            break;
+        }
+        if let Ok(record @ serde_json::Value::Object(_)) = serde_json::from_slice(&line?) {
+            records.push(record);
+        }
+    }
+    arrow::json::reader::infer_json_schema_from_iterator(records.into_iter().map(Ok))
+}
+
+/// Reads record batches, leaving out or setting to null the records that can't be read.
+pub struct TolerantReader<R> {
+    reader: R,
+    format: RecordFormat,
+    splitter: Option<Splitter>,
+    schema: SchemaRef,
+    on_error: OnError,
+    max_errors: Option<usize>,
+    rejects: Option<Box<dyn Write>>,
+    listener: Option<Box<dyn FnMut(Rejected)>>,
+    batch_size: usize,
+    /// Whether the header still has to be skipped.
+    header: bool,
+    offset: u64,
+    line: u64,
+    index: u64,
+    skipped: usize,
+    nulled: usize,
+}
+
+impl<R: BufRead> TolerantReader<R> {
+    pub fn new(
+        reader: R,
+        format: RecordFormat,
+        schema: SchemaRef,
+        on_error: OnError,
+        max_errors: Option<usize>,
+        rejects: Option<Box<dyn Write>>,
+    ) -> Self {
+        let (splitter, header) = match format {
+            RecordFormat::Csv(ref options) => {
+                (Some(Splitter::new(options)), options.header.unwrap_or(true))
+            }
+            RecordFormat::Json => (None, false),
+        };
+        TolerantReader {
+            reader,
+            format,
+            splitter,
+            schema,
+            on_error,
+            max_errors,
+            rejects,
+            listener: None,
+            batch_size: 1024,
+            header,
+            offset: 0,
+            line: 1,
+            index: 0,
+            skipped: 0,
+            nulled: 0,
+        }
+    }
+
+    /// Sets the number of records per batch, 1024 by default.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Sets the position of the start of the input and the index of its first record, for
+    /// inputs that are a part of a file.
+    pub(crate) fn with_start(mut self, offset: u64, line: u64, index: u64) -> Self {
+        (self.offset, self.line, self.index) = (offset, line, index);
+        self
+    }
+
+    /// Calls `listener` with each record that can't be read, after it is written to the
+    /// reject file.
+    pub fn with_listener<F: FnMut(Rejected) + 'static>(mut self, listener: F) -> Self {
+        self.listener = Some(Box::new(listener));
+        self
+    }
+
+    /// The number of records that were left out so far.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// The number of records that had values set to null so far.
+    pub fn nulled(&self) -> usize {
+        self.nulled
+    }
+
+    /// Reads the next record, leaving out blank lines, comments and the header.
+    fn next_record(&mut self) -> io::Result<Option<Record>> {
+        loop {
+            let (offset, line) = (self.offset, self.line);
+            let mut bytes = Vec::new();
+            match self.splitter {
+                Some(ref mut splitter) => loop {
+                    let buffer = self.reader.fill_buf()?;
+                    if buffer.is_empty() {
+                        break;
+                    }
+                    let mut used = 0;
+                    let mut end = false;
+                    for &byte in buffer {
+                        used += 1;
+                        let commented = splitter.commented;
+                        end = splitter.step(byte);
+                        if byte == b'\n' {
+                            self.line += 1;
+                        }
+                        // Comments end without ending a record.
+                        if !commented && !splitter.commented {
+                            bytes.push(byte);
+                        }
+                        if end || (commented && is_line_end(byte)) {
+                            break;
+                        }
+                    }
+                    self.reader.consume(used);
+                    self.offset += used as u64;
+                    if end || bytes.is_empty() {
+                        break;
+                    }
+                },
+                None => {
+                    let used = self.reader.read_until(b'\n', &mut bytes)?;
+                    if used == 0 {
+                        return Ok(None);
+                    }
+                    self.offset += used as u64;
+                    self.line += 1;
+                }
+            }
+            if bytes.is_empty() && line == self.line {
+                return Ok(None);
+            }
+            while bytes
+                .last()
+                .is_some_and(|&byte| byte == b'\n' || byte == b'\r')
+            {
+                bytes.pop();
+            }
+            // Arrow's CSV reader only skips empty lines, its JSON reader any whitespace.
+            let blank = match self.format {
+                RecordFormat::Csv(_) => bytes.is_empty(),
+                RecordFormat::Json => bytes.iter().all(u8::is_ascii_whitespace),
+            };
+            if blank {
+                continue;
+            }
+            if std::mem::take(&mut self.header) {
+                continue;
+            }
+            self.index += 1;
+            return Ok(Some(Record {
+                offset,
+                line,
+                index: self.index - 1,
+                bytes,
+            }));
+        }
+    }
+
+    /// Parses a record on its own, setting the values that fail to null if asked to.
+    /// Returns the batch and whether values were set to null.
+    fn parse_record(&self, record: &Record) -> Result<(RecordBatch, bool), ArrowError> {
+        let error = match parse(&self.format, self.schema.clone(), &[&record.bytes]) {
+            Ok(batch) => return Ok((batch, false)),
+            Err(error) => error,
+        };
+        let failing = match self.on_error {
+            OnError::Null => failing_fields(&self.format, &self.schema, &record.bytes),
+            _ => Vec::new(),
+        };
+        if failing.is_empty() {
+            return Err(error);
+        }
+        let batch = match self.format {
+            // The columns are read as strings, and replaced by nulls after parsing.
+            RecordFormat::Csv(_) => {
+                let fields: Vec<Field> = self
+                    .schema
+                    .fields()
+                    .iter()
+                    .map(
+                        |field| match failing.iter().any(|path| path[0] == *field.name()) {
+                            true => Field::new(field.name(), DataType::Utf8, true),
+                            false => (**field).clone(),
+                        },
+                    )
+                    .collect();
+                let batch = parse(
+                    &self.format,
+                    Arc::new(Schema::new(fields)),
+                    &[&record.bytes],
+                );
+                batch.and_then(|batch| {
+                    let columns = batch
+                        .columns()
+                        .iter()
+                        .zip(self.schema.fields())
+                        .map(|(column, field)| {
+                            match failing.iter().any(|path| path[0] == *field.name()) {
+                                true => new_null_array(field.data_type(), 1),
+                                false => column.clone(),
+                            }
+                        })
+                        .collect();
+                    RecordBatch::try_new(self.schema.clone(), columns)
+                })
+            }
+            // The fields are removed from the object, including nested ones.
+            RecordFormat::Json => {
+                let Ok(mut value) = serde_json::from_slice(&record.bytes) else {
+                    return Err(error);
+                };
+                for path in &failing {
+                    remove_field(&mut value, path);
+                }
+                let bytes = serde_json::to_vec(&value).unwrap();
+                parse(&self.format, self.schema.clone(), &[&bytes])
+            }
+        };
+        batch.map(|batch| (batch, true)).map_err(|_| error)
+    }
+
+    /// Counts a record that failed, writes it to the reject file and passes it to the
+    /// listener.
+    fn reject(
+        &mut self,
+        record: &Record,
+        error: &ArrowError,
+        nulled: bool,
+    ) -> Result<(), ArrowError> {
+        if nulled {
+            self.nulled += 1;
+        } else {
+            self.skipped += 1;
+        }
+        let message = error.to_string();
+        let rejected = Rejected {
+            position: record.position(&self.format, &self.schema),
+            error: RELATIVE_LINE.replace_all(&message, "").into_owned(),
+            data: String::from_utf8_lossy(&record.bytes).into_owned(),
+            nulled,
+        };
+        if let Some(rejects) = self.rejects.as_mut() {
+            let reject = json!({
+                "line": record.line,
+                "record": record.index,
+                "column": rejected.position.column,
+                "action": if nulled { "null" } else { "skip" },
+                "error": rejected.error,
+                "data": rejected.data,
+            });
+            writeln!(rejects, "{reject}")?;
+        }
+        let last = rejected.error.clone();
+        if let Some(listener) = self.listener.as_mut() {
+            listener(rejected);
+        }
+        let errors = self.skipped + self.nulled;
+        match self.max_errors {
+            Some(max_errors) if errors > max_errors => Err(record.error(
+                &self.format,
+                &self.schema,
+                ArrowError::ParseError(format!(
+                    "Stopped after {errors} records that could not be read, more than the maximum of {max_errors}. The last: {last}"
+                )),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>, ArrowError> {
+        let mut records = Vec::new();
+        while records.len() < self.batch_size {
+            match self.next_record()? {
+                Some(record) => records.push(record),
+                None => break,
+            }
+        }
+        if records.is_empty() {
+            if let Some(rejects) = self.rejects.as_mut() {
+                rejects.flush()?;
+            }
+            return Ok(None);
+        }
+
+        let bytes: Vec<&[u8]> = records.iter().map(|record| &record.bytes[..]).collect();
+        if let Ok(batch) = parse(&self.format, self.schema.clone(), &bytes) {
+            return Ok(Some(batch));
+        }
+        let mut batches = Vec::new();
+        for record in &records {
+            match self.parse_record(record) {
+                Ok((batch, false)) => batches.push(batch),
+                Ok((batch, true)) => {
+                    // Parse again to report why values were set to null.
+                    let error =
+                        parse(&self.format, self.schema.clone(), &[&record.bytes]).unwrap_err();
+                    self.reject(record, &error, true)?;
+                    batches.push(batch);
+                }
+                Err(error) if self.on_error == OnError::Fail => {
+                    return Err(record.error(&self.format, &self.schema, error))
+                }
+                Err(error) => self.reject(record, &error, false)?,
+            }
+        }
+        Ok(Some(concat_batches(&self.schema, &batches)?))
+    }
+}
+
+/// Parses records, each without its line break, into one batch.
+fn parse(
+    format: &RecordFormat,
+    schema: SchemaRef,
+    records: &[&[u8]],
+) -> Result<RecordBatch, ArrowError> {
+    let input = Cursor::new(records.join(&b'\n'));
+    let batches = match format {
+        RecordFormat::Csv(options) => arrow::csv::ReaderBuilder::new(schema.clone())
+            .with_format(options.format().with_header(false))
+            .with_batch_size(records.len())
+            .build(input)?
+            .collect::<Result<Vec<_>, _>>()?,
+        RecordFormat::Json => arrow::json::ReaderBuilder::new(schema.clone())
+            .with_batch_size(records.len())
+            .build(input)?
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+    concat_batches(&schema, &batches)
+}
+
+/// The fields that fail when a record is parsed with only them typed, as paths of
+/// field names. A nested JSON field is given instead of its parent when it fails on its
+/// own.
+pub(crate) fn failing_fields(
+    format: &RecordFormat,
+    schema: &Schema,
+    bytes: &[u8],
+) -> Vec<Vec<String>> {
+    let fields = schema.fields();
+    match format {
+        // The other columns are read as strings, so a CSV record that doesn't have the
+        // number of columns of the schema fails for every column and has none.
+        RecordFormat::Csv(_) => {
+            let strings = |keep: Option<usize>| {
+                let fields: Vec<Field> = fields
+                    .iter()
+                    .enumerate()
+                    .map(|(index, field)| match Some(index) == keep {
+                        true => (**field).clone(),
+                        false => Field::new(field.name(), DataType::Utf8, true),
+                    })
+                    .collect();
+                Arc::new(Schema::new(fields))
+            };
+            if parse(format, strings(None), &[bytes]).is_err() {
+                return Vec::new();
+            }
+            (0..fields.len())
+                .filter(|&index| parse(format, strings(Some(index)), &[bytes]).is_err())
+                .map(|index| vec![fields[index].name().clone()])
+                .collect()
+        }
+        // Fields that are not in the schema are ignored by arrow's JSON reader, so a record
+        // that isn't an object fails without any.
+        RecordFormat::Json => {
+            if parse(format, Arc::new(Schema::empty()), &[bytes]).is_err() {
+                return Vec::new();
+            }
+            fields
+                .iter()
+                .flat_map(|field| failing_json_fields(schema, bytes, field, &[]))
+                .collect()
+        }
+    }
+}
+
+/// The paths of the fields within `field` at `parent` that fail in a JSON record.
+fn failing_json_fields(
+    schema: &Schema,
+    bytes: &[u8],
+    field: &Field,
+    parent: &[String],
+) -> Vec<Vec<String>> {
+    let mut path = parent.to_vec();
+    path.push(field.name().clone());
+    let only = Schema::new(vec![nested_field(schema, &path)]);
+    if parse(&RecordFormat::Json, Arc::new(only), &[bytes]).is_ok() {
+        return Vec::new();
+    }
+    let nested: Vec<_> = match field.data_type() {
+        DataType::Struct(children) => children
+            .iter()
+            .flat_map(|child| failing_json_fields(schema, bytes, child, &path))
+            .collect(),
+        _ => Vec::new(),
+    };
+    match nested.is_empty() {
+        true => vec![path],
+        false => nested,
+    }
+}
+
+/// The top-level field of `schema` at the start of `path`, with only the nested fields
+/// along the path. The parents are nullable, so that only the last field can fail.
+fn nested_field(schema: &Schema, path: &[String]) -> Field {
+    fn only(field: &Field, path: &[String]) -> Field {
+        match (path, field.data_type()) {
+            ([], _) => field.clone(),
+            ([name, rest @ ..], DataType::Struct(children)) => {
+                let child = children.iter().find(|child| child.name() == name).unwrap();
+                Field::new_struct(field.name(), vec![only(child, rest)], true)
+            }
+            _ => unreachable!("paths only lead through structs"),
+        }
+    }
+    let field = schema.field_with_name(&path[0]).unwrap();
+    only(field, &path[1..])
+}
+
+/// Removes the field at `path` from a JSON object, if it is there.
+fn remove_field(value: &mut serde_json::Value, path: &[String]) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let object = parents
+        .iter()
+        .try_fold(value, |value, name| value.get_mut(name))
+        .and_then(serde_json::Value::as_object_mut);
+    if let Some(object) = object {
+        object.remove(last);
+    }
+}
+
+impl<R: BufRead> Iterator for TolerantReader<R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_batch().transpose()
+    }
+}
+
+impl<R: BufRead> RecordBatchReader for TolerantReader<R> {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
@@ -0,0 +1,84 @@
+//! A machine-readable report of a conversion, written by `--report`.
+
+use crate::converter::{ConversionSummary, SchemaSource};
+use crate::input::is_gzip;
+use crate::writer::OutputFormat;
+use arrow::error::ArrowError;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// What went into a conversion and what it wrote.
+#[derive(Clone, Debug)]
+pub struct ConversionReport {
+    pub input: PathBuf,
+    pub schema_source: SchemaSource,
+    /// The output file, stdout if not present.
+    pub output: Option<PathBuf>,
+    pub output_format: OutputFormat,
+    pub summary: ConversionSummary,
+    /// The wall time of the conversion, including schema inference.
+    pub elapsed: Duration,
+}
+
+impl ConversionReport {
+    /// The report as JSON. The sizes of the input and output are those of the files, and
+    /// `null` for pipes and stdout.
+    pub fn to_json(&self) -> Value {
+        let summary = &self.summary;
+        let columns: Vec<Value> = summary
+            .columns
+            .iter()
+            .map(|column| {
+                json!({
+                    "path": column.path,
+                    "compressed_bytes": column.compressed_bytes,
+                    "uncompressed_bytes": column.uncompressed_bytes,
+                })
+            })
+            .collect();
+        let (schema_source, schema_file) = match self.schema_source {
+            SchemaSource::Infer => ("inferred", None),
+            SchemaSource::File(ref path) => ("file", Some(path)),
+            SchemaSource::Existing(ref path) => ("existing", Some(path)),
+            SchemaSource::Schema(_) => ("given", None),
+        };
+        json!({
+            "input": {
+                "path": self.input,
+                "bytes": file_size(&self.input),
+                "compression": is_gzip(&self.input).then_some("gzip"),
+            },
+            "schema": summary.schema,
+            "schema_source": schema_source,
+            "schema_file": schema_file,
+            "output": {
+                "path": self.output,
+                "format": self.output_format.name(),
+                "bytes": self.output.as_deref().and_then(file_size),
+            },
+            "rows": summary.rows,
+            "batches": summary.batches,
+            "skipped": summary.skipped,
+            "nulled": summary.nulled,
+            "row_groups": summary.row_groups,
+            "early_row_groups": summary.early_row_groups,
+            "columns": columns,
+            "elapsed_seconds": self.elapsed.as_secs_f64(),
+        })
+    }
+
+    /// Writes the report as pretty-printed JSON.
+    pub fn write(&self, path: &Path) -> Result<(), ArrowError> {
+        let json = serde_json::to_string_pretty(&self.to_json()).unwrap();
+        std::fs::write(path, json + "\n")?;
+        Ok(())
+    }
+}
+
+fn file_size(path: &Path) -> Option<u64> {
+    std::fs::metadata(path)
+        .ok()
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+}
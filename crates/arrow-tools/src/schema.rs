@@ -0,0 +1,156 @@
+//! Loading and comparing Arrow schemas.
+
+use arrow::datatypes::{DataType, Schema};
+use arrow::error::ArrowError;
+use arrow::ipc::reader::FileReader;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+
+/// Reads a schema file in the JSON format that `--print-schema` writes.
+pub fn read_schema_file(path: &Path) -> Result<Schema, ArrowError> {
+    let file = File::open(path).map_err(|error| {
+        ArrowError::IoError(
+            format!("Error opening schema file: {path:?}, message: {error}"),
+            error,
+        )
+    })?;
+    serde_json::from_reader(file)
+        .map_err(|error| ArrowError::SchemaError(format!("Error reading schema json: {error}")))
+}
+
+/// Reads the schema of a Parquet or Arrow IPC file, or returns `None` for other files.
+pub fn read_file_schema(path: &Path) -> Result<Option<Schema>, ArrowError> {
+    let mut file = File::open(path)?;
+    let mut magic = Vec::with_capacity(6);
+    file.by_ref().take(6).read_to_end(&mut magic)?;
+    file.rewind()?;
+
+    if magic.starts_with(b"PAR1") {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file)?;
+        Ok(Some(builder.schema().as_ref().clone()))
+    } else if magic == b"ARROW1" {
+        let reader = FileReader::try_new(file, None)?;
+        Ok(Some(reader.schema().as_ref().clone()))
+    } else {
+        Ok(None)
+    }
+}
+
+/// A difference of a top-level field between a reference schema and a new one.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SchemaChange {
+    Added {
+        name: String,
+        data_type: DataType,
+    },
+    Removed {
+        name: String,
+    },
+    /// The field is at another position among the fields that both schemas have.
+    Reordered {
+        name: String,
+        from: usize,
+        to: usize,
+    },
+    TypeChanged {
+        name: String,
+        from: DataType,
+        to: DataType,
+    },
+    NullabilityChanged {
+        name: String,
+        from: bool,
+        to: bool,
+    },
+}
+
+impl SchemaChange {
+    /// Whether data with the new schema can break readers of the reference schema: a field
+    /// was removed, its type changed or it can now be null.
+    pub fn is_breaking(&self) -> bool {
+        match self {
+            SchemaChange::Added { .. } | SchemaChange::Reordered { .. } => false,
+            SchemaChange::Removed { .. } | SchemaChange::TypeChanged { .. } => true,
+            SchemaChange::NullabilityChanged { to, .. } => *to,
+        }
+    }
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaChange::Added { name, data_type } => write!(f, "+ {name}: {data_type}"),
+            SchemaChange::Removed { name } => write!(f, "- {name}"),
+            SchemaChange::Reordered { name, from, to } => {
+                write!(f, "~ {name}: moved from position {from} to {to}")
+            }
+            SchemaChange::TypeChanged { name, from, to } => write!(f, "~ {name}: {from} -> {to}"),
+            SchemaChange::NullabilityChanged { name, to, .. } => match to {
+                true => write!(f, "~ {name}: now nullable"),
+                false => write!(f, "~ {name}: no longer nullable"),
+            },
+        }
+    }
+}
+
+/// The changes of the top-level fields from the `reference` schema to the `new` one, in the
+/// order of the fields of the reference and then of the added fields. Fields are matched
+/// by name.
+pub fn diff_schemas(reference: &Schema, new: &Schema) -> Vec<SchemaChange> {
+    let mut changes = Vec::new();
+    let kept: Vec<&str> = reference
+        .fields()
+        .iter()
+        .map(|field| field.name().as_str())
+        .filter(|name| new.field_with_name(name).is_ok())
+        .collect();
+    let new_kept: Vec<&str> = new
+        .fields()
+        .iter()
+        .map(|field| field.name().as_str())
+        .filter(|name| reference.field_with_name(name).is_ok())
+        .collect();
+
+    for field in reference.fields() {
+        let name = field.name().clone();
+        let Ok(new_field) = new.field_with_name(&name) else {
+            changes.push(SchemaChange::Removed { name });
+            continue;
+        };
+        let from = kept.iter().position(|kept| *kept == name).unwrap();
+        let to = new_kept.iter().position(|kept| *kept == name).unwrap();
+        if from != to {
+            changes.push(SchemaChange::Reordered {
+                name: name.clone(),
+                from,
+                to,
+            });
+        }
+        if field.data_type() != new_field.data_type() {
+            changes.push(SchemaChange::TypeChanged {
+                name: name.clone(),
+                from: field.data_type().clone(),
+                to: new_field.data_type().clone(),
+            });
+        }
+        if field.is_nullable() != new_field.is_nullable() {
+            changes.push(SchemaChange::NullabilityChanged {
+                name,
+                from: field.is_nullable(),
+                to: new_field.is_nullable(),
+            });
+        }
+    }
+    for field in new.fields() {
+        if reference.field_with_name(field.name()).is_err() {
+            changes.push(SchemaChange::Added {
+                name: field.name().clone(),
+                data_type: field.data_type().clone(),
+            });
+        }
+    }
+    changes
+}
@@ -0,0 +1,397 @@
+//! Reading SQLite tables and query results as Arrow record batches.
+
+use crate::infer::InferredDataType;
+use arrow::array::{ArrayRef, BinaryBuilder, Float64Builder, Int64Builder, StringBuilder};
+use arrow::compute::{cast_with_options, CastOptions};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::Arc;
+use std::thread;
+
+const BATCH_SIZE: usize = 1024;
+
+/// Returns the query that selects all rows of a table.
+pub fn table_query(table: &str) -> String {
+    format!("SELECT * FROM \"{}\"", table.replace('"', "\"\""))
+}
+
+fn open(path: &Path) -> Result<Connection, ArrowError> {
+    Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(external)
+}
+
+/// The type affinity SQLite derives from a declared column type.
+/// See <https://www.sqlite.org/datatype3.html#determination_of_column_affinity>.
+#[derive(Clone, Copy, PartialEq)]
+enum Affinity {
+    Integer,
+    Text,
+    Blob,
+    Real,
+    Numeric,
+}
+
+impl Affinity {
+    fn from_decl_type(decl_type: Option<&str>) -> Self {
+        let decl_type = decl_type.unwrap_or_default().to_uppercase();
+        if decl_type.contains("INT") {
+            Affinity::Integer
+        } else if ["CHAR", "CLOB", "TEXT"]
+            .iter()
+            .any(|name| decl_type.contains(name))
+        {
+            Affinity::Text
+        } else if decl_type.is_empty() || decl_type.contains("BLOB") {
+            Affinity::Blob
+        } else if ["REAL", "FLOA", "DOUB"]
+            .iter()
+            .any(|name| decl_type.contains(name))
+        {
+            Affinity::Real
+        } else {
+            Affinity::Numeric
+        }
+    }
+}
+
+/// The storage classes seen in one column while sampling rows.
+#[derive(Default)]
+struct StorageClasses {
+    integer: bool,
+    boolean: bool,
+    real: bool,
+    blob: bool,
+    text: Option<InferredDataType>,
+}
+
+impl StorageClasses {
+    fn update(&mut self, value: ValueRef) {
+        match value {
+            ValueRef::Null => {}
+            ValueRef::Integer(i) => {
+                self.boolean = (!self.integer || self.boolean) && (i == 0 || i == 1);
+                self.integer = true;
+            }
+            ValueRef::Real(_) => self.real = true,
+            ValueRef::Text(text) => self
+                .text
+                .get_or_insert_with(InferredDataType::default)
+                .update(&String::from_utf8_lossy(text)),
+            ValueRef::Blob(_) => self.blob = true,
+        }
+    }
+
+    fn data_type(&self, affinity: Affinity, decl_type: Option<&str>) -> DataType {
+        let decl_type = decl_type.unwrap_or_default().to_uppercase();
+        let is_boolean = decl_type.starts_with("BOOL");
+        if affinity == Affinity::Text {
+            return DataType::Utf8;
+        }
+        if self.blob {
+            return match (&self.text, self.integer || self.real) {
+                (None, false) => DataType::Binary,
+                _ => DataType::Utf8,
+            };
+        }
+        match (&self.text, self.integer, self.real) {
+            (Some(_), true, _) | (Some(_), _, true) => DataType::Utf8,
+            (Some(text), false, false) => text.data_type(),
+            (None, _, true) => DataType::Float64,
+            (None, true, false) if is_boolean && self.boolean => DataType::Boolean,
+            (None, true, false) => DataType::Int64,
+            // No values were sampled, so fall back to the declared type
+            (None, false, false) => match affinity {
+                Affinity::Integer => DataType::Int64,
+                Affinity::Real => DataType::Float64,
+                Affinity::Blob if decl_type.contains("BLOB") => DataType::Binary,
+                Affinity::Numeric if is_boolean => DataType::Boolean,
+                _ => DataType::Utf8,
+            },
+        }
+    }
+}
+
+/// Infers the Arrow schema of a query from the declared column types and the storage
+/// classes of the first `max_read_records` rows, or all rows if `None`.
+pub fn infer_schema(
+    path: &Path,
+    query: &str,
+    max_read_records: Option<usize>,
+) -> Result<Schema, ArrowError> {
+    let connection = open(path)?;
+    let mut statement = connection.prepare(query).map_err(external)?;
+    let columns = statement
+        .columns()
+        .iter()
+        .map(|column| {
+            (
+                column.name().to_string(),
+                column.decl_type().map(str::to_string),
+            )
+        })
+        .collect::<Vec<_>>();
+    let mut classes = columns
+        .iter()
+        .map(|_| StorageClasses::default())
+        .collect::<Vec<_>>();
+
+    let mut rows = statement.query([]).map_err(external)?;
+    let mut read = 0;
+    while max_read_records.is_none_or(|max| read < max) {
+        let Some(row) = rows.next().map_err(external)? else {
+            break;
+        };
+        for (i, class) in classes.iter_mut().enumerate() {
+            class.update(row.get_ref(i).map_err(external)?);
+        }
+        read += 1;
+    }
+
+    Ok(Schema::new(
+        columns
+            .iter()
+            .zip(classes)
+            .map(|((name, decl_type), class)| {
+                let decl_type = decl_type.as_deref();
+                let affinity = Affinity::from_decl_type(decl_type);
+                Field::new(name, class.data_type(affinity, decl_type), true)
+            })
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// Builds one column from SQLite values, as the closest of the Arrow types that SQLite
+/// values can be stored in. The result is cast to the type of the field afterwards.
+enum ColumnBuilder {
+    Int64(Int64Builder),
+    Float64(Float64Builder),
+    Text(StringBuilder),
+    Binary(BinaryBuilder),
+}
+
+impl ColumnBuilder {
+    fn new(data_type: &DataType) -> Self {
+        match data_type {
+            DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64 => ColumnBuilder::Int64(Int64Builder::new()),
+            DataType::Float16 | DataType::Float32 | DataType::Float64 => {
+                ColumnBuilder::Float64(Float64Builder::new())
+            }
+            DataType::Binary | DataType::LargeBinary | DataType::BinaryView => {
+                ColumnBuilder::Binary(BinaryBuilder::new())
+            }
+            _ => ColumnBuilder::Text(StringBuilder::new()),
+        }
+    }
+
+    fn append(&mut self, value: ValueRef) -> Result<(), ArrowError> {
+        match (self, value) {
+            (ColumnBuilder::Int64(builder), ValueRef::Null) => builder.append_null(),
+            (ColumnBuilder::Int64(builder), ValueRef::Integer(i)) => builder.append_value(i),
+            (ColumnBuilder::Int64(builder), ValueRef::Real(f))
+                if f.fract() == 0.0 && f >= i64::MIN as f64 && f < i64::MAX as f64 =>
+            {
+                builder.append_value(f as i64)
+            }
+            (ColumnBuilder::Int64(builder), ValueRef::Text(bytes)) => {
+                builder.append_value(parse(bytes, &DataType::Int64)?)
+            }
+            (ColumnBuilder::Float64(builder), ValueRef::Null) => builder.append_null(),
+            (ColumnBuilder::Float64(builder), ValueRef::Integer(i)) => {
+                builder.append_value(i as f64)
+            }
+            (ColumnBuilder::Float64(builder), ValueRef::Real(f)) => builder.append_value(f),
+            (ColumnBuilder::Float64(builder), ValueRef::Text(bytes)) => {
+                builder.append_value(parse(bytes, &DataType::Float64)?)
+            }
+            (ColumnBuilder::Text(builder), ValueRef::Null) => builder.append_null(),
+            (ColumnBuilder::Text(builder), ValueRef::Integer(i)) => {
+                builder.append_value(i.to_string())
+            }
+            (ColumnBuilder::Text(builder), ValueRef::Real(f)) => {
+                builder.append_value(f.to_string())
+            }
+            (ColumnBuilder::Text(builder), ValueRef::Text(bytes) | ValueRef::Blob(bytes)) => {
+                builder.append_value(utf8(bytes)?)
+            }
+            (ColumnBuilder::Binary(builder), ValueRef::Null) => builder.append_null(),
+            (ColumnBuilder::Binary(builder), ValueRef::Integer(i)) => {
+                builder.append_value(i.to_string())
+            }
+            (ColumnBuilder::Binary(builder), ValueRef::Real(f)) => {
+                builder.append_value(f.to_string())
+            }
+            (ColumnBuilder::Binary(builder), ValueRef::Text(bytes) | ValueRef::Blob(bytes)) => {
+                builder.append_value(bytes)
+            }
+            (ColumnBuilder::Int64(_), ValueRef::Real(f)) => {
+                return Err(ArrowError::CastError(format!(
+                    "Cannot convert {f} to Int64"
+                )))
+            }
+            (builder, ValueRef::Blob(_)) => {
+                return Err(ArrowError::CastError(format!(
+                    "Cannot convert a blob to {}",
+                    builder.data_type()
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    fn data_type(&self) -> DataType {
+        match self {
+            ColumnBuilder::Int64(_) => DataType::Int64,
+            ColumnBuilder::Float64(_) => DataType::Float64,
+            ColumnBuilder::Text(_) => DataType::Utf8,
+            ColumnBuilder::Binary(_) => DataType::Binary,
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            ColumnBuilder::Int64(builder) => Arc::new(builder.finish()),
+            ColumnBuilder::Float64(builder) => Arc::new(builder.finish()),
+            ColumnBuilder::Text(builder) => Arc::new(builder.finish()),
+            ColumnBuilder::Binary(builder) => Arc::new(builder.finish()),
+        }
+    }
+}
+
+fn utf8(bytes: &[u8]) -> Result<&str, ArrowError> {
+    std::str::from_utf8(bytes)
+        .map_err(|error| ArrowError::CastError(format!("Invalid UTF-8 in text: {error}")))
+}
+
+/// Parses a number that SQLite stored as text.
+fn parse<T: std::str::FromStr>(bytes: &[u8], data_type: &DataType) -> Result<T, ArrowError> {
+    let text = utf8(bytes)?;
+    text.trim()
+        .parse()
+        .map_err(|_| ArrowError::CastError(format!("Cannot convert \"{text}\" to {data_type}")))
+}
+
+fn column_error(field: &Field, error: ArrowError) -> ArrowError {
+    ArrowError::CastError(format!(
+        "Error converting column \"{}\": {error}",
+        field.name()
+    ))
+}
+
+fn read_batches(
+    path: &Path,
+    query: &str,
+    schema: SchemaRef,
+    mut send: impl FnMut(Result<RecordBatch, ArrowError>) -> bool,
+) -> Result<(), ArrowError> {
+    let connection = open(path)?;
+    let mut statement = connection.prepare(query).map_err(external)?;
+    if statement.column_count() != schema.fields().len() {
+        return Err(ArrowError::SchemaError(format!(
+            "Query returns {} columns but the schema has {} fields",
+            statement.column_count(),
+            schema.fields().len()
+        )));
+    }
+    let mut builders = schema
+        .fields()
+        .iter()
+        .map(|field| ColumnBuilder::new(field.data_type()))
+        .collect::<Vec<_>>();
+    let options = CastOptions {
+        safe: false,
+        ..Default::default()
+    };
+    let finish = |builders: &mut Vec<ColumnBuilder>| {
+        let columns = builders
+            .iter_mut()
+            .zip(schema.fields())
+            .map(|(builder, field)| {
+                cast_with_options(&builder.finish(), field.data_type(), &options)
+                    .map_err(|error| column_error(field, error))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        RecordBatch::try_new(schema.clone(), columns)
+    };
+
+    let mut rows = statement.query([]).map_err(external)?;
+    let mut len = 0;
+    while let Some(row) = rows.next().map_err(external)? {
+        for ((i, builder), field) in builders.iter_mut().enumerate().zip(schema.fields()) {
+            builder
+                .append(row.get_ref(i).map_err(external)?)
+                .map_err(|error| column_error(field, error))?;
+        }
+        len += 1;
+        if len == BATCH_SIZE {
+            len = 0;
+            let batch = finish(&mut builders);
+            // Nothing is read after a batch that failed
+            let failed = batch.is_err();
+            if !send(batch) || failed {
+                return Ok(());
+            }
+        }
+    }
+    if len > 0 {
+        send(finish(&mut builders));
+    }
+    Ok(())
+}
+
+/// Streams the rows of a query in record batches.
+///
+/// The query runs on a background thread so that reading from SQLite overlaps with
+/// writing the output.
+pub struct SqliteReader {
+    schema: SchemaRef,
+    batches: Receiver<Result<RecordBatch, ArrowError>>,
+}
+
+impl SqliteReader {
+    pub fn new(path: PathBuf, query: String, schema: SchemaRef) -> Self {
+        let (sender, batches) = sync_channel(2);
+        let thread_schema = schema.clone();
+        thread::spawn(move || {
+            let result = read_batches(&path, &query, thread_schema, |batch| {
+                sender.send(batch).is_ok()
+            });
+            if let Err(error) = result {
+                let _ = sender.send(Err(error));
+            }
+        });
+        SqliteReader { schema, batches }
+    }
+}
+
+impl Iterator for SqliteReader {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.batches.recv().ok()
+    }
+}
+
+impl RecordBatchReader for SqliteReader {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+fn external(error: rusqlite::Error) -> ArrowError {
+    ArrowError::ExternalError(Box::new(error))
+}
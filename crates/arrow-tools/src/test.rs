@@ -5,7 +5,8 @@ use std::io::{Read, Seek};
 #[test]
 fn seekable_reader() {
     let mut seekable_reader =
-        SeekableReader::from_unbuffered_reader(File::open("../../data/simple.csv").unwrap(), None);
+        SeekableReader::from_unbuffered_reader(File::open("../../data/simple.csv").unwrap(), None)
+            .unwrap();
     let mut reader = File::open("../../data/simple.csv").unwrap();
 
     let mut buf1 = vec![0; 20];
@@ -19,3 +20,1276 @@ fn seekable_reader() {
     seekable_reader.read_exact(&mut buf3).unwrap();
     assert_eq!(buf3, buf2);
 }
+
+#[test]
+fn seekable_reader_bounds() {
+    // A fully buffered input can be rewound after reading to the end.
+    let mut expected = Vec::new();
+    File::open("../../data/simple.csv")
+        .unwrap()
+        .read_to_end(&mut expected)
+        .unwrap();
+    let mut seekable_reader = SeekableReader::from_unbuffered_reader(&expected[..], None).unwrap();
+    let mut read = Vec::new();
+    seekable_reader.read_to_end(&mut read).unwrap();
+    assert_eq!(read, expected);
+    seekable_reader.rewind().unwrap();
+    read.clear();
+    seekable_reader.read_to_end(&mut read).unwrap();
+    assert_eq!(read, expected);
+
+    // Reading goes on past the buffer, after which seeking fails.
+    let input = "1,2\n".repeat(10_000);
+    let mut seekable_reader =
+        SeekableReader::from_unbuffered_reader(input.as_bytes(), Some(1)).unwrap();
+    let mut read = String::new();
+    seekable_reader.read_to_string(&mut read).unwrap();
+    assert_eq!(read, input);
+    assert!(seekable_reader.rewind().is_err());
+}
+
+#[test]
+fn seekable_reader_error() {
+    // Errors while filling the buffer are returned, e.g. from a corrupt gzip file.
+    struct Failing;
+    impl Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "corrupt deflate stream",
+            ))
+        }
+    }
+    let error = SeekableReader::from_unbuffered_reader(Failing, None)
+        .err()
+        .unwrap();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn infer_text_types() {
+    use super::infer::InferredDataType;
+    use arrow::datatypes::{DataType, TimeUnit};
+
+    let infer = |values: &[&str]| {
+        let mut inferred = InferredDataType::default();
+        values.iter().for_each(|value| inferred.update(value));
+        inferred.data_type()
+    };
+
+    assert_eq!(infer(&[]), DataType::Null);
+    assert_eq!(infer(&["1", "-2"]), DataType::Int64);
+    assert_eq!(infer(&["1", "2.5"]), DataType::Float64);
+    assert_eq!(infer(&["true", "FALSE"]), DataType::Boolean);
+    assert_eq!(infer(&["2024-01-01"]), DataType::Date32);
+    assert_eq!(
+        infer(&["2024-01-01", "2024-01-01T10:00:00.123"]),
+        DataType::Timestamp(TimeUnit::Millisecond, None)
+    );
+    assert_eq!(infer(&["1", "abc"]), DataType::Utf8);
+    assert_eq!(infer(&[""]), DataType::Utf8);
+
+    // Types are merged across the chunks that are inferred separately
+    let mut values = vec!["1"; 2500];
+    values.push("2.5");
+    assert_eq!(infer(&values), DataType::Float64);
+    let mut values = vec!["2024-01-01"; 2500];
+    values.push("2024-01-01 10:00:00.123456");
+    assert_eq!(
+        infer(&values),
+        DataType::Timestamp(TimeUnit::Microsecond, None)
+    );
+    values.push("x");
+    assert_eq!(infer(&values), DataType::Utf8);
+}
+
+#[cfg(feature = "xlsx")]
+#[test]
+fn xlsx_sheet() {
+    use super::xlsx::Sheet;
+    use arrow::array::{Array, AsArray, RecordBatchReader};
+    use arrow::datatypes::{Date32Type, Float64Type, Int64Type};
+    use std::sync::Arc;
+
+    let sheet = Sheet::open("../../data/simple.xlsx", Some("data"), None, true, None).unwrap();
+    let schema = Arc::new(sheet.infer_schema(None));
+    let mut reader = sheet.into_reader(schema);
+    assert_eq!(reader.schema().fields().len(), 4);
+    let batch = reader.next().unwrap().unwrap();
+    assert_eq!(batch.num_rows(), 3);
+    assert_eq!(
+        batch.column(0).as_primitive::<Int64Type>().values(),
+        &[42, 12, 7]
+    );
+    // 2024-02-29 is 19782 days after the Unix epoch
+    assert_eq!(batch.column(2).as_primitive::<Date32Type>().value(1), 19782);
+    assert!(reader.next().is_none());
+
+    let sheet = Sheet::open(
+        "../../data/simple.xlsx",
+        Some("1"),
+        Some("B3:C5"),
+        true,
+        None,
+    )
+    .unwrap();
+    let schema = Arc::new(sheet.infer_schema(None));
+    let batch = sheet.into_reader(schema).next().unwrap().unwrap();
+    assert_eq!(
+        batch.column(1).as_primitive::<Float64Type>().values(),
+        &[1.5, 2.0]
+    );
+
+    // Cells that match the null regex are null, whatever their type
+    let null_regex = regex::Regex::new("^(x|2)$").unwrap();
+    let sheet = Sheet::open(
+        "../../data/simple.xlsx",
+        Some("1"),
+        Some("B3:C5"),
+        true,
+        Some(null_regex),
+    )
+    .unwrap();
+    let schema = Arc::new(sheet.infer_schema(None));
+    let batch = sheet.into_reader(schema).next().unwrap().unwrap();
+    assert_eq!(batch.column(0).null_count(), 1);
+    assert_eq!(batch.column(0).as_string::<i32>().value(1), "y");
+    assert!(batch.column(1).is_null(1));
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_query() {
+    use super::sqlite::{infer_schema, table_query, SqliteReader};
+    use arrow::array::{Array, AsArray, RecordBatchReader};
+    use arrow::datatypes::{DataType, Float64Type};
+    use std::path::Path;
+    use std::sync::Arc;
+
+    let path = Path::new("../../data/simple.sqlite");
+    let query = table_query("readings");
+    let schema = Arc::new(infer_schema(path, &query, None).unwrap());
+    assert_eq!(schema.field(0).data_type(), &DataType::Int64);
+
+    let mut reader = SqliteReader::new(path.to_path_buf(), query, schema);
+    assert_eq!(reader.schema().fields().len(), 6);
+    let batch = reader.next().unwrap().unwrap();
+    assert_eq!(batch.num_rows(), 3);
+    let value = batch.column(2).as_primitive::<Float64Type>();
+    assert_eq!(value.value(1), 19.0);
+    assert!(value.is_null(2));
+    assert_eq!(batch.column(4).as_binary::<i32>().value(0), &[1, 2]);
+    assert!(reader.next().is_none());
+
+    // Columns that only have a declared type still get one without sampling rows
+    let schema = infer_schema(path, "SELECT * FROM simple", Some(0)).unwrap();
+    assert_eq!(schema.field(1).data_type(), &DataType::Boolean);
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_values() {
+    use super::sqlite::{table_query, SqliteReader};
+    use arrow::array::AsArray;
+    use arrow::datatypes::{DataType, Field, Int32Type, Schema};
+    use std::sync::Arc;
+
+    let path = std::env::temp_dir().join("arrow-tools-sqlite-values.sqlite");
+    let _ = std::fs::remove_file(&path);
+    let connection = rusqlite::Connection::open(&path).unwrap();
+    connection
+        .execute_batch(
+            "CREATE TABLE t (a ANY, b TEXT);
+            INSERT INTO t VALUES (1, 'x'), (2.0, 'y'), ('3', 'z');
+            INSERT INTO t VALUES (4, X'FF');",
+        )
+        .unwrap();
+    drop(connection);
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int32, true),
+        Field::new("b", DataType::Utf8, true),
+    ]));
+
+    // Integers stored in any storage class are read into the integer column
+    let query = "SELECT * FROM t LIMIT 3".to_string();
+    let batch = SqliteReader::new(path.clone(), query, schema.clone())
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        batch.column(0).as_primitive::<Int32Type>().values(),
+        &[1, 2, 3]
+    );
+
+    // A blob that is not UTF-8 fails the text column and ends the reader
+    let mut reader = SqliteReader::new(path.clone(), table_query("t"), schema);
+    let error = reader.next().unwrap().unwrap_err().to_string();
+    assert!(error.contains("column \"b\""), "{error}");
+    assert!(error.contains("Invalid UTF-8"), "{error}");
+    assert!(reader.next().is_none());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn fixed_width_layout() {
+    use super::fwf::{FixedWidthReader, Layout};
+    use arrow::array::AsArray;
+    use arrow::datatypes::Float64Type;
+    use std::io::BufReader;
+    use std::sync::Arc;
+
+    let layout = Layout::from_file("../../data/simple.layout").unwrap();
+    let mut input = BufReader::new(File::open("../../data/simple.fwf").unwrap());
+    let schema = Arc::new(layout.infer_schema(&mut input, None, None).unwrap());
+    input.rewind().unwrap();
+
+    let batch = FixedWidthReader::new(input, layout, schema, None)
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(batch.num_rows(), 3);
+    assert_eq!(
+        batch.column(2).as_primitive::<Float64Type>().values(),
+        &[12.34, 0.5, -9.99]
+    );
+    assert_eq!(batch.column(3).as_string::<i32>().value(1), "Bob");
+}
+
+#[test]
+fn log_lines() {
+    use super::logs::{LogFormat, LogParser, LogReader};
+    use arrow::array::{Array, AsArray};
+    use arrow::datatypes::Int64Type;
+    use regex::Regex;
+    use std::io::{BufReader, Cursor};
+    use std::sync::Arc;
+
+    let format = LogFormat::Combined;
+    let null_regex = format
+        .null_pattern()
+        .map(|pattern| Regex::new(pattern).unwrap());
+    let parser = LogParser::new(Regex::new(format.pattern()).unwrap(), null_regex).unwrap();
+    let mut input = BufReader::new(File::open("../../data/simple.log").unwrap());
+    let schema = Arc::new(parser.infer_schema(&mut input, None).unwrap());
+    input.rewind().unwrap();
+
+    let rejects = Cursor::new(Vec::new());
+    let mut reader = LogReader::new(input, parser, schema, Some(Box::new(rejects)));
+    let batch = reader.next().unwrap().unwrap();
+    assert_eq!(batch.num_rows(), 3);
+    assert_eq!(reader.rejected(), 1);
+    let size = batch
+        .column_by_name("size")
+        .unwrap()
+        .as_primitive::<Int64Type>();
+    assert_eq!(size.value(0), 2326);
+    assert!(size.is_null(1));
+
+    assert!(LogParser::new(Regex::new(r"(\w+) (?P<rest>.*)").unwrap(), None).is_err());
+}
+
+#[cfg(feature = "xml")]
+#[test]
+fn xml_records() {
+    use super::values::{infer_schema, ValueReader};
+    use super::xml::XmlRecords;
+    use arrow::array::{Array, AsArray};
+    use arrow::datatypes::Int64Type;
+    use std::io::BufReader;
+    use std::sync::Arc;
+
+    let mut input = BufReader::new(File::open("../../data/simple.xml").unwrap());
+    let records = XmlRecords::new(&mut input, "catalog/book").unwrap();
+    let schema = Arc::new(infer_schema(records, None).unwrap());
+    input.rewind().unwrap();
+
+    let records = XmlRecords::new(input, "/catalog/book").unwrap();
+    let batch = ValueReader::try_new(records, schema)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(batch.num_rows(), 2);
+    let authors = batch.column_by_name("author").unwrap().as_list::<i32>();
+    assert_eq!(authors.value_length(0), 2);
+    assert_eq!(authors.value_length(1), 1);
+    let title = batch.column_by_name("title").unwrap().as_string::<i32>();
+    assert_eq!(title.value(1), "Programming & Data");
+    let published = batch.column_by_name("published").unwrap().as_struct();
+    let year = published.column_by_name("year").unwrap();
+    assert_eq!(year.as_primitive::<Int64Type>().value(1), 2021);
+    assert_eq!(
+        batch.column_by_name("note").unwrap().logical_null_count(),
+        2
+    );
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn msgpack_values() {
+    use super::msgpack::MessagePackValues;
+    use std::io::BufReader;
+
+    let input = BufReader::new(File::open("../../data/simple.msgpack").unwrap());
+    let values = MessagePackValues::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(values.len(), 3);
+    assert_eq!(values[0]["at"], "2020-09-13T12:26:40Z");
+    assert_eq!(values[0]["raw"], "0102");
+    assert_eq!(values[2]["a"], -7);
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn cbor_values() {
+    use super::cbor::CborValues;
+    use super::values::ValueReader;
+    use arrow::array::AsArray;
+    use arrow::datatypes::{DataType, Field, Schema, TimeUnit, TimestampSecondType};
+    use std::io::BufReader;
+    use std::sync::Arc;
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("at", DataType::Timestamp(TimeUnit::Second, None), true),
+        Field::new("raw", DataType::Binary, true),
+    ]));
+    let input = BufReader::new(File::open("../../data/simple.cbor").unwrap());
+    let batch = ValueReader::try_new(CborValues::new(input), schema)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(batch.num_rows(), 3);
+    let at = batch.column(0).as_primitive::<TimestampSecondType>();
+    assert_eq!(at.value(1), 1600000060);
+    assert_eq!(batch.column(1).as_binary::<i32>().value(1), &[0xff]);
+}
+
+#[cfg(feature = "protobuf")]
+#[test]
+fn protobuf_messages() {
+    use super::protobuf::{load_message, message_schema, ProtobufMessages};
+    use super::values::ValueReader;
+    use arrow::array::{Array, AsArray};
+    use arrow::datatypes::{Float64Type, TimestampNanosecondType};
+    use std::io::BufReader;
+    use std::sync::Arc;
+
+    let message = load_message("../../data/simple.desc", "example.Event").unwrap();
+    let schema = Arc::new(message_schema(&message).unwrap());
+    let input = BufReader::new(File::open("../../data/simple.pb").unwrap());
+    let messages = ProtobufMessages::new(input, message);
+    let batch = ValueReader::try_new(messages, schema)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(batch.num_rows(), 3);
+    let level = batch.column_by_name("level").unwrap().as_string::<i32>();
+    assert_eq!(level.value(1), "LEVEL_INFO");
+    let created_at = batch.column_by_name("created_at").unwrap();
+    let created_at = created_at.as_primitive::<TimestampNanosecondType>();
+    assert_eq!(created_at.value(0), 1_600_000_000_500_000_000);
+    let payload = batch.column_by_name("payload").unwrap().as_string::<i32>();
+    assert_eq!(payload.value(0), "text");
+    assert_eq!(payload.value(1), "data");
+    assert!(payload.is_null(2));
+    let score = batch.column_by_name("score").unwrap();
+    assert!(score.is_null(1));
+    assert_eq!(score.as_primitive::<Float64Type>().value(2), 1.5);
+    assert!(batch.column_by_name("location").unwrap().is_null(2));
+    assert_eq!(
+        batch
+            .column_by_name("counts")
+            .unwrap()
+            .as_map()
+            .value_length(0),
+        1
+    );
+}
+
+#[test]
+fn pgcopy_formats() {
+    use super::pgcopy::{
+        columns_schema, infer_schema, CopyFormat, PgColumn, PgCopyReader, PgType, BINARY_SIGNATURE,
+    };
+    use arrow::array::{Array, AsArray};
+    use arrow::datatypes::{Decimal128Type, TimestampMicrosecondType};
+    use std::io::BufReader;
+    use std::sync::Arc;
+
+    let columns = [
+        "id:int8",
+        "name:text",
+        "active:bool",
+        "price:numeric(10,2)",
+        "created:timestamptz",
+        "data:bytea",
+        "note:text",
+    ]
+    .map(|column| column.parse::<PgColumn>().unwrap());
+    let schema = Arc::new(columns_schema(&columns));
+    let types: Vec<PgType> = columns.iter().map(|column| column.pg_type).collect();
+
+    let text = BufReader::new(File::open("../../data/simple.copy").unwrap());
+    let text = PgCopyReader::new(text, CopyFormat::text(), schema.clone())
+        .next()
+        .unwrap()
+        .unwrap();
+    let binary = BufReader::new(File::open("../../data/simple.pgcopy").unwrap());
+    let binary = PgCopyReader::new(binary, CopyFormat::Binary(types), schema)
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(text, binary);
+
+    assert_eq!(text.num_rows(), 3);
+    assert!(text.column_by_name("name").unwrap().is_null(2));
+    let active = text.column_by_name("active").unwrap().as_boolean();
+    assert!(active.value(0) && !active.value(1));
+    let price = text.column_by_name("price").unwrap();
+    let price = price.as_primitive::<Decimal128Type>();
+    assert_eq!(price.value(0), 1250);
+    assert_eq!(price.value(2), -75);
+    let created = text.column_by_name("created").unwrap();
+    let created = created.as_primitive::<TimestampMicrosecondType>();
+    assert_eq!(created.value(0), 1_577_865_600_000_000);
+    let data = text.column_by_name("data").unwrap().as_binary::<i32>();
+    assert_eq!(data.value(0), [1, 2, 255]);
+    assert!(data.value(2).is_empty());
+    let note = text.column_by_name("note").unwrap().as_string::<i32>();
+    assert_eq!(note.value(0), "line one\nline two");
+    assert_eq!(note.value(1), "tab\there");
+
+    // A delimiter in a value is escaped with a backslash.
+    let format = CopyFormat::Text {
+        delimiter: b',',
+        null: "\\N".to_string(),
+    };
+    let input = "a\\,b,1\nc,\\N\n\\\\,3\n".as_bytes();
+    let schema = Arc::new(infer_schema(input, format.clone(), None).unwrap());
+    assert_eq!(schema.fields().len(), 2);
+    let batch = PgCopyReader::new(input, format, schema)
+        .next()
+        .unwrap()
+        .unwrap();
+    let values = batch.column(0).as_string::<i32>();
+    assert_eq!(
+        values.iter().collect::<Vec<_>>(),
+        [Some("a,b"), Some("c"), Some("\\")]
+    );
+    assert!(batch.column(1).is_null(1));
+
+    // Escaped bytes are decoded as UTF-8.
+    let format = CopyFormat::text();
+    let schema = Arc::new(columns_schema(&columns[1..2]));
+    let input = "\\xC3\\xA9t\\303\\251\n".as_bytes();
+    let batch = PgCopyReader::new(input, format.clone(), schema.clone())
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(batch.column(0).as_string::<i32>().value(0), "été");
+    let error = PgCopyReader::new("\\xff\n".as_bytes(), format, schema)
+        .next()
+        .unwrap()
+        .unwrap_err();
+    assert!(error.to_string().contains("Invalid UTF-8"), "{error}");
+
+    // Negative lengths other than -1 for null and negative counts are errors.
+    let schema = Arc::new(columns_schema(&columns[..1]));
+    for (count, length) in [(1i16, -2i32), (-2, 8)] {
+        let mut binary = BINARY_SIGNATURE.to_vec();
+        binary.extend([0; 8]);
+        binary.extend(count.to_be_bytes());
+        binary.extend(length.to_be_bytes());
+        let format = CopyFormat::Binary(vec![PgType::Int8]);
+        let error = PgCopyReader::new(&binary[..], format, schema.clone())
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert!(error.to_string().contains("-2"), "{error}");
+    }
+}
+
+#[test]
+fn bson_documents() {
+    use super::bson::{infer_schema, BsonDocuments, ObjectIdType};
+    use super::values::ValueReader;
+    use arrow::array::{Array, AsArray};
+    use arrow::datatypes::{DataType, Decimal128Type, TimestampMillisecondType};
+    use std::io::BufReader;
+    use std::sync::Arc;
+
+    let mut input = BufReader::new(File::open("../../data/simple.bson").unwrap());
+    let schema = infer_schema(&mut input, None, ObjectIdType::FixedSizeBinary).unwrap();
+    assert_eq!(
+        schema.field_with_name("_id").unwrap().data_type(),
+        &DataType::FixedSizeBinary(12)
+    );
+    input.rewind().unwrap();
+    let batch = ValueReader::try_new(BsonDocuments::new(input), Arc::new(schema))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(batch.num_rows(), 3);
+    let id = batch.column_by_name("_id").unwrap().as_fixed_size_binary();
+    assert_eq!(
+        id.value(0),
+        b"\x5f\x1e\x9b\x7a\x2c\x8d\x4e\x6f\x70\x81\x82\x93"
+    );
+    let created = batch.column_by_name("created").unwrap();
+    let created = created.as_primitive::<TimestampMillisecondType>();
+    assert_eq!(created.value(0), 1_600_000_000_500);
+    assert!(created.is_null(2));
+    let price = batch.column_by_name("price").unwrap();
+    let price = price.as_primitive::<Decimal128Type>();
+    assert_eq!(price.value(0), 1250);
+    assert_eq!(price.value(1), -75);
+    assert_eq!(price.value(2), 100_000);
+    let data = batch.column_by_name("data").unwrap().as_binary::<i32>();
+    assert_eq!(data.value(0), [1, 2, 255]);
+    let address = batch.column_by_name("address").unwrap().as_struct();
+    let since = address.column_by_name("since").unwrap();
+    assert_eq!(since.as_primitive::<TimestampMillisecondType>().value(0), 0);
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn yaml_documents() {
+    use super::values::{infer_schema, ValueReader};
+    use super::yaml::YamlDocuments;
+    use arrow::array::{Array, AsArray};
+    use arrow::datatypes::Int64Type;
+    use std::sync::Arc;
+
+    let input = File::open("../../data/simple.yaml").unwrap();
+    let schema = infer_schema(YamlDocuments::new(input), None).unwrap();
+    let input = File::open("../../data/simple.yaml").unwrap();
+    let batch = ValueReader::try_new(YamlDocuments::new(input), Arc::new(schema))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(batch.num_rows(), 3);
+    let name = batch.column_by_name("name").unwrap().as_string::<i32>();
+    assert_eq!(name.value(2), "cache-1");
+    let os = batch.column_by_name("os").unwrap().as_string::<i32>();
+    assert_eq!(os.value(2), "ubuntu");
+    let cores = batch.column_by_name("cores").unwrap();
+    let cores = cores.as_primitive::<Int64Type>();
+    assert_eq!(cores.values(), &[2, 8, 4]);
+    assert!(batch.column_by_name("memory").unwrap().is_null(0));
+}
+
+#[test]
+fn converter() {
+    use super::converter::{ConvertError, Converter};
+    use super::formats::{CsvOptions, FormatOptions};
+    use super::writer::OutputFormat;
+    use arrow::datatypes::DataType;
+
+    let input = Box::new(&b"a;b\n1;x\n2;y\n3;z\n"[..]) as Box<dyn Read>;
+    let options = CsvOptions {
+        delimiter: Some(';'),
+        ..Default::default()
+    };
+    let mut converter = Converter::new(input, FormatOptions::Csv(options))
+        .with_output_format(OutputFormat::Parquet);
+    let schema = converter.schema().unwrap();
+    assert_eq!(schema.field(0).data_type(), &DataType::Int64);
+    let mut output = Vec::new();
+    let summary = converter.write(&mut output).unwrap();
+    assert_eq!((summary.rows, summary.batches, summary.skipped), (3, 1, 0));
+    assert!(output.starts_with(b"PAR1"));
+
+    let summary = Converter::from_path("../../data/simple.json.gz")
+        .unwrap()
+        .write(std::io::sink())
+        .unwrap();
+    assert_eq!(
+        summary.schema,
+        Converter::from_path("../../data/simple.json")
+            .unwrap()
+            .schema()
+            .unwrap()
+    );
+
+    let error = Converter::from_path("../../data/simple.csv")
+        .unwrap()
+        .with_schema_file("../../data/missing.json")
+        .write(std::io::sink())
+        .unwrap_err();
+    assert!(matches!(error, ConvertError::Schema(_)));
+    assert!(matches!(
+        Converter::from_path("../../data/simple.desc"),
+        Err(ConvertError::Input(_))
+    ));
+}
+
+#[test]
+fn parallel_csv() {
+    use super::csv::ParallelCsvReader;
+    use super::formats::CsvOptions;
+    use arrow::compute::concat_batches;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    let mut csv = String::from("id,text\n");
+    for id in 0..5000 {
+        match id % 4 {
+            0 => csv.push_str(&format!("{id},\"line\nbreak\"\n")),
+            1 => csv.push_str(&format!("{id},\"quoted \"\"{id}\"\", comma\"\n")),
+            2 => csv.push_str(&format!("# comment \"{id}\n{id},plain\n")),
+            _ => csv.push_str(&format!("{id},\r\n")),
+        }
+    }
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("text", DataType::Utf8, true),
+    ]));
+    let options = CsvOptions {
+        comment: Some('#'),
+        ..Default::default()
+    };
+
+    let expected = arrow::csv::ReaderBuilder::new(schema.clone())
+        .with_format(options.format())
+        .build(csv.as_bytes())
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let expected = concat_batches(&schema, &expected).unwrap();
+    assert_eq!(expected.num_rows(), 5000);
+
+    for chunk_size in [1, 100, 10_000, 1 << 20] {
+        let batches = ParallelCsvReader::new(csv.as_bytes(), schema.clone(), &options, 4)
+            .with_chunk_size(chunk_size)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(concat_batches(&schema, &batches).unwrap(), expected);
+    }
+
+    let invalid = "id,text\n1,a\nx,b\n";
+    let result = ParallelCsvReader::new(invalid.as_bytes(), schema.clone(), &options, 2)
+        .collect::<Result<Vec<_>, _>>();
+    assert!(result.is_err());
+}
+
+#[test]
+fn parallel_parquet() {
+    use super::writer::{BatchWriter, OutputFormat, ParquetOptions};
+    use arrow::array::{ArrayRef, Int64Array, ListArray, StringArray, StructArray};
+    use arrow::compute::concat_batches;
+    use arrow::datatypes::{DataType, Field, Int64Type};
+    use arrow::record_batch::RecordBatch;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use std::sync::Arc;
+
+    let ids = Arc::new(Int64Array::from_iter_values(0..2500)) as ArrayRef;
+    let names = Arc::new(StringArray::from_iter_values(
+        (0..2500).map(|id| format!("name {id}")),
+    )) as ArrayRef;
+    let point = Arc::new(StructArray::from(vec![
+        (
+            Arc::new(Field::new("x", DataType::Int64, false)),
+            ids.clone(),
+        ),
+        (
+            Arc::new(Field::new("name", DataType::Utf8, false)),
+            names.clone(),
+        ),
+    ])) as ArrayRef;
+    let tags = Arc::new(ListArray::from_iter_primitive::<Int64Type, _, _>(
+        (0..2500).map(|id| (id % 3 != 0).then(|| vec![Some(id), None])),
+    )) as ArrayRef;
+    let batch = RecordBatch::try_from_iter([
+        ("id", ids),
+        ("name", names),
+        ("point", point),
+        ("tags", tags),
+    ])
+    .unwrap();
+
+    let write = |write_threads, name| {
+        let options = ParquetOptions {
+            max_row_group_size: Some(1000),
+            write_threads,
+            ..Default::default()
+        };
+        let output = std::env::temp_dir().join(name);
+        let mut writer = BatchWriter::try_new(
+            File::create(&output).unwrap(),
+            batch.schema(),
+            OutputFormat::Parquet,
+            &options,
+        )
+        .unwrap();
+        for offset in (0..2500).step_by(300) {
+            writer
+                .write(&batch.slice(offset, 300.min(2500 - offset)))
+                .unwrap();
+        }
+        writer.finish().unwrap();
+        output
+    };
+
+    let single = write(None, "arrow-tools-single.parquet");
+    let parallel = write(Some(3), "arrow-tools-parallel.parquet");
+    assert!(matches!(
+        BatchWriter::try_new(
+            Vec::new(),
+            batch.schema(),
+            OutputFormat::Parquet,
+            &ParquetOptions {
+                write_threads: Some(3),
+                ..Default::default()
+            }
+        ),
+        Ok(BatchWriter::ParallelParquet(_))
+    ));
+
+    let read = |output: &std::path::PathBuf| {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(output).unwrap()).unwrap();
+        assert_eq!(reader.metadata().num_row_groups(), 3);
+        let schema = reader.schema().clone();
+        let batches = reader
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        concat_batches(&schema, &batches).unwrap()
+    };
+    assert_eq!(read(&parallel), read(&single));
+    assert_eq!(read(&single), batch);
+}
+
+#[test]
+fn pipeline() {
+    use super::converter::{ConvertError, Converter};
+    use super::formats::{CsvOptions, FormatOptions};
+    use super::input::ReadAhead;
+
+    let data: Vec<u8> = (0..1_000_000u32)
+        .flat_map(|value| value.to_le_bytes())
+        .collect();
+    let mut read = Vec::new();
+    ReadAhead::new(std::io::Cursor::new(data.clone()))
+        .read_to_end(&mut read)
+        .unwrap();
+    assert_eq!(read, data);
+
+    let mut csv = String::from("a\n");
+    (0..5000).for_each(|value| csv.push_str(&format!("{value}\n")));
+    csv.push_str("x\n");
+    let options = CsvOptions {
+        max_read_records: Some(10),
+        ..Default::default()
+    };
+    let input = Box::new(std::io::Cursor::new(csv)) as Box<dyn Read>;
+    let mut output = Vec::new();
+    let error = Converter::new(input, FormatOptions::Csv(options))
+        .write(&mut output)
+        .unwrap_err();
+    assert!(matches!(error, ConvertError::Read(_)));
+}
+
+#[test]
+fn memory_limit() {
+    use super::converter::Converter;
+    use super::formats::{CsvOptions, FormatOptions};
+    use super::input::SpillingReader;
+    use super::writer::OutputFormat;
+
+    let mut csv = String::from("id,text\n");
+    (0..20000).for_each(|id| csv.push_str(&format!("{id},some text for row {id}\n")));
+
+    let mut reader = SpillingReader::new(csv.as_bytes(), Some(100), 1000).unwrap();
+    let mut start = vec![0; 2000];
+    reader.read_exact(&mut start).unwrap();
+    reader.rewind().unwrap();
+    let mut read = String::new();
+    reader.read_to_string(&mut read).unwrap();
+    assert_eq!(read, csv);
+    assert!(reader.rewind().is_err());
+
+    let input = Box::new(std::io::Cursor::new(csv.clone())) as Box<dyn Read>;
+    let summary = Converter::new(input, FormatOptions::Csv(CsvOptions::default()))
+        .with_output_format(OutputFormat::Parquet)
+        .with_memory_limit(256 << 10)
+        .write(std::io::sink())
+        .unwrap();
+    assert_eq!(summary.rows, 20000);
+    assert!(summary.batches > 20);
+    assert!(summary.early_row_groups > 0);
+
+    let input = Box::new(std::io::Cursor::new(csv)) as Box<dyn Read>;
+    let summary = Converter::new(input, FormatOptions::Csv(CsvOptions::default()))
+        .with_output_format(OutputFormat::Parquet)
+        .write(std::io::sink())
+        .unwrap();
+    assert_eq!((summary.batches, summary.early_row_groups), (20, 0));
+}
+
+#[test]
+fn progress() {
+    use super::converter::Converter;
+    use super::formats::{CsvOptions, FormatOptions};
+    use super::progress::Progress;
+    use super::writer::OutputFormat;
+    use std::time::Duration;
+
+    let progress = Progress::new();
+    let path = std::path::Path::new("../../data/simple.csv.gz");
+    progress.set_total_bytes(path.metadata().unwrap().len());
+    Converter::new(path, FormatOptions::Csv(CsvOptions::default()))
+        .with_output_format(OutputFormat::Parquet)
+        .with_progress(progress.clone())
+        .write(std::io::sink())
+        .unwrap();
+
+    let state = progress.state();
+    assert_eq!(state.rows, 3);
+    assert_eq!(state.row_groups, 1);
+    assert_eq!(Some(state.bytes_read), state.total_bytes);
+    assert_eq!(state.bytes_decompressed, 28);
+    assert_eq!(state.eta(Duration::from_secs(1)), Some(Duration::ZERO));
+
+    let halfway = super::progress::ProgressState {
+        total_bytes: Some(100),
+        bytes_read: 25,
+        ..Default::default()
+    };
+    assert_eq!(
+        halfway.eta(Duration::from_secs(2)),
+        Some(Duration::from_secs(6))
+    );
+}
+
+#[test]
+fn error_position() {
+    use super::converter::Converter;
+    use super::csv::CsvReader;
+    use super::error::{Error, ErrorKind, Position};
+    use super::formats::{CsvOptions, FormatOptions, JsonOptions};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::error::ArrowError;
+    use std::io::Cursor;
+    use std::sync::Arc;
+
+    fn locate(
+        path: &std::path::Path,
+        options: FormatOptions,
+    ) -> (ErrorKind, Position, Option<String>) {
+        let mut converter = Converter::new(path, options);
+        let error = match converter.schema() {
+            Ok(_) => converter.write(std::io::sink()).unwrap_err(),
+            Err(error) => error,
+        };
+        let error = Error::from(error).locate(path);
+        assert_eq!(error.position.file.as_deref(), Some(path));
+        (error.kind, *error.position, error.snippet)
+    }
+
+    let path = std::env::temp_dir().join("arrow-tools-error-position.csv");
+    std::fs::write(&path, "a,b\n1,2\n# comment\n\n\"3\n3\",4\n5,x\n").unwrap();
+    for threads in [None, Some(2)] {
+        let options = FormatOptions::Csv(CsvOptions {
+            comment: Some('#'),
+            max_read_records: Some(1),
+            threads,
+            ..Default::default()
+        });
+        let (kind, position, snippet) = locate(&path, options);
+        assert_eq!(kind, ErrorKind::Read);
+        assert_eq!(position.record, Some(1));
+        assert_eq!(position.line, Some(5));
+        assert_eq!(position.byte_offset, Some(19));
+        assert_eq!(position.column.as_deref(), Some("a"));
+        assert_eq!(snippet.as_deref(), Some("\"3"));
+    }
+
+    // The failing record is in the second batch, which doesn't start with the header.
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int64, true),
+        Field::new("b", DataType::Int64, true),
+    ]));
+    let csv = Cursor::new("a,b\n1,2\n3,4\n5,6\n7,x\n");
+    let mut reader = CsvReader::new(csv, schema, &CsvOptions::default(), 2);
+    let error = reader.find_map(Result::err).unwrap();
+    let ArrowError::ExternalError(error) = error else {
+        panic!("{error}");
+    };
+    let error = error.downcast::<Error>().unwrap();
+    assert_eq!(error.position.record, Some(3));
+    assert_eq!(error.position.line, Some(5));
+    assert_eq!(error.position.byte_offset, Some(16));
+    assert_eq!(error.position.column.as_deref(), Some("b"));
+    assert_eq!(error.snippet.as_deref(), Some("7,x"));
+
+    std::fs::write(&path, "a,b\n1,2\n3,4,5\n").unwrap();
+    // Schema inference fails before reading.
+    let (kind, position, snippet) = locate(&path, FormatOptions::Csv(CsvOptions::default()));
+    assert_eq!(kind, ErrorKind::Schema);
+    assert_eq!(position.record, Some(1));
+    assert_eq!(position.line, Some(3));
+    assert_eq!(position.byte_offset, Some(8));
+    assert_eq!(snippet.as_deref(), Some("3,4,5"));
+
+    let path = path.with_extension("json");
+    let options = FormatOptions::Json(JsonOptions {
+        max_read_records: Some(1),
+        ..Default::default()
+    });
+    std::fs::write(&path, "{\"a\":1}\n\n{\"a\":\"x\"}\n").unwrap();
+    let (kind, position, snippet) = locate(&path, options.clone());
+    assert_eq!(kind, ErrorKind::Read);
+    assert_eq!(position.record, Some(1));
+    assert_eq!(position.line, Some(3));
+    assert_eq!(position.byte_offset, Some(9));
+    assert_eq!(position.column.as_deref(), Some("a"));
+    assert_eq!(snippet.as_deref(), Some("{\"a\":\"x\"}"));
+
+    std::fs::write(&path, "{\"a\":1}\n{\"a\":2}\n{\"a\":}\n").unwrap();
+    let (kind, position, snippet) = locate(&path, options);
+    assert_eq!(kind, ErrorKind::Read);
+    assert_eq!(position.record, Some(2));
+    assert_eq!(position.line, Some(3));
+    assert_eq!(position.byte_offset, Some(16));
+    assert_eq!(snippet.as_deref(), Some("{\"a\":}"));
+
+    let (kind, position, _) = locate(&path, FormatOptions::Json(JsonOptions::default()));
+    assert_eq!(kind, ErrorKind::Schema);
+    assert_eq!(position.record, Some(2));
+    assert_eq!(position.line, Some(3));
+}
+
+#[test]
+fn on_error() {
+    use super::converter::Converter;
+    use super::formats::{CsvOptions, FormatOptions, JsonOptions};
+    use super::rejects::{OnError, RecordFormat, TolerantReader};
+    use arrow::array::{Array, AsArray, Int64Array};
+    use arrow::datatypes::{DataType, Field, Int64Type, Schema};
+    use std::sync::Arc;
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int64, true),
+        Field::new("b", DataType::Int64, true),
+    ]));
+    let csv = "a,b\n1,2\n# comment\n3,x\n\n\"5\n\",6\n7,8,9\n10,11\n";
+    let options = CsvOptions {
+        comment: Some('#'),
+        ..Default::default()
+    };
+    let read = |on_error| {
+        let mut reader = TolerantReader::new(
+            csv.as_bytes(),
+            RecordFormat::Csv(options.clone()),
+            schema.clone(),
+            on_error,
+            None,
+            None,
+        )
+        .with_batch_size(2);
+        let batches: Vec<_> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+        let batch = arrow::compute::concat_batches(&schema, &batches).unwrap();
+        (batch, reader.skipped(), reader.nulled())
+    };
+
+    let (batch, skipped, nulled) = read(OnError::Skip);
+    assert_eq!((batch.num_rows(), skipped, nulled), (2, 3, 0));
+    let (batch, skipped, nulled) = read(OnError::Null);
+    assert_eq!((batch.num_rows(), skipped, nulled), (4, 1, 2));
+    let a = batch
+        .column(0)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    let b = batch
+        .column(1)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    assert_eq!(a.values(), &[1, 3, 0, 10]);
+    assert!(a.is_null(2) && b.is_null(1));
+    assert_eq!(b.value(2), 6);
+
+    // Lines with only whitespace are records to the CSV reader, unlike empty lines.
+    let rejected = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let listener = rejected.clone();
+    let mut reader = TolerantReader::new(
+        "a,b\n1,2\n\n \r\n3,4\n".as_bytes(),
+        RecordFormat::Csv(CsvOptions::default()),
+        schema.clone(),
+        OnError::Skip,
+        None,
+        None,
+    )
+    .with_listener(move |rejected| listener.lock().unwrap().push(rejected));
+    assert_eq!(reader.next().unwrap().unwrap().num_rows(), 2);
+    let rejected = rejected.lock().unwrap();
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(rejected[0].position.line, Some(4));
+    assert_eq!(rejected[0].position.record, Some(1));
+
+    let path = std::env::temp_dir().join("arrow-tools-on-error.jsonl");
+    let rejects = Box::new(std::fs::File::create(&path).unwrap());
+    let json = "{\"a\":1,\"b\":2}\n{\"a\":\"x\",\"b\":4}\n{\"a\":5\n";
+    let mut reader = TolerantReader::new(
+        json.as_bytes(),
+        RecordFormat::Json,
+        schema.clone(),
+        OnError::Null,
+        Some(1),
+        Some(rejects),
+    )
+    .with_batch_size(1);
+    assert_eq!(reader.next().unwrap().unwrap().num_rows(), 1);
+    let batch = reader.next().unwrap().unwrap();
+    assert!(batch.column(0).is_null(0) && !batch.column(1).is_null(0));
+    assert!(reader
+        .next()
+        .unwrap()
+        .unwrap_err()
+        .to_string()
+        .contains("more than the maximum of 1"));
+    drop(reader);
+    let lines: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["action"], "null");
+    assert_eq!(lines[0]["line"], 2);
+    assert_eq!(lines[1]["action"], "skip");
+    assert_eq!(lines[1]["data"], "{\"a\":5");
+
+    // Nested fields that fail are set to null on their own.
+    let nested = Arc::new(Schema::new(vec![Field::new_struct(
+        "s",
+        vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Int64, true),
+        ],
+        true,
+    )]));
+    let rejected = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let listener = rejected.clone();
+    let mut reader = TolerantReader::new(
+        "{\"s\":{\"a\":\"x\",\"b\":1}}\n".as_bytes(),
+        RecordFormat::Json,
+        nested,
+        OnError::Null,
+        None,
+        None,
+    )
+    .with_listener(move |rejected| listener.lock().unwrap().push(rejected));
+    let batch = reader.next().unwrap().unwrap();
+    let s = batch.column(0).as_struct();
+    assert!(!s.is_null(0) && s.column(0).is_null(0));
+    assert_eq!(s.column(1).as_primitive::<Int64Type>().value(0), 1);
+    let rejected = rejected.lock().unwrap();
+    assert_eq!(rejected[0].position.column.as_deref(), Some("s.a"));
+
+    // Records that can't be read are left out of schema inference too.
+    let open = |input: &'static str, options| {
+        Converter::new(Box::new(input.as_bytes()) as Box<dyn Read>, options)
+    };
+    let options = CsvOptions {
+        on_error: OnError::Skip,
+        ..Default::default()
+    };
+    let mut converter = open("a,b\n1,2\n4\n5,6,7\n8,9\n", FormatOptions::Csv(options));
+    assert_eq!(converter.schema().unwrap().fields().len(), 2);
+    let summary = converter.write(std::io::sink()).unwrap();
+    assert_eq!((summary.rows, summary.skipped), (2, 2));
+    let options = JsonOptions {
+        on_error: OnError::Skip,
+        ..Default::default()
+    };
+    let mut converter = open("{\"a\":1}\n[2]\n{\"a\":\n", FormatOptions::Json(options));
+    assert_eq!(
+        converter.schema().unwrap().field(0).data_type(),
+        &DataType::Int64
+    );
+    let summary = converter.write(std::io::sink()).unwrap();
+    assert_eq!((summary.rows, summary.skipped), (1, 2));
+}
+
+#[test]
+fn validate() {
+    use super::converter::Converter;
+    use super::formats::{CsvOptions, FormatOptions, JsonOptions};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::{Arc, Mutex};
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int64, false),
+        Field::new("b", DataType::Int64, true),
+    ]));
+    let path = std::env::temp_dir().join("arrow-tools-validate.json");
+    std::fs::write(
+        &path,
+        "{\"a\":1,\"b\":2}\n{\"b\":3}\n\n{\"a\":\"x\"}\n{\"a\":4}\n",
+    )
+    .unwrap();
+    let rejected = Arc::new(Mutex::new(Vec::new()));
+    let listener = rejected.clone();
+    let summary = Converter::new(&path, FormatOptions::Json(JsonOptions::default()))
+        .with_schema(schema)
+        .validate(move |rejected| listener.lock().unwrap().push(rejected))
+        .unwrap();
+    assert_eq!((summary.rows, summary.invalid), (2, 2));
+    let rejected = rejected.lock().unwrap();
+    let positions: Vec<_> = rejected
+        .iter()
+        .map(|rejected| {
+            let position = &rejected.position;
+            (position.line, position.record, position.byte_offset)
+        })
+        .collect();
+    assert_eq!(
+        positions,
+        vec![(Some(2), Some(1), Some(14)), (Some(4), Some(2), Some(23))]
+    );
+    assert_eq!(rejected[0].position.column.as_deref(), Some("a"));
+    assert_eq!(rejected[1].data, "{\"a\":\"x\"}");
+
+    // Records that can't be read are left out of inference to be reported.
+    let input = Box::new(&b"a,b\n1,2\n4\n5,6,7\n8,9\n"[..]) as Box<dyn Read>;
+    let summary = Converter::new(input, FormatOptions::Csv(CsvOptions::default()))
+        .validate(|_| {})
+        .unwrap();
+    assert_eq!((summary.rows, summary.invalid), (2, 2));
+}
+
+#[test]
+fn schema_diff() {
+    use super::schema::{diff_schemas, SchemaChange};
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    let reference = Schema::new(vec![
+        Field::new("a", DataType::Int64, false),
+        Field::new("b", DataType::Utf8, true),
+        Field::new("c", DataType::Boolean, true),
+        Field::new("d", DataType::Float64, true),
+    ]);
+    let new = Schema::new(vec![
+        Field::new("a", DataType::Int64, true),
+        Field::new("c", DataType::Boolean, false),
+        Field::new("b", DataType::LargeUtf8, true),
+        Field::new("e", DataType::Date32, true),
+    ]);
+    let changes = diff_schemas(&reference, &new);
+    assert_eq!(
+        changes,
+        vec![
+            SchemaChange::NullabilityChanged {
+                name: "a".to_string(),
+                from: false,
+                to: true
+            },
+            SchemaChange::Reordered {
+                name: "b".to_string(),
+                from: 1,
+                to: 2
+            },
+            SchemaChange::TypeChanged {
+                name: "b".to_string(),
+                from: DataType::Utf8,
+                to: DataType::LargeUtf8
+            },
+            SchemaChange::Reordered {
+                name: "c".to_string(),
+                from: 2,
+                to: 1
+            },
+            SchemaChange::NullabilityChanged {
+                name: "c".to_string(),
+                from: true,
+                to: false
+            },
+            SchemaChange::Removed {
+                name: "d".to_string()
+            },
+            SchemaChange::Added {
+                name: "e".to_string(),
+                data_type: DataType::Date32
+            },
+        ]
+    );
+    let breaking: Vec<bool> = changes.iter().map(SchemaChange::is_breaking).collect();
+    assert_eq!(breaking, [true, false, true, false, false, true, false]);
+    assert_eq!(changes[0].to_string(), "~ a: now nullable");
+    assert!(diff_schemas(&reference, &reference).is_empty());
+}
+
+#[test]
+fn schema_from() {
+    use super::converter::Converter;
+    use super::formats::{CsvOptions, FormatOptions};
+    use super::schema::read_file_schema;
+    use super::writer::OutputFormat;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::collections::HashMap;
+    use std::fs::File;
+    use std::sync::Arc;
+
+    let metadata = HashMap::from([("unit".to_string(), "seconds".to_string())]);
+    let schema = Arc::new(Schema::new_with_metadata(
+        vec![
+            Field::new("a", DataType::Int64, false).with_metadata(metadata),
+            Field::new("b", DataType::Boolean, true),
+        ],
+        HashMap::from([("table".to_string(), "events".to_string())]),
+    ));
+    let options = FormatOptions::Csv(CsvOptions::default());
+    for (output_format, extension) in [
+        (OutputFormat::Parquet, "parquet"),
+        (OutputFormat::Arrow, "arrow"),
+    ] {
+        let existing = std::env::temp_dir().join(format!("arrow-tools-schema-from.{extension}"));
+        let output = std::env::temp_dir().join(format!("arrow-tools-schema-from-new.{extension}"));
+        Converter::new("../../data/simple.csv", options.clone())
+            .with_schema(schema.clone())
+            .with_output_format(output_format)
+            .write(File::create(&existing).unwrap())
+            .unwrap();
+        let summary = Converter::new("../../data/simple.csv", options.clone())
+            .with_schema_from(&existing)
+            .with_output_format(output_format)
+            .write(File::create(&output).unwrap())
+            .unwrap();
+        assert_eq!(summary.schema, schema);
+        assert_eq!(
+            read_file_schema(&output).unwrap().as_ref(),
+            Some(schema.as_ref())
+        );
+    }
+
+    let error = Converter::new("../../data/simple.csv", options)
+        .with_schema_from("../../data/simple.csv")
+        .schema()
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("neither a Parquet nor an Arrow file"));
+}
+
+#[test]
+#[cfg(feature = "cli")]
+fn cli_errors() {
+    use super::cli::run_alias_from;
+    use super::error::ErrorKind;
+    use super::formats::InputFormat;
+    use super::writer::OutputFormat;
+
+    // Errors of the arguments are returned for the binaries to exit with, not exited on.
+    for args in [&["csv2parquet", "--help"][..], &["csv2parquet", "--bogus"]] {
+        let error = run_alias_from(InputFormat::Csv, OutputFormat::Parquet, args).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Usage);
+    }
+}
@@ -0,0 +1,120 @@
+//! Converting streams of JSON values into Arrow record batches, for formats that map onto
+//! the JSON data model. Schemas are inferred with the same rules as the Arrow JSON reader.
+
+use arrow::datatypes::{DataType, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::json::reader::{infer_json_schema_from_iterator, Decoder, ReaderBuilder};
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use chrono::{DateTime, SecondsFormat};
+use serde_json::Value;
+
+const BATCH_SIZE: usize = 1024;
+
+/// Infers the schema from the first `max_read_records` values, or all values if `None`.
+pub fn infer_schema<I>(values: I, max_read_records: Option<usize>) -> Result<Schema, ArrowError>
+where
+    I: Iterator<Item = Result<Value, ArrowError>>,
+{
+    infer_json_schema_from_iterator(values.take(max_read_records.unwrap_or(usize::MAX)))
+}
+
+/// Encodes binary data as a hex string, which the Arrow JSON decoder reads into binary
+/// columns.
+pub fn hex(bytes: &[u8]) -> Value {
+    Value::String(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+/// Formats a point in time as an RFC 3339 string in UTC, or null if it is out of range.
+pub fn timestamp(seconds: i64, nanoseconds: u32) -> Value {
+    match DateTime::from_timestamp(seconds, nanoseconds) {
+        Some(time) => Value::String(time.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+        None => Value::Null,
+    }
+}
+
+/// Converts a float to a JSON number, or null if it is not finite.
+pub fn float(value: f64) -> Value {
+    serde_json::Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+/// Wraps single values in a list where the schema expects a list, since formats like XML
+/// can't tell a list with one element from a single value.
+fn conform(value: Value, data_type: &DataType) -> Value {
+    match (value, data_type) {
+        (Value::Array(values), DataType::List(field) | DataType::LargeList(field)) => Value::Array(
+            values
+                .into_iter()
+                .map(|value| conform(value, field.data_type()))
+                .collect(),
+        ),
+        (Value::Null, _) => Value::Null,
+        (value, DataType::List(field) | DataType::LargeList(field)) => {
+            Value::Array(vec![conform(value, field.data_type())])
+        }
+        (Value::Object(mut object), DataType::Struct(fields)) => {
+            for field in fields {
+                if let Some(value) = object.get_mut(field.name()) {
+                    *value = conform(value.take(), field.data_type());
+                }
+            }
+            Value::Object(object)
+        }
+        (value, _) => value,
+    }
+}
+
+/// Iterates over record batches of JSON values, one row per value.
+pub struct ValueReader<I> {
+    values: I,
+    schema: SchemaRef,
+    decoder: Decoder,
+}
+
+impl<I> ValueReader<I>
+where
+    I: Iterator<Item = Result<Value, ArrowError>>,
+{
+    pub fn try_new(values: I, schema: SchemaRef) -> Result<Self, ArrowError> {
+        let decoder = ReaderBuilder::new(schema.clone())
+            .with_batch_size(BATCH_SIZE)
+            .with_coerce_primitive(true)
+            .build_decoder()?;
+        Ok(ValueReader {
+            values,
+            schema,
+            decoder,
+        })
+    }
+
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>, ArrowError> {
+        let data_type = DataType::Struct(self.schema.fields().clone());
+        let values = self
+            .values
+            .by_ref()
+            .take(BATCH_SIZE)
+            .map(|value| value.map(|value| conform(value, &data_type)))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.decoder.serialize(&values)?;
+        self.decoder.flush()
+    }
+}
+
+impl<I> Iterator for ValueReader<I>
+where
+    I: Iterator<Item = Result<Value, ArrowError>>,
+{
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_batch().transpose()
+    }
+}
+
+impl<I> RecordBatchReader for ValueReader<I>
+where
+    I: Iterator<Item = Result<Value, ArrowError>>,
+{
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
@@ -0,0 +1,466 @@
+//! Writing record batches to Arrow IPC and Parquet files.
+
+use arrow::datatypes::SchemaRef;
+use arrow::error::ArrowError;
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::arrow_writer::{compute_leaves, ArrowColumnWriter, ArrowRowGroupWriterFactory};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{BrotliLevel, Compression, Encoding, GzipLevel, ZstdLevel};
+use parquet::file::metadata::ParquetMetaData;
+use parquet::file::properties::{EnabledStatistics, WriterProperties};
+use parquet::file::writer::SerializedFileWriter;
+use std::ffi::OsStr;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// The file format to write.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum OutputFormat {
+    /// The Arrow IPC file format.
+    Arrow,
+    /// Apache Parquet.
+    Parquet,
+}
+
+impl OutputFormat {
+    /// Picks the format from the extension of the output file.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path
+            .extension()
+            .and_then(OsStr::to_str)?
+            .to_lowercase()
+            .as_str()
+        {
+            "arrow" | "ipc" | "feather" => Some(OutputFormat::Arrow),
+            "parquet" | "pq" => Some(OutputFormat::Parquet),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Arrow => "arrow",
+            OutputFormat::Parquet => "parquet",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum ParquetCompression {
+    UNCOMPRESSED,
+    SNAPPY,
+    GZIP,
+    LZO,
+    BROTLI,
+    LZ4,
+    ZSTD,
+    LZ4_RAW,
+}
+
+impl From<ParquetCompression> for Compression {
+    fn from(compression: ParquetCompression) -> Self {
+        match compression {
+            ParquetCompression::UNCOMPRESSED => Compression::UNCOMPRESSED,
+            ParquetCompression::SNAPPY => Compression::SNAPPY,
+            ParquetCompression::GZIP => Compression::GZIP(GzipLevel::default()),
+            ParquetCompression::LZO => Compression::LZO,
+            ParquetCompression::BROTLI => Compression::BROTLI(BrotliLevel::default()),
+            ParquetCompression::LZ4 => Compression::LZ4,
+            ParquetCompression::ZSTD => Compression::ZSTD(ZstdLevel::default()),
+            ParquetCompression::LZ4_RAW => Compression::LZ4_RAW,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum ParquetEncoding {
+    PLAIN,
+    PLAIN_DICTIONARY,
+    RLE,
+    RLE_DICTIONARY,
+    DELTA_BINARY_PACKED,
+    DELTA_LENGTH_BYTE_ARRAY,
+    DELTA_BYTE_ARRAY,
+    BYTE_STREAM_SPLIT,
+}
+
+impl From<ParquetEncoding> for Encoding {
+    fn from(encoding: ParquetEncoding) -> Self {
+        match encoding {
+            ParquetEncoding::PLAIN => Encoding::PLAIN,
+            ParquetEncoding::PLAIN_DICTIONARY => Encoding::PLAIN_DICTIONARY,
+            ParquetEncoding::RLE => Encoding::RLE,
+            ParquetEncoding::RLE_DICTIONARY => Encoding::RLE_DICTIONARY,
+            ParquetEncoding::DELTA_BINARY_PACKED => Encoding::DELTA_BINARY_PACKED,
+            ParquetEncoding::DELTA_LENGTH_BYTE_ARRAY => Encoding::DELTA_LENGTH_BYTE_ARRAY,
+            ParquetEncoding::DELTA_BYTE_ARRAY => Encoding::DELTA_BYTE_ARRAY,
+            ParquetEncoding::BYTE_STREAM_SPLIT => Encoding::BYTE_STREAM_SPLIT,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ParquetEnabledStatistics {
+    None,
+    Chunk,
+    Page,
+}
+
+impl From<ParquetEnabledStatistics> for EnabledStatistics {
+    fn from(statistics: ParquetEnabledStatistics) -> Self {
+        match statistics {
+            ParquetEnabledStatistics::None => EnabledStatistics::None,
+            ParquetEnabledStatistics::Chunk => EnabledStatistics::Chunk,
+            ParquetEnabledStatistics::Page => EnabledStatistics::Page,
+        }
+    }
+}
+
+/// Settings of the Parquet writer. Unset options use the defaults of the `parquet` crate.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct ParquetOptions {
+    /// Set the compression.
+    #[cfg_attr(feature = "cli", arg(short, long, value_enum))]
+    pub compression: Option<ParquetCompression>,
+
+    /// Sets encoding for any column.
+    #[cfg_attr(feature = "cli", arg(short, long, value_enum))]
+    pub encoding: Option<ParquetEncoding>,
+
+    /// Sets data page size limit.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub data_page_size_limit: Option<usize>,
+
+    /// Sets dictionary page size limit.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub dictionary_page_size_limit: Option<usize>,
+
+    /// Sets write batch size.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub write_batch_size: Option<usize>,
+
+    /// Sets max size for a row group.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub max_row_group_size: Option<usize>,
+
+    /// Sets "created by" property.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub created_by: Option<String>,
+
+    /// Sets flag to enable/disable dictionary encoding for any column.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub dictionary: Option<bool>,
+
+    /// Sets flag to enable/disable statistics for any column.
+    #[cfg_attr(feature = "cli", arg(long, value_enum))]
+    pub statistics: Option<ParquetEnabledStatistics>,
+
+    /// The number of threads that encode and compress the columns of a row group. 1 if not present.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub write_threads: Option<usize>,
+
+    /// Flushes row groups once their estimated size reaches this many bytes, e.g. to stay
+    /// within a memory limit.
+    #[cfg_attr(feature = "cli", arg(skip))]
+    pub max_row_group_bytes: Option<usize>,
+}
+
+impl ParquetOptions {
+    pub fn writer_properties(&self) -> WriterProperties {
+        let mut props = WriterProperties::builder();
+
+        if let Some(enabled) = self.dictionary {
+            props = props.set_dictionary_enabled(enabled);
+        }
+
+        if let Some(statistics) = self.statistics {
+            props = props.set_statistics_enabled(statistics.into());
+        }
+
+        if let Some(compression) = self.compression {
+            props = props.set_compression(compression.into());
+        }
+
+        if let Some(encoding) = self.encoding {
+            props = props.set_encoding(encoding.into());
+        }
+
+        if let Some(size) = self.write_batch_size {
+            props = props.set_write_batch_size(size);
+        }
+
+        if let Some(size) = self.data_page_size_limit {
+            props = props.set_data_page_size_limit(size);
+        }
+
+        if let Some(size) = self.dictionary_page_size_limit {
+            props = props.set_dictionary_page_size_limit(size);
+        }
+
+        if let Some(size) = self.max_row_group_size {
+            props = props.set_max_row_group_row_count(Some(size));
+        }
+
+        if let Some(bytes) = self.max_row_group_bytes {
+            props = props.set_max_row_group_bytes(Some(bytes.max(1)));
+        }
+
+        if let Some(ref created_by) = self.created_by {
+            props = props.set_created_by(created_by.clone());
+        }
+
+        props.build()
+    }
+}
+
+/// The bytes of batches that are collected before they are encoded, so that each thread
+/// has enough work at a time.
+const ENCODE_BYTES: usize = 8 * 1024 * 1024;
+
+/// Writes Parquet files with the columns of each row group encoded and compressed on
+/// several threads.
+///
+/// Batches are collected until they reach a few megabytes, then each column is encoded
+/// into the column writer of the row group by the next free thread. When the row group is
+/// full, or its encoded size reaches the maximum row group bytes, the column chunks are
+/// closed the same way and appended in order.
+pub struct ParallelParquetWriter<W: Write + Send> {
+    writer: SerializedFileWriter<W>,
+    factory: ArrowRowGroupWriterFactory,
+    schema: SchemaRef,
+    threads: usize,
+    max_rows: usize,
+    max_bytes: usize,
+    row_groups: usize,
+    /// The column writers of the row group that is being written.
+    columns: Option<Vec<ArrowColumnWriter>>,
+    /// The batches that are not encoded yet.
+    pending: Vec<RecordBatch>,
+    /// The size of the pending batches, counting only the parts of their buffers that they
+    /// use.
+    pending_bytes: usize,
+    rows: usize,
+}
+
+impl<W: Write + Send> ParallelParquetWriter<W> {
+    pub fn try_new(
+        output: W,
+        schema: SchemaRef,
+        properties: WriterProperties,
+        threads: usize,
+    ) -> Result<Self, ArrowError> {
+        // The Arrow writer adds the Arrow schema to the metadata, as in single-threaded files.
+        let (writer, factory) = ArrowWriter::try_new(output, schema.clone(), Some(properties))?
+            .into_serialized_writer()?;
+        let max_rows = writer
+            .properties()
+            .max_row_group_row_count()
+            .unwrap_or(usize::MAX)
+            .max(1);
+        let max_bytes = writer
+            .properties()
+            .max_row_group_bytes()
+            .unwrap_or(usize::MAX);
+        Ok(ParallelParquetWriter {
+            writer,
+            factory,
+            schema,
+            threads: threads.max(1),
+            max_rows,
+            max_bytes,
+            row_groups: 0,
+            columns: None,
+            pending: Vec::new(),
+            pending_bytes: 0,
+            rows: 0,
+        })
+    }
+
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<(), ArrowError> {
+        let mut offset = 0;
+        while offset < batch.num_rows() {
+            let length = (self.max_rows - self.rows).min(batch.num_rows() - offset);
+            let slice = batch.slice(offset, length);
+            for column in slice.columns() {
+                self.pending_bytes += column.to_data().get_slice_memory_size()?;
+            }
+            self.pending.push(slice);
+            self.rows += length;
+            offset += length;
+            if self.pending_bytes >= ENCODE_BYTES {
+                self.encode()?;
+            }
+            if self.rows == self.max_rows || self.in_progress_bytes() >= self.max_bytes {
+                self.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The estimated size of the row group that is being written.
+    fn in_progress_bytes(&self) -> usize {
+        let encoded: usize = self
+            .columns
+            .iter()
+            .flatten()
+            .map(ArrowColumnWriter::get_estimated_total_bytes)
+            .sum();
+        encoded + self.pending_bytes
+    }
+
+    /// Encodes the pending batches into the column writers of the row group.
+    fn encode(&mut self) -> Result<(), ArrowError> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let columns = match self.columns {
+            Some(ref mut columns) => columns,
+            None => self
+                .columns
+                .insert(self.factory.create_column_writers(self.row_groups)?),
+        };
+        let mut leaves: Vec<Vec<_>> = columns.iter().map(|_| Vec::new()).collect();
+        for batch in self.pending.drain(..) {
+            let mut columns = leaves.iter_mut();
+            for (field, array) in self.schema.fields().iter().zip(batch.columns()) {
+                for leaf in compute_leaves(field, array)? {
+                    columns.next().unwrap().push(leaf);
+                }
+            }
+        }
+        self.pending_bytes = 0;
+
+        let threads = self.threads.min(columns.len());
+        let jobs = Mutex::new(columns.iter_mut().zip(leaves));
+        let result = Mutex::new(Ok(()));
+        std::thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| loop {
+                    let Some((writer, leaves)) = jobs.lock().unwrap().next() else {
+                        break;
+                    };
+                    if let Err(error) = leaves.iter().try_for_each(|leaf| writer.write(leaf)) {
+                        *result.lock().unwrap() = Err(error);
+                    }
+                });
+            }
+        });
+        Ok(result.into_inner().unwrap()?)
+    }
+
+    /// Encodes the pending batches and writes the row group.
+    fn flush(&mut self) -> Result<(), ArrowError> {
+        self.encode()?;
+        let Some(columns) = self.columns.take() else {
+            return Ok(());
+        };
+
+        // Closing a column writer compresses its last page.
+        let threads = self.threads.min(columns.len());
+        let jobs = Mutex::new(columns.into_iter().enumerate());
+        let chunks = Mutex::new(Vec::new());
+        std::thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| loop {
+                    let Some((index, writer)) = jobs.lock().unwrap().next() else {
+                        break;
+                    };
+                    let chunk = writer.close();
+                    chunks.lock().unwrap().push((index, chunk));
+                });
+            }
+        });
+
+        let mut chunks = chunks.into_inner().unwrap();
+        chunks.sort_by_key(|&(index, _)| index);
+        let mut row_group = self.writer.next_row_group()?;
+        for (_, chunk) in chunks {
+            chunk?.append_to_row_group(&mut row_group)?;
+        }
+        row_group.close()?;
+        self.row_groups += 1;
+        self.rows = 0;
+        Ok(())
+    }
+
+    /// Writes the last row group and the footer of the file.
+    pub fn finish(mut self) -> Result<ParquetMetaData, ArrowError> {
+        self.flush()?;
+        Ok(self.writer.close()?)
+    }
+}
+
+/// Writes record batches to an Arrow IPC or Parquet file.
+pub enum BatchWriter<W: Write + Send> {
+    Arrow(FileWriter<W>),
+    Parquet(ArrowWriter<W>),
+    ParallelParquet(ParallelParquetWriter<W>),
+}
+
+impl<W: Write + Send> BatchWriter<W> {
+    /// Starts a file with the schema. The Parquet options are ignored for Arrow files, and
+    /// Parquet files are encoded on several threads if `write_threads` is more than one.
+    pub fn try_new(
+        output: W,
+        schema: SchemaRef,
+        format: OutputFormat,
+        options: &ParquetOptions,
+    ) -> Result<Self, ArrowError> {
+        Ok(match format {
+            OutputFormat::Arrow => BatchWriter::Arrow(FileWriter::try_new(output, &schema)?),
+            OutputFormat::Parquet => match options.write_threads {
+                Some(threads) if threads > 1 => {
+                    BatchWriter::ParallelParquet(ParallelParquetWriter::try_new(
+                        output,
+                        schema,
+                        options.writer_properties(),
+                        threads,
+                    )?)
+                }
+                _ => BatchWriter::Parquet(ArrowWriter::try_new(
+                    output,
+                    schema,
+                    Some(options.writer_properties()),
+                )?),
+            },
+        })
+    }
+
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<(), ArrowError> {
+        match self {
+            BatchWriter::Arrow(writer) => writer.write(batch),
+            BatchWriter::Parquet(writer) => Ok(writer.write(batch)?),
+            BatchWriter::ParallelParquet(writer) => writer.write(batch),
+        }
+    }
+
+    /// The number of Parquet row groups that were written so far.
+    pub fn row_groups(&self) -> usize {
+        match self {
+            BatchWriter::Arrow(_) => 0,
+            BatchWriter::Parquet(writer) => writer.flushed_row_groups().len(),
+            BatchWriter::ParallelParquet(writer) => writer.row_groups,
+        }
+    }
+
+    /// Writes the footer of the file. Returns the metadata of Parquet files.
+    pub fn finish(self) -> Result<Option<ParquetMetaData>, ArrowError> {
+        match self {
+            BatchWriter::Arrow(mut writer) => {
+                writer.finish()?;
+                Ok(None)
+            }
+            BatchWriter::Parquet(writer) => Ok(Some(writer.close()?)),
+            BatchWriter::ParallelParquet(writer) => Ok(Some(writer.finish()?)),
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! Reading Excel and OpenDocument spreadsheets as Arrow record batches.
+
+use crate::infer::{InferredDataType, StringBatchBuilder};
+use arrow::datatypes::{Field, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use calamine::{open_workbook_auto, Data, Range, Reader};
+use regex::Regex;
+use std::path::Path;
+
+const BATCH_SIZE: usize = 1024;
+
+/// A zero-based `(row, column)` cell position.
+type CellPosition = (u32, u32);
+
+/// A rectangular block of cells from one worksheet, split into a header and data rows.
+pub struct Sheet {
+    cells: Range<Data>,
+    header: bool,
+    null_regex: Option<Regex>,
+}
+
+impl Sheet {
+    /// Opens a worksheet of a `.xlsx`, `.xlsm`, `.xlsb`, `.xls` or `.ods` workbook.
+    ///
+    /// `sheet` is either a sheet name or a zero-based sheet index and defaults to the first
+    /// sheet. `range` restricts the cells to an A1-style range such as `B2:F100`, or to
+    /// everything below and right of a single cell such as `B2`. Cells whose text matches
+    /// `null_regex` are read as null.
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        sheet: Option<&str>,
+        range: Option<&str>,
+        header: bool,
+        null_regex: Option<Regex>,
+    ) -> Result<Self, ArrowError> {
+        let mut workbook = open_workbook_auto(path).map_err(external)?;
+        let names = workbook.sheet_names();
+        let name = match sheet {
+            None => names.first(),
+            Some(sheet) => names
+                .iter()
+                .find(|name| *name == sheet)
+                .or_else(|| sheet.parse::<usize>().ok().and_then(|i| names.get(i))),
+        }
+        .ok_or_else(|| {
+            ArrowError::InvalidArgumentError(format!(
+                "Sheet {} not found, available sheets: {names:?}",
+                sheet.unwrap_or("0")
+            ))
+        })?
+        .clone();
+        let mut cells = workbook.worksheet_range(&name).map_err(external)?;
+        if let Some(range) = range {
+            let (start, end) = parse_range(range)?;
+            let end = match (end, cells.end()) {
+                (Some(end), _) => end,
+                (None, Some(used_end)) => used_end,
+                (None, None) => start,
+            };
+            cells = cells.range(start, end);
+        }
+        Ok(Sheet {
+            cells,
+            header,
+            null_regex,
+        })
+    }
+
+    fn column_names(&self) -> Vec<String> {
+        let width = self.cells.width();
+        (0..width)
+            .map(|col| match self.header {
+                true => match self.cells.get((0, col)) {
+                    Some(Data::Empty) | None => format!("column_{}", col + 1),
+                    Some(cell) => cell.to_string(),
+                },
+                false => format!("column_{}", col + 1),
+            })
+            .collect()
+    }
+
+    fn first_data_row(&self) -> usize {
+        usize::from(self.header)
+    }
+
+    /// Infers the schema from the first `max_read_records` data rows, or all rows if `None`.
+    pub fn infer_schema(&self, max_read_records: Option<usize>) -> Schema {
+        let names = self.column_names();
+        let mut types = vec![InferredDataType::default(); names.len()];
+        let rows = self.cells.height().saturating_sub(self.first_data_row());
+        let rows = max_read_records.map_or(rows, |max| rows.min(max));
+        for row in self.first_data_row()..self.first_data_row() + rows {
+            for (col, inferred) in types.iter_mut().enumerate() {
+                let cell = self.cells.get((row, col));
+                match (cell, cell_value(cell, self.null_regex.as_ref())) {
+                    (_, None) => {}
+                    (Some(Data::String(_)) | Some(Data::DurationIso(_)), _) => {
+                        inferred.update_utf8()
+                    }
+                    (_, Some(value)) => inferred.update(&value),
+                }
+            }
+        }
+        if max_read_records == Some(0) {
+            types.iter_mut().for_each(InferredDataType::update_utf8);
+        }
+        Schema::new(
+            names
+                .into_iter()
+                .zip(types)
+                .map(|(name, inferred)| Field::new(name, inferred.data_type(), true))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Returns a reader over the data rows, converted to the given schema.
+    pub fn into_reader(self, schema: SchemaRef) -> SheetReader {
+        SheetReader {
+            row: self.first_data_row(),
+            builder: StringBatchBuilder::new(schema.clone()),
+            schema,
+            cells: self.cells,
+            null_regex: self.null_regex,
+        }
+    }
+}
+
+/// Iterates over the data rows of a [`Sheet`] in record batches.
+pub struct SheetReader {
+    schema: SchemaRef,
+    cells: Range<Data>,
+    builder: StringBatchBuilder,
+    row: usize,
+    null_regex: Option<Regex>,
+}
+
+impl Iterator for SheetReader {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let end = self.cells.height().min(self.row + BATCH_SIZE);
+        if self.row >= end {
+            return None;
+        }
+        let width = self.schema.fields().len();
+        for row in self.row..end {
+            self.builder.append_row(
+                (0..width)
+                    .map(|col| cell_value(self.cells.get((row, col)), self.null_regex.as_ref())),
+            );
+        }
+        self.row = end;
+        Some(self.builder.finish())
+    }
+}
+
+impl RecordBatchReader for SheetReader {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// The text of a cell, or `None` if it is empty or matches the null regex.
+fn cell_value(cell: Option<&Data>, null_regex: Option<&Regex>) -> Option<String> {
+    cell.and_then(cell_to_string)
+        .filter(|value| !null_regex.is_some_and(|regex| regex.is_match(value)))
+}
+
+/// Formats a cell so that it parses as the matching Arrow type. Excel date serials
+/// become ISO dates, or timestamps if they have a time component.
+fn cell_to_string(cell: &Data) -> Option<String> {
+    match cell {
+        Data::Empty | Data::Error(_) => None,
+        Data::DateTime(datetime) if datetime.is_datetime() => {
+            let datetime = datetime.as_datetime()?;
+            if datetime.and_utc().timestamp_millis() % 86_400_000 == 0 {
+                Some(datetime.format("%Y-%m-%d").to_string())
+            } else {
+                Some(datetime.format("%Y-%m-%dT%H:%M:%S%.3f").to_string())
+            }
+        }
+        Data::DateTime(duration) => Some(duration.as_f64().to_string()),
+        cell => Some(cell.to_string()),
+    }
+}
+
+/// Parses `A1` or `A1:C10` into zero-based `(row, column)` corners.
+fn parse_range(range: &str) -> Result<(CellPosition, Option<CellPosition>), ArrowError> {
+    let invalid = || ArrowError::InvalidArgumentError(format!("Invalid cell range: {range}"));
+    let parse_cell = |cell: &str| -> Option<CellPosition> {
+        let split = cell.find(|c: char| c.is_ascii_digit())?;
+        let (letters, digits) = cell.split_at(split);
+        if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        let col = letters.chars().try_fold(0u32, |acc, c| {
+            acc.checked_mul(26)?
+                .checked_add(c.to_ascii_uppercase() as u32 - 'A' as u32 + 1)
+        })?;
+        let row = digits.parse::<u32>().ok()?;
+        Some((row.checked_sub(1)?, col - 1))
+    };
+    match range.split_once(':') {
+        Some((start, end)) => Ok((
+            parse_cell(start).ok_or_else(invalid)?,
+            Some(parse_cell(end).ok_or_else(invalid)?),
+        )),
+        None => Ok((parse_cell(range).ok_or_else(invalid)?, None)),
+    }
+}
+
+fn external(error: calamine::Error) -> ArrowError {
+    ArrowError::ExternalError(Box::new(error))
+}
@@ -0,0 +1,194 @@
+//! Streaming records out of XML documents as JSON values.
+//!
+//! Attributes and child elements of a record become fields, child elements with their own
+//! children or attributes become nested objects, and repeated elements become lists. Text
+//! next to child elements or attributes is stored in a `#text` field.
+
+use crate::infer::InferredDataType;
+use crate::values::float;
+use arrow::datatypes::DataType;
+use arrow::error::ArrowError;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde_json::{Map, Value};
+use std::io::BufRead;
+
+/// The field that holds the text of elements that also have attributes or children.
+pub const TEXT_FIELD: &str = "#text";
+
+/// Iterates over the elements at a path in an XML document, without loading the whole
+/// document.
+pub struct XmlRecords<R> {
+    reader: Reader<R>,
+    path: Vec<String>,
+    anchored: bool,
+    stack: Vec<String>,
+    buf: Vec<u8>,
+}
+
+impl<R: BufRead> XmlRecords<R> {
+    /// Creates an iterator over the elements at `path`, e.g. `catalog/book`. The path matches
+    /// at any depth unless it starts with `/`.
+    pub fn new(reader: R, path: &str) -> Result<Self, ArrowError> {
+        let anchored = path.starts_with('/');
+        let path: Vec<String> = path
+            .split('/')
+            .filter(|name| !name.is_empty())
+            .map(String::from)
+            .collect();
+        if path.is_empty() {
+            return Err(ArrowError::InvalidArgumentError(
+                "The record path needs at least one element name".to_string(),
+            ));
+        }
+        Ok(XmlRecords {
+            reader: Reader::from_reader(reader),
+            path,
+            anchored,
+            stack: Vec::new(),
+            buf: Vec::new(),
+        })
+    }
+
+    fn is_record(&self) -> bool {
+        if self.anchored {
+            self.stack == self.path
+        } else {
+            self.stack.ends_with(&self.path)
+        }
+    }
+
+    fn error(&self, error: impl std::fmt::Display) -> ArrowError {
+        ArrowError::ParseError(format!(
+            "Error reading XML at byte {}: {error}",
+            self.reader.buffer_position()
+        ))
+    }
+
+    /// Reads the content of an element up to its end tag.
+    fn read_element(&mut self, start: &BytesStart) -> Result<Map<String, Value>, ArrowError> {
+        let mut object = self.attributes(start)?;
+        let mut text = String::new();
+        let mut buf = Vec::new();
+        loop {
+            let event = self
+                .reader
+                .read_event_into(&mut buf)
+                .map_err(|error| self.error(error))?;
+            match event {
+                Event::Start(start) => {
+                    let start = start.into_owned();
+                    let child = self.read_element(&start)?;
+                    insert(&mut object, name(&start), collapse(child));
+                }
+                Event::Empty(start) => {
+                    let child = self.attributes(&start)?;
+                    insert(&mut object, name(&start), collapse(child));
+                }
+                Event::Text(value) => {
+                    text.push_str(&value.unescape().map_err(|error| self.error(error))?)
+                }
+                Event::CData(value) => text.push_str(&String::from_utf8_lossy(&value)),
+                Event::End(_) => break,
+                Event::Eof => return Err(self.error("unexpected end of document")),
+                _ => {}
+            }
+            buf.clear();
+        }
+        let text = text.trim();
+        if !text.is_empty() {
+            object.insert(TEXT_FIELD.to_string(), typed_value(text));
+        }
+        Ok(object)
+    }
+
+    fn attributes(&self, start: &BytesStart) -> Result<Map<String, Value>, ArrowError> {
+        let mut object = Map::new();
+        for attribute in start.attributes() {
+            let attribute = attribute.map_err(|error| self.error(error))?;
+            if attribute.key.as_namespace_binding().is_some() {
+                continue;
+            }
+            let value = attribute
+                .unescape_value()
+                .map_err(|error| self.error(error))?;
+            let key = String::from_utf8_lossy(attribute.key.local_name().as_ref()).into_owned();
+            insert(&mut object, key, typed_value(&value));
+        }
+        Ok(object)
+    }
+}
+
+impl<R: BufRead> Iterator for XmlRecords<R> {
+    type Item = Result<Value, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let event = match self.reader.read_event_into(&mut self.buf) {
+                Ok(event) => event.into_owned(),
+                Err(error) => return Some(Err(self.error(error))),
+            };
+            self.buf.clear();
+            match event {
+                Event::Start(start) => {
+                    self.stack.push(name(&start));
+                    if self.is_record() {
+                        let record = self.read_element(&start);
+                        self.stack.pop();
+                        return Some(record.map(Value::Object));
+                    }
+                }
+                Event::Empty(start) => {
+                    self.stack.push(name(&start));
+                    let is_record = self.is_record();
+                    self.stack.pop();
+                    if is_record {
+                        return Some(self.attributes(&start).map(Value::Object));
+                    }
+                }
+                Event::End(_) => {
+                    self.stack.pop();
+                }
+                Event::Eof => return None,
+                _ => {}
+            }
+        }
+    }
+}
+
+fn name(start: &BytesStart) -> String {
+    String::from_utf8_lossy(start.local_name().as_ref()).into_owned()
+}
+
+/// Adds a field, turning it into a list if the name repeats.
+fn insert(object: &mut Map<String, Value>, key: String, value: Value) {
+    match object.get_mut(&key) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+        None => {
+            object.insert(key, value);
+        }
+    }
+}
+
+/// Elements with only text become plain values and empty elements become null.
+fn collapse(mut object: Map<String, Value>) -> Value {
+    match object.len() {
+        0 => Value::Null,
+        1 if object.contains_key(TEXT_FIELD) => object.remove(TEXT_FIELD).unwrap(),
+        _ => Value::Object(object),
+    }
+}
+
+/// Converts text to a boolean or number if the CSV inference rules would read it as one.
+fn typed_value(text: &str) -> Value {
+    let mut inferred = InferredDataType::default();
+    inferred.update(text);
+    let value = match inferred.data_type() {
+        DataType::Boolean => text.to_lowercase().parse::<bool>().ok().map(Value::from),
+        DataType::Int64 => text.parse::<i64>().ok().map(Value::from),
+        DataType::Float64 => text.parse::<f64>().ok().map(float),
+        _ => None,
+    };
+    value.unwrap_or_else(|| Value::String(text.to_string()))
+}
@@ -0,0 +1,104 @@
+//! Reading YAML multi-document streams as JSON values.
+
+use crate::values::float;
+use arrow::error::ArrowError;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use serde_norway::{Deserializer, Value as YamlValue};
+use std::io::Read;
+
+/// Iterates over the records of a YAML stream.
+///
+/// Each document is a record, except for documents that are a sequence, whose elements are
+/// records. Empty documents are skipped, merge keys (`<<`) are applied, tags are dropped and
+/// keys that aren't strings are converted to strings. The whole stream is read on the first
+/// call to `next`.
+pub struct YamlDocuments<'a> {
+    documents: Deserializer<'a>,
+    index: usize,
+    records: std::vec::IntoIter<YamlValue>,
+}
+
+impl<'a> YamlDocuments<'a> {
+    pub fn new<R: Read + 'a>(reader: R) -> Self {
+        YamlDocuments {
+            documents: Deserializer::from_reader(reader),
+            index: 0,
+            records: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl Iterator for YamlDocuments<'_> {
+    type Item = Result<Value, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.records.next() {
+                return Some(Ok(to_json(record)));
+            }
+            let document = self.documents.next()?;
+            self.index += 1;
+            let mut value = match YamlValue::deserialize(document) {
+                Ok(value) => value,
+                Err(error) => {
+                    return Some(Err(ArrowError::ParseError(format!(
+                        "Error reading YAML document {}: {error}",
+                        self.index
+                    ))))
+                }
+            };
+            if let Err(error) = value.apply_merge() {
+                return Some(Err(ArrowError::ParseError(format!(
+                    "Error merging keys in YAML document {}: {error}",
+                    self.index
+                ))));
+            }
+            self.records = match untag(value) {
+                YamlValue::Null => Vec::new(),
+                YamlValue::Sequence(values) => values,
+                value => vec![value],
+            }
+            .into_iter();
+        }
+    }
+}
+
+fn untag(value: YamlValue) -> YamlValue {
+    match value {
+        YamlValue::Tagged(tagged) => untag(tagged.value),
+        value => value,
+    }
+}
+
+fn to_json(value: YamlValue) -> Value {
+    match value {
+        YamlValue::Null => Value::Null,
+        YamlValue::Bool(value) => Value::Bool(value),
+        YamlValue::Number(number) => match (number.as_i64(), number.as_u64(), number.as_f64()) {
+            (Some(value), _, _) => Value::from(value),
+            (None, Some(value), _) => Value::from(value),
+            (None, None, Some(value)) => float(value),
+            _ => Value::Null,
+        },
+        YamlValue::String(value) => Value::String(value),
+        YamlValue::Sequence(values) => Value::Array(values.into_iter().map(to_json).collect()),
+        YamlValue::Mapping(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key_string(key), to_json(value)))
+                .collect::<Map<_, _>>(),
+        ),
+        YamlValue::Tagged(tagged) => to_json(tagged.value),
+    }
+}
+
+fn key_string(key: YamlValue) -> String {
+    match untag(key) {
+        YamlValue::String(key) => key,
+        YamlValue::Null => "null".to_string(),
+        YamlValue::Bool(key) => key.to_string(),
+        YamlValue::Number(key) => key.to_string(),
+        key => to_json(key).to_string(),
+    }
+}
@@ -0,0 +1,96 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+#[allow(deprecated)]
+fn get_schema() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("bson2arrow")?;
+
+    let assert = cmd.arg("../../data/simple.bson").arg("-n").assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#"    {
+      "name": "price",
+      "data_type": {
+        "Decimal128": [
+          38,
+          2
+        ]
+      },
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn get_schema_gz() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("bson2arrow")?;
+
+    let assert = cmd.arg("../../data/simple.bson.gz").arg("-n").assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#"    {
+      "name": "_id",
+      "data_type": {
+        "FixedSizeBinary": 12
+      },
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn object_id_string() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("bson2arrow")?;
+
+    let assert = cmd
+        .arg("../../data/simple.bson")
+        .arg("--object-id")
+        .arg("string")
+        .arg("-n")
+        .assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#"    {
+      "name": "_id",
+      "data_type": "Utf8",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn help() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("bson2arrow")?;
+
+    let assert = cmd.arg("--help").assert();
+
+    assert
+        .success()
+        .stdout(predicate::str::contains(if cfg!(windows) {
+            "Usage: bson2arrow.exe [OPTIONS] <BSON> [ARROW]"
+        } else {
+            "Usage: bson2arrow [OPTIONS] <BSON> [ARROW]"
+        }));
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+#[allow(deprecated)]
+fn get_schema() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("bson2parquet")?;
+
+    let assert = cmd
+        .arg("../../data/simple.bson")
+        .arg("out.parquet")
+        .arg("-n")
+        .assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#"    {
+      "name": "price",
+      "data_type": {
+        "Decimal128": [
+          38,
+          2
+        ]
+      },
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn get_schema_gz() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("bson2parquet")?;
+
+    let assert = cmd
+        .arg("../../data/simple.bson.gz")
+        .arg("out.parquet")
+        .arg("-n")
+        .assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#"    {
+      "name": "_id",
+      "data_type": {
+        "FixedSizeBinary": 12
+      },
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn object_id_string() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("bson2parquet")?;
+
+    let assert = cmd
+        .arg("../../data/simple.bson")
+        .arg("--object-id")
+        .arg("string")
+        .arg("out.parquet")
+        .arg("-n")
+        .assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#"    {
+      "name": "_id",
+      "data_type": "Utf8",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn help() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("bson2parquet")?;
+
+    let assert = cmd.arg("--help").assert();
+
+    assert
+        .success()
+        .stdout(predicate::str::contains(if cfg!(windows) {
+            "Usage: bson2parquet.exe [OPTIONS] <BSON> [PARQUET]"
+        } else {
+            "Usage: bson2parquet [OPTIONS] <BSON> [PARQUET]"
+        }));
+
+    Ok(())
+}
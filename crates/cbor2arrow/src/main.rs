@@ -0,0 +1,12 @@
+use arrow_tools::cli::run_alias;
+use arrow_tools::error::exit_code;
+use arrow_tools::formats::InputFormat;
+use arrow_tools::writer::OutputFormat;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    exit_code(run_alias(InputFormat::Cbor, OutputFormat::Arrow))
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,81 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+#[allow(deprecated)]
+fn get_schema() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("cbor2arrow")?;
+
+    let assert = cmd.arg("../../data/simple.cbor").arg("-n").assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#"    {
+      "name": "point",
+      "data_type": {
+        "Struct": [
+          {
+            "name": "x",
+            "data_type": "Float64",
+            "nullable": true,
+            "dict_id": 0,
+            "dict_is_ordered": false,
+            "metadata": {}
+          },
+          {
+            "name": "y",
+            "data_type": "Float64",
+            "nullable": true,
+            "dict_id": 0,
+            "dict_is_ordered": false,
+            "metadata": {}
+          }
+        ]
+      },"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn get_schema_gz() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("cbor2arrow")?;
+
+    let assert = cmd.arg("../../data/simple.cbor.gz").arg("-n").assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#"    {
+      "name": "tags",
+      "data_type": {
+        "List": {
+          "name": "item",
+          "data_type": "Utf8",
+          "nullable": true,
+          "dict_id": 0,
+          "dict_is_ordered": false,
+          "metadata": {}
+        }
+      },"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn help() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("cbor2arrow")?;
+
+    let assert = cmd.arg("--help").assert();
+
+    assert
+        .success()
+        .stdout(predicate::str::contains(if cfg!(windows) {
+            "Usage: cbor2arrow.exe [OPTIONS] <CBOR> [ARROW]"
+        } else {
+            "Usage: cbor2arrow [OPTIONS] <CBOR> [ARROW]"
+        }));
+
+    Ok(())
+}
@@ -64,6 +64,89 @@ fn get_schema_gz() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+#[allow(deprecated)]
+fn config_file() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("csv2arrow")?;
+
+    let assert = cmd
+        .arg("--config")
+        .arg("../../data/simple.toml")
+        .arg("../../data/simple.csv")
+        .arg("-n")
+        .assert();
+
+    assert
+        .success()
+        .stdout(predicate::str::contains(r#""data_type": "Null""#))
+        .stdout(predicate::str::contains(r#""data_type": "Int64""#).not());
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn config_profile() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("csv2arrow")?;
+
+    let assert = cmd
+        .arg("--config")
+        .arg("../../data/simple.toml")
+        .arg("--profile")
+        .arg("typed")
+        .arg("../../data/simple.csv")
+        .arg("-n")
+        .assert();
+
+    assert
+        .success()
+        .stdout(predicate::str::contains(r#""data_type": "Int64""#));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn config_overridden_by_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("csv2arrow")?;
+
+    let assert = cmd
+        .arg("--config")
+        .arg("../../data/simple.toml")
+        .arg("-m")
+        .arg("10")
+        .arg("../../data/simple.csv")
+        .arg("-n")
+        .assert();
+
+    assert
+        .success()
+        .stdout(predicate::str::contains(r#""data_type": "Int64""#));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn config_unknown_profile() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("csv2arrow")?;
+
+    let assert = cmd
+        .arg("--config")
+        .arg("../../data/simple.toml")
+        .arg("--profile")
+        .arg("missing")
+        .arg("../../data/simple.csv")
+        .arg("-n")
+        .assert();
+
+    assert.failure().stderr(predicate::str::contains(
+        "available profiles: spark-compatible, typed",
+    ));
+
+    Ok(())
+}
+
 #[test]
 #[allow(deprecated)]
 fn help() -> Result<(), Box<dyn std::error::Error>> {
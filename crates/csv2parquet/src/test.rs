@@ -72,6 +72,306 @@ fn get_schema_gz() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+#[allow(deprecated)]
+fn threads() -> Result<(), Box<dyn std::error::Error>> {
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use std::io::Write;
+
+    // Large enough to be split into several chunks, with records that span lines and
+    // all of the line endings that the reader accepts.
+    let input = std::env::temp_dir().join("csv2parquet-threads.csv");
+    let mut file = std::io::BufWriter::new(std::fs::File::create(&input)?);
+    writeln!(file, "id,text,value")?;
+    for i in 0..300_000 {
+        let end = match i % 3 {
+            0 => "\n",
+            1 => "\r\n",
+            _ => "\r",
+        };
+        write!(
+            file,
+            "{i},\"line {i}{end}next \"\"{i}\"\"\",{}.5{end}",
+            i % 100
+        )?;
+    }
+    file.flush()?;
+
+    let read = |threads: &str| -> Result<_, Box<dyn std::error::Error>> {
+        let output = std::env::temp_dir().join(format!("csv2parquet-threads-{threads}.parquet"));
+        Command::cargo_bin("csv2parquet")?
+            .arg(&input)
+            .arg(&output)
+            .arg("--threads")
+            .arg(threads)
+            .assert()
+            .success();
+        let batches = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&output)?)?
+            .build()?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(arrow::compute::concat_batches(
+            &batches[0].schema(),
+            &batches,
+        )?)
+    };
+    let single = read("1")?;
+    assert_eq!(single.num_rows(), 300_000);
+    assert_eq!(read("4")?, single);
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn progress() -> Result<(), Box<dyn std::error::Error>> {
+    let output = std::env::temp_dir().join("csv2parquet-progress.parquet");
+    let mut cmd = Command::cargo_bin("csv2parquet")?;
+
+    let assert = cmd
+        .arg("../../data/simple.csv")
+        .arg(&output)
+        .arg("--progress=json")
+        .assert();
+    assert
+        .success()
+        .stderr(predicate::str::contains("\"event\":\"done\""))
+        .stderr(predicate::str::contains("\"rows\":3,"))
+        .stderr(predicate::str::contains("\"total_bytes\":28}"));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn report() -> Result<(), Box<dyn std::error::Error>> {
+    let output = std::env::temp_dir().join("csv2parquet-report.parquet");
+    let report = std::env::temp_dir().join("csv2parquet-report.json");
+    let mut cmd = Command::cargo_bin("csv2parquet")?;
+
+    let assert = cmd
+        .arg("../../data/simple.csv.gz")
+        .arg(&output)
+        .arg("--report")
+        .arg(&report)
+        .assert();
+    assert.success();
+
+    let report = std::fs::read_to_string(report)?;
+    let output_bytes = std::fs::metadata(&output)?.len();
+    for expected in [
+        "\"compression\": \"gzip\"".to_string(),
+        "\"schema_source\": \"inferred\"".to_string(),
+        "\"rows\": 3,".to_string(),
+        "\"row_groups\": 1,".to_string(),
+        "\"path\": \"a\",".to_string(),
+        format!("\"bytes\": {output_bytes},"),
+    ] {
+        assert!(report.contains(&expected), "{expected} not in {report}");
+    }
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn error_position() -> Result<(), Box<dyn std::error::Error>> {
+    let input = std::env::temp_dir().join("csv2parquet-error-position.csv");
+    let output = std::env::temp_dir().join("csv2parquet-error-position.parquet");
+    std::fs::write(&input, "a,b\n1,2\n\n3,4\n5,x\n")?;
+    let mut cmd = Command::cargo_bin("csv2parquet")?;
+
+    let assert = cmd
+        .arg(&input)
+        .arg(&output)
+        .arg("--max-read-records")
+        .arg("1")
+        .assert();
+    assert
+        .code(5)
+        .stderr(predicate::str::contains(
+            "line 5, record 2, column \"b\", byte 13",
+        ))
+        .stderr(predicate::str::contains(" 5 | 5,x"));
+
+    let mut cmd = Command::cargo_bin("csv2parquet")?;
+    let assert = cmd.arg("missing.csv").arg(&output).assert();
+    assert.code(3);
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn on_error() -> Result<(), Box<dyn std::error::Error>> {
+    let input = std::env::temp_dir().join("csv2parquet-on-error.csv");
+    let output = std::env::temp_dir().join("csv2parquet-on-error.parquet");
+    let rejects = std::env::temp_dir().join("csv2parquet-on-error.jsonl");
+    std::fs::write(&input, "a,b\n1,2\n3,x\n4,5,6\n7,8\n")?;
+    let mut cmd = Command::cargo_bin("csv2parquet")?;
+
+    let assert = cmd
+        .arg(&input)
+        .arg(&output)
+        .arg("--max-read-records")
+        .arg("1")
+        .arg("--on-error")
+        .arg("null")
+        .arg("--reject-file")
+        .arg(&rejects)
+        .assert();
+    assert
+        .success()
+        .stderr(predicate::str::contains(
+            "Skipped 1 records that could not be read",
+        ))
+        .stderr(predicate::str::contains(
+            "Set values that could not be read to null in 1 records",
+        ));
+    let rejected = std::fs::read_to_string(&rejects)?;
+    assert_eq!(rejected.lines().count(), 2);
+    assert!(rejected.contains(r#""action":"null","column":"b","data":"3,x""#));
+    assert!(rejected.contains(r#""line":4,"record":2"#));
+
+    let mut cmd = Command::cargo_bin("csv2parquet")?;
+    let assert = cmd
+        .arg(&input)
+        .arg(&output)
+        .arg("--max-read-records")
+        .arg("1")
+        .arg("--on-error")
+        .arg("skip")
+        .arg("--max-errors")
+        .arg("1")
+        .assert();
+    assert
+        .code(5)
+        .stderr(predicate::str::contains("more than the maximum of 1"))
+        .stderr(predicate::str::contains(" 4 | 4,5,6"));
+
+    // With an inferred schema, ragged rows are rejected instead of failing inference.
+    std::fs::write(&input, "a,b\n1,2\n4\n5,6,7\n8,9\n")?;
+    let mut cmd = Command::cargo_bin("csv2parquet")?;
+    let assert = cmd
+        .arg(&input)
+        .arg(&output)
+        .arg("--on-error")
+        .arg("skip")
+        .arg("--reject-file")
+        .arg(&rejects)
+        .assert();
+    assert.success().stderr(predicate::str::contains(
+        "Skipped 2 records that could not be read",
+    ));
+    let rejected = std::fs::read_to_string(&rejects)?;
+    assert_eq!(rejected.lines().count(), 2);
+    assert!(rejected.contains(r#""data":"5,6,7","error""#));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn validate() -> Result<(), Box<dyn std::error::Error>> {
+    let input = std::env::temp_dir().join("csv2parquet-validate.csv");
+    std::fs::write(&input, "a,b\n1,2\n3,x\n4,5,6\n7,8\n")?;
+    let mut cmd = Command::cargo_bin("csv2parquet")?;
+
+    let assert = cmd
+        .arg(&input)
+        .arg("--max-read-records")
+        .arg("1")
+        .arg("--validate")
+        .assert();
+    assert
+        .code(1)
+        .stderr(predicate::str::contains(
+            "line 3, record 1, column \"b\", byte 8",
+        ))
+        .stderr(predicate::str::contains(" 4 | 4,5,6"))
+        .stderr(predicate::str::contains("2 of 4 records are invalid"));
+
+    // Every ragged row is reported with an inferred schema too.
+    std::fs::write(&input, "a,b\n1,2\n4\n5,6,7\n8,9\n")?;
+    let mut cmd = Command::cargo_bin("csv2parquet")?;
+    let assert = cmd.arg(&input).arg("--validate").assert();
+    assert
+        .code(1)
+        .stderr(predicate::str::contains(" 3 | 4"))
+        .stderr(predicate::str::contains(" 4 | 5,6,7"))
+        .stderr(predicate::str::contains("2 of 4 records are invalid"));
+
+    let mut cmd = Command::cargo_bin("csv2parquet")?;
+    let assert = cmd.arg("../../data/simple.csv").arg("--validate").assert();
+    assert
+        .success()
+        .stderr(predicate::str::contains("All 3 records are valid"));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn schema_from() -> Result<(), Box<dyn std::error::Error>> {
+    let existing = std::env::temp_dir().join("csv2parquet-schema-from.parquet");
+    let output = std::env::temp_dir().join("csv2parquet-schema-from-new.parquet");
+    let schema = std::env::temp_dir().join("csv2parquet-schema-from.json");
+    std::fs::write(
+        &schema,
+        r#"{"fields":[{"name":"a","data_type":"Utf8","nullable":false,"dict_id":0,"dict_is_ordered":false,"metadata":{"unit":"seconds"}},{"name":"b","data_type":"Boolean","nullable":true,"dict_id":0,"dict_is_ordered":false,"metadata":{}}],"metadata":{}}"#,
+    )?;
+    let mut cmd = Command::cargo_bin("csv2parquet")?;
+    let assert = cmd
+        .arg("../../data/simple.csv")
+        .arg(&existing)
+        .arg("--schema-file")
+        .arg(&schema)
+        .assert();
+    assert.success();
+
+    let mut cmd = Command::cargo_bin("csv2parquet")?;
+    let assert = cmd
+        .arg("../../data/simple.csv")
+        .arg(&output)
+        .arg("--schema-from")
+        .arg(&existing)
+        .arg("--print-schema")
+        .assert();
+    assert
+        .success()
+        .stdout(predicate::str::contains(r#""data_type": "Utf8""#))
+        .stdout(predicate::str::contains(r#""unit": "seconds""#));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn memory_limit() -> Result<(), Box<dyn std::error::Error>> {
+    let output = std::env::temp_dir().join("csv2parquet-memory-limit.parquet");
+    let mut cmd = Command::cargo_bin("csv2parquet")?;
+
+    let assert = cmd
+        .arg("../../data/simple.csv.gz")
+        .arg(&output)
+        .arg("--memory-limit")
+        .arg("1M")
+        .assert();
+    assert.success();
+
+    let mut cmd = Command::cargo_bin("csv2parquet")?;
+    let assert = cmd
+        .arg("../../data/simple.csv")
+        .arg(&output)
+        .arg("--memory-limit")
+        .arg("lots")
+        .assert();
+    assert.failure().stderr(predicate::str::contains(
+        "invalid size \"lots\", expected e.g. 512M or 2G",
+    ));
+
+    Ok(())
+}
+
 #[test]
 #[allow(deprecated)]
 fn help() -> Result<(), Box<dyn std::error::Error>> {
@@ -82,9 +382,9 @@ fn help() -> Result<(), Box<dyn std::error::Error>> {
     assert
         .success()
         .stdout(predicate::str::contains(if cfg!(windows) {
-            "Usage: csv2parquet.exe [OPTIONS] <CSV> <PARQUET>"
+            "Usage: csv2parquet.exe [OPTIONS] <CSV> [PARQUET]"
         } else {
-            "Usage: csv2parquet [OPTIONS] <CSV> <PARQUET>"
+            "Usage: csv2parquet [OPTIONS] <CSV> [PARQUET]"
         }));
 
     Ok(())
@@ -0,0 +1,149 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+#[allow(deprecated)]
+fn get_schema() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("fwf2arrow")?;
+
+    let assert = cmd
+        .arg("../../data/simple.fwf")
+        .arg("--layout")
+        .arg("../../data/simple.layout")
+        .arg("-n")
+        .assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#""fields": [
+    {
+      "name": "a",
+      "data_type": "Int64",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },
+    {
+      "name": "b",
+      "data_type": "Boolean",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },
+    {
+      "name": "amount",
+      "data_type": "Float64",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },
+    {
+      "name": "name",
+      "data_type": "Utf8",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    }
+  ]"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn get_schema_gz() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("fwf2arrow")?;
+
+    let assert = cmd
+        .arg("../../data/simple.fwf.gz")
+        .arg("--layout")
+        .arg("../../data/simple.layout")
+        .arg("-n")
+        .assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#""fields": [
+    {
+      "name": "a",
+      "data_type": "Int64",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },
+    {
+      "name": "b",
+      "data_type": "Boolean",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },
+    {
+      "name": "amount",
+      "data_type": "Float64",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },
+    {
+      "name": "name",
+      "data_type": "Utf8",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    }
+  ]"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn get_schema_columns() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("fwf2arrow")?;
+
+    let assert = cmd
+        .arg("../../data/simple.fwf")
+        .arg("--column")
+        .arg("amount:11:8:Decimal128(9, 2):2")
+        .arg("-n")
+        .assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#""data_type": {
+        "Decimal128": [
+          9,
+          2
+        ]
+      }"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn help() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("fwf2arrow")?;
+
+    let assert = cmd.arg("--help").assert();
+
+    assert
+        .success()
+        .stdout(predicate::str::contains(if cfg!(windows) {
+            "Usage: fwf2arrow.exe [OPTIONS] <FWF> [ARROW]"
+        } else {
+            "Usage: fwf2arrow [OPTIONS] <FWF> [ARROW]"
+        }));
+
+    Ok(())
+}
@@ -82,10 +82,47 @@ fn help() -> Result<(), Box<dyn std::error::Error>> {
     assert
         .success()
         .stdout(predicate::str::contains(if cfg!(windows) {
-            "Usage: json2parquet.exe [OPTIONS] <JSON> <PARQUET>"
+            "Usage: json2parquet.exe [OPTIONS] <JSON> [PARQUET]"
         } else {
-            "Usage: json2parquet [OPTIONS] <JSON> <PARQUET>"
+            "Usage: json2parquet [OPTIONS] <JSON> [PARQUET]"
         }));
 
     Ok(())
 }
+
+#[test]
+#[allow(deprecated)]
+fn on_error() -> Result<(), Box<dyn std::error::Error>> {
+    let input = std::env::temp_dir().join("json2parquet-on-error.json");
+    let output = std::env::temp_dir().join("json2parquet-on-error.parquet");
+    let rejects = std::env::temp_dir().join("json2parquet-on-error.jsonl");
+    std::fs::write(
+        &input,
+        "{\"a\":1,\"b\":true}\n{\"a\":\"x\",\"b\":false}\n{\"a\":\n",
+    )?;
+    let mut cmd = Command::cargo_bin("json2parquet")?;
+
+    let assert = cmd
+        .arg(&input)
+        .arg(&output)
+        .arg("--max-read-records")
+        .arg("1")
+        .arg("--on-error")
+        .arg("null")
+        .arg("--reject-file")
+        .arg(&rejects)
+        .assert();
+    assert
+        .success()
+        .stderr(predicate::str::contains(
+            "Skipped 1 records that could not be read",
+        ))
+        .stderr(predicate::str::contains(
+            "Set values that could not be read to null in 1 records",
+        ));
+    let rejected = std::fs::read_to_string(&rejects)?;
+    assert!(rejected.contains(r#""action":"null""#));
+    assert!(rejected.contains(r#""line":3,"record":2"#));
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+#[allow(deprecated)]
+fn get_schema() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("log2arrow")?;
+
+    let assert = cmd.arg("../../data/simple.log").arg("-n").assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#"    {
+      "name": "status",
+      "data_type": "Int64",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },
+    {
+      "name": "size",
+      "data_type": "Int64",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn get_schema_gz() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("log2arrow")?;
+
+    let assert = cmd.arg("../../data/simple.log.gz").arg("-n").assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#"    {
+      "name": "status",
+      "data_type": "Int64",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn get_schema_regex() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("log2arrow")?;
+
+    let assert = cmd
+        .arg("../../data/simple.syslog")
+        .arg("--regex")
+        .arg(r"^(?:<\d+>)?\w{3} [ \d]\d [\d:]{8} (?P<host>\S+) (?P<program>[^\[:]+)(?:\[(?P<pid>\d+)\])?:")
+        .arg("-n")
+        .assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#""fields": [
+    {
+      "name": "host",
+      "data_type": "Utf8",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },
+    {
+      "name": "program",
+      "data_type": "Utf8",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },
+    {
+      "name": "pid",
+      "data_type": "Int64",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    }
+  ]"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn help() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("log2arrow")?;
+
+    let assert = cmd.arg("--help").assert();
+
+    assert
+        .success()
+        .stdout(predicate::str::contains(if cfg!(windows) {
+            "Usage: log2arrow.exe [OPTIONS] <LOG> [ARROW]"
+        } else {
+            "Usage: log2arrow [OPTIONS] <LOG> [ARROW]"
+        }));
+
+    Ok(())
+}
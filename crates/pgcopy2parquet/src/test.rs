@@ -0,0 +1,123 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+#[allow(deprecated)]
+fn get_schema() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("pgcopy2parquet")?;
+
+    let assert = cmd
+        .arg("../../data/simple.copy")
+        .arg("out.parquet")
+        .arg("-n")
+        .assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#"    {
+      "name": "column_4",
+      "data_type": "Float64",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn get_schema_gz() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("pgcopy2parquet")?;
+
+    let assert = cmd
+        .arg("../../data/simple.copy.gz")
+        .arg("out.parquet")
+        .arg("-n")
+        .assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#"    {
+      "name": "column_4",
+      "data_type": "Float64",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn get_schema_binary() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("pgcopy2parquet")?;
+
+    let assert = cmd
+        .arg("../../data/simple.pgcopy")
+        .arg("--column")
+        .arg("id:int8")
+        .arg("--column")
+        .arg("price:numeric(10,2)")
+        .arg("out.parquet")
+        .arg("-n")
+        .assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#"    {
+      "name": "price",
+      "data_type": {
+        "Decimal128": [
+          10,
+          2
+        ]
+      },
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    }"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn binary_without_types() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("pgcopy2parquet")?;
+
+    let assert = cmd
+        .arg("../../data/simple.pgcopy.gz")
+        .arg("out.parquet")
+        .arg("-n")
+        .assert();
+
+    assert.failure().stderr(predicate::str::contains(
+        "The binary format needs the column types",
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn help() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("pgcopy2parquet")?;
+
+    let assert = cmd.arg("--help").assert();
+
+    assert
+        .success()
+        .stdout(predicate::str::contains(if cfg!(windows) {
+            "Usage: pgcopy2parquet.exe [OPTIONS] <COPY> [PARQUET]"
+        } else {
+            "Usage: pgcopy2parquet [OPTIONS] <COPY> [PARQUET]"
+        }));
+
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+#[allow(deprecated)]
+fn get_schema() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("proto2arrow")?;
+
+    let assert = cmd
+        .arg("../../data/simple.pb")
+        .arg("--descriptor-set")
+        .arg("../../data/simple.desc")
+        .arg("--message")
+        .arg("example.Event")
+        .arg("-n")
+        .assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#"    {
+      "name": "created_at",
+      "data_type": {
+        "Timestamp": [
+          "Nanosecond",
+          "+00:00"
+        ]
+      },
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn convert_gz() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("proto2arrow")?;
+
+    let assert = cmd
+        .arg("../../data/simple.pb.gz")
+        .arg("--descriptor-set")
+        .arg("../../data/simple.desc")
+        .arg("--message")
+        .arg("example.Event")
+        .assert();
+
+    assert.success();
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn unknown_message() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("proto2arrow")?;
+
+    let assert = cmd
+        .arg("../../data/simple.pb")
+        .arg("--descriptor-set")
+        .arg("../../data/simple.desc")
+        .arg("--message")
+        .arg("example.Missing")
+        .arg("-n")
+        .assert();
+
+    assert.failure().stderr(predicate::str::contains(
+        r#"Message "example.Missing" not found"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn help() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("proto2arrow")?;
+
+    let assert = cmd.arg("--help").assert();
+
+    assert
+        .success()
+        .stdout(predicate::str::contains(if cfg!(windows) {
+            "Usage: proto2arrow.exe [OPTIONS] --descriptor-set <DESCRIPTOR_SET> --message <MESSAGE> <PROTOBUF> [ARROW]"
+        } else {
+            "Usage: proto2arrow [OPTIONS] --descriptor-set <DESCRIPTOR_SET> --message <MESSAGE> <PROTOBUF> [ARROW]"
+        }));
+
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+#[allow(deprecated)]
+fn get_schema() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("sqlite2parquet")?;
+
+    let assert = cmd
+        .arg("../../data/simple.sqlite")
+        .arg("--table")
+        .arg("simple")
+        .arg("-n")
+        .arg("out.parquet")
+        .assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#""fields": [
+    {
+      "name": "a",
+      "data_type": "Int64",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },
+    {
+      "name": "b",
+      "data_type": "Boolean",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    }
+  ]"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn get_schema_query() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("sqlite2parquet")?;
+
+    let assert = cmd
+        .arg("../../data/simple.sqlite")
+        .arg("--query")
+        .arg("SELECT device, payload, note FROM readings")
+        .arg("-n")
+        .arg("out.parquet")
+        .assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#""fields": [
+    {
+      "name": "device",
+      "data_type": "Utf8",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },
+    {
+      "name": "payload",
+      "data_type": "Binary",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },
+    {
+      "name": "note",
+      "data_type": "Float64",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    }
+  ]"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn help() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("sqlite2parquet")?;
+
+    let assert = cmd.arg("--help").assert();
+
+    assert
+        .success()
+        .stdout(predicate::str::contains(if cfg!(windows) {
+            "Usage: sqlite2parquet.exe [OPTIONS] <--table <TABLE>|--query <QUERY>> <SQLITE> [PARQUET]"
+        } else {
+            "Usage: sqlite2parquet [OPTIONS] <--table <TABLE>|--query <QUERY>> <SQLITE> [PARQUET]"
+        }));
+
+    Ok(())
+}
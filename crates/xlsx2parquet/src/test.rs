@@ -0,0 +1,116 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+#[allow(deprecated)]
+fn get_schema() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("xlsx2parquet")?;
+
+    let assert = cmd
+        .arg("../../data/simple.xlsx")
+        .arg("-n")
+        .arg("out.parquet")
+        .assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#""fields": [
+    {
+      "name": "a",
+      "data_type": "Int64",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },
+    {
+      "name": "b",
+      "data_type": "Boolean",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },
+    {
+      "name": "c",
+      "data_type": "Date32",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },
+    {
+      "name": "d",
+      "data_type": {
+        "Timestamp": [
+          "Millisecond",
+          null
+        ]
+      },
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    }
+  ]"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn get_schema_range() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("xlsx2parquet")?;
+
+    let assert = cmd
+        .arg("../../data/simple.xlsx")
+        .arg("--sheet")
+        .arg("offset")
+        .arg("--range")
+        .arg("B3:C5")
+        .arg("-n")
+        .arg("out.parquet")
+        .assert();
+
+    assert.success().stdout(predicate::str::contains(
+        r#""fields": [
+    {
+      "name": "name",
+      "data_type": "Utf8",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    },
+    {
+      "name": "value",
+      "data_type": "Float64",
+      "nullable": true,
+      "dict_id": 0,
+      "dict_is_ordered": false,
+      "metadata": {}
+    }
+  ]"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated)]
+fn help() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("xlsx2parquet")?;
+
+    let assert = cmd.arg("--help").assert();
+
+    assert
+        .success()
+        .stdout(predicate::str::contains(if cfg!(windows) {
+            "Usage: xlsx2parquet.exe [OPTIONS] <XLSX> [PARQUET]"
+        } else {
+            "Usage: xlsx2parquet [OPTIONS] <XLSX> [PARQUET]"
+        }));
+
+    Ok(())
+}